@@ -0,0 +1,86 @@
+use anyhow::{Context, Result, anyhow};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// A named file a ruleset produced besides its diagnostics, e.g. a
+/// dependency graph, as sent in an `artifact` event:
+///
+/// ```json
+/// { "name": "deps.svg", "mediaType": "image/svg+xml", "content": "<svg ...", "encoding": "text" }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Artifact {
+    /// Relative path under the ruleset's artifact directory, `/`-separated
+    pub name: String,
+    #[serde(default)]
+    pub media_type: Option<String>,
+    pub content: String,
+    /// `text` (the default) or `base64`
+    #[serde(default)]
+    pub encoding: Option<String>,
+}
+
+/// An artifact as written out, listed in reports
+#[derive(Debug, Clone)]
+pub struct WrittenArtifact {
+    pub ruleset: String,
+    pub name: String,
+    pub path: PathBuf,
+    pub media_type: Option<String>,
+    pub bytes: usize,
+}
+
+impl Artifact {
+    /// The decoded content
+    fn bytes(&self) -> Result<Vec<u8>> {
+        match self.encoding.as_deref() {
+            None | Some("text") => Ok(self.content.clone().into_bytes()),
+            Some("base64") => base64::engine::general_purpose::STANDARD
+                .decode(&self.content)
+                .map_err(|e| anyhow!("invalid base64 content ({})", e)),
+            Some(other) => Err(anyhow!(
+                "unknown encoding '{}', expected text or base64",
+                other
+            )),
+        }
+    }
+
+    /// Where the artifact goes under `dir`, refusing names that would
+    /// leave the ruleset's own directory
+    fn path(&self, dir: &Path, ruleset: &str) -> Result<PathBuf> {
+        let name = Path::new(&self.name);
+        let plain = name
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+        if self.name.is_empty() || !plain {
+            return Err(anyhow!(
+                "artifact name '{}' must be a relative path without '..'",
+                self.name
+            ));
+        }
+        Ok(dir.join(ruleset).join(name))
+    }
+
+    /// Write the artifact to `<dir>/<ruleset>/<name>`, replacing any
+    /// earlier one of the same name
+    pub fn write(&self, dir: &Path, ruleset: &str) -> Result<WrittenArtifact> {
+        let path = self.path(dir, ruleset)?;
+        let bytes = self.bytes()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        fs::write(&path, &bytes)
+            .with_context(|| format!("Failed to write artifact {}", path.display()))?;
+        Ok(WrittenArtifact {
+            ruleset: ruleset.to_string(),
+            name: self.name.clone(),
+            path,
+            media_type: self.media_type.clone(),
+            bytes: bytes.len(),
+        })
+    }
+}
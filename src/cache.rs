@@ -0,0 +1,373 @@
+use crate::artifacts::Artifact;
+use crate::paths;
+use crate::state::ProjectState;
+use anyhow::{Context, Result};
+use forseti_sdk::core::Diagnostic;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Bumped whenever entries or what goes into their keys change, so older
+/// entries are never read back
+const CACHE_VERSION: &str = "2";
+
+/// What a ruleset reported for one file, as stored in the cache
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CachedResult {
+    pub diagnostics: Vec<Diagnostic>,
+    #[serde(default)]
+    pub metrics: BTreeMap<String, f64>,
+    #[serde(default)]
+    pub artifacts: Vec<Artifact>,
+    pub version: Option<String>,
+}
+
+/// Lint results of unchanged files, one JSON file per entry under
+/// `.forseti/cache/results/`
+///
+/// An entry is keyed by everything that can change a ruleset's answer: the
+/// ruleset, the installed binary or image, its config, the file's path and
+/// its content. Anything else invalidates it, so entries are never updated,
+/// only written once and read back.
+pub struct ResultCache {
+    dir: PathBuf,
+}
+
+impl ResultCache {
+    /// Open the cache of a project, creating its directory if needed
+    pub fn open(state: &ProjectState) -> Result<Self> {
+        let dir = state.results_cache();
+        {
+            // Creates the state directory and its .gitignore on first use
+            let _lock = state.lock()?;
+            fs::create_dir_all(&dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        Ok(Self { dir })
+    }
+
+    /// Key of the entry for `content` at `path`, analyzed by the ruleset
+    /// `ruleset_id` installed as `ruleset_identity` with `config`
+    pub fn key(
+        ruleset_id: &str,
+        ruleset_identity: &str,
+        config: &toml::value::Table,
+        path: &str,
+        content: &str,
+    ) -> String {
+        let config = serde_json::to_string(config).unwrap_or_default();
+        digest(&[
+            CACHE_VERSION,
+            ruleset_id,
+            ruleset_identity,
+            &config,
+            path,
+            content,
+        ])
+    }
+
+    /// The entry for `key`; unreadable entries count as missing
+    pub fn get(&self, key: &str) -> Option<CachedResult> {
+        let text = fs::read_to_string(self.entry(key)).ok()?;
+        serde_json::from_str(&text).ok()
+    }
+
+    pub fn put(&self, key: &str, result: &CachedResult) -> Result<()> {
+        let path = self.entry(key);
+        paths::write_atomic(&path, &serde_json::to_vec(result)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    fn entry(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+}
+
+/// Files and results of the previous `forseti lint --incremental` run, in
+/// `.forseti/cache/incremental.json`
+///
+/// Files whose modification time and size, or failing that content, match
+/// their entry get the stored results of every ruleset whose binary and
+/// config are unchanged, without starting it.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct IncrementalState {
+    version: String,
+    /// Workspace-relative path → what the run linted there
+    files: BTreeMap<String, IncrementalFile>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct IncrementalFile {
+    stamp: Option<FileStamp>,
+    /// Digest of the content the results are for
+    hash: String,
+    /// Ruleset id → its results
+    results: BTreeMap<String, IncrementalResult>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct IncrementalResult {
+    /// [`IncrementalState::ruleset_key`] of the ruleset that produced it
+    ruleset: String,
+    #[serde(flatten)]
+    result: CachedResult,
+}
+
+/// Cheap check for whether a file changed since it was last linted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileStamp {
+    /// Nanoseconds since the Unix epoch
+    modified: u128,
+    size: u64,
+}
+
+impl FileStamp {
+    /// Stamp of the file at `path`, if the platform reports modification times
+    pub fn of(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(Self {
+            modified: modified.as_nanos(),
+            size: metadata.len(),
+        })
+    }
+}
+
+impl IncrementalState {
+    /// The previous run's state; empty when there was none or it was written
+    /// by another version
+    pub fn load(state: &ProjectState) -> Self {
+        fs::read_to_string(state.incremental_state())
+            .ok()
+            .and_then(|text| serde_json::from_str::<Self>(&text).ok())
+            .filter(|loaded| loaded.version == CACHE_VERSION)
+            .unwrap_or_default()
+    }
+
+    /// Replace the state on disk, dropping entries for deleted files
+    pub fn save(mut self, state: &ProjectState, workspace_root: &Path) -> Result<()> {
+        self.version = CACHE_VERSION.to_string();
+        self.files
+            .retain(|path, _| workspace_root.join(path).is_file());
+        let path = state.incremental_state();
+        let _lock = state.lock()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        paths::write_atomic(&path, &serde_json::to_vec(&self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// What a ruleset's results depend on besides the file: its installed
+    /// binary or image and its config
+    pub fn ruleset_key(ruleset_identity: &str, config: &toml::value::Table) -> String {
+        let config = serde_json::to_string(config).unwrap_or_default();
+        digest(&[ruleset_identity, &config])
+    }
+
+    /// The entry for `path` when the file is unchanged since it was recorded
+    pub fn unchanged(
+        &self,
+        path: &str,
+        stamp: Option<FileStamp>,
+        content: &str,
+    ) -> Option<&IncrementalFile> {
+        let file = self.files.get(path)?;
+        let same = (stamp.is_some() && stamp == file.stamp) || digest(&[content]) == file.hash;
+        same.then_some(file)
+    }
+
+    /// Record what was linted at `path`, replacing the previous entry
+    pub fn record(&mut self, path: String, file: IncrementalFile) {
+        self.files.insert(path, file);
+    }
+
+    /// Forget `path`, so the next run lints it again
+    pub fn forget(&mut self, path: &str) {
+        self.files.remove(path);
+    }
+}
+
+impl IncrementalFile {
+    pub fn new(stamp: Option<FileStamp>, content: &str) -> Self {
+        Self {
+            stamp,
+            hash: digest(&[content]),
+            results: BTreeMap::new(),
+        }
+    }
+
+    /// The results of `ruleset_id` when it's still the ruleset with
+    /// `ruleset_key`
+    pub fn result(&self, ruleset_id: &str, ruleset_key: &str) -> Option<&CachedResult> {
+        self.results
+            .get(ruleset_id)
+            .filter(|stored| stored.ruleset == ruleset_key)
+            .map(|stored| &stored.result)
+    }
+
+    pub fn insert(&mut self, ruleset_id: &str, ruleset_key: String, result: CachedResult) {
+        self.results.insert(
+            ruleset_id.to_string(),
+            IncrementalResult {
+                ruleset: ruleset_key,
+                result,
+            },
+        );
+    }
+}
+
+/// Hex SHA-256 of `parts`
+fn digest(parts: &[&str]) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part.as_bytes());
+        // Separator so ("ab", "c") and ("a", "bc") differ
+        hasher.update([0]);
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn config(text: &str) -> toml::value::Table {
+        toml::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn every_input_changes_the_key() {
+        let base = config("todo = \"warn\"");
+        let key = ResultCache::key("base", "/bin/base:1", &base, "src/a.rs", "TODO\n");
+
+        assert_eq!(
+            key,
+            ResultCache::key("base", "/bin/base:1", &base, "src/a.rs", "TODO\n")
+        );
+        for other in [
+            ResultCache::key("other", "/bin/base:1", &base, "src/a.rs", "TODO\n"),
+            ResultCache::key("base", "/bin/base:2", &base, "src/a.rs", "TODO\n"),
+            ResultCache::key(
+                "base",
+                "/bin/base:1",
+                &config("todo = \"error\""),
+                "src/a.rs",
+                "TODO\n",
+            ),
+            ResultCache::key("base", "/bin/base:1", &base, "src/b.rs", "TODO\n"),
+            ResultCache::key("base", "/bin/base:1", &base, "src/a.rs", "TODO \n"),
+        ] {
+            assert_ne!(key, other);
+        }
+        // Parts are separated, so shifting text between them is a change too
+        assert_ne!(
+            ResultCache::key("ab", "c", &base, "p", "x"),
+            ResultCache::key("a", "bc", &base, "p", "x")
+        );
+    }
+
+    #[test]
+    fn entries_read_back_and_unreadable_ones_are_missing() {
+        let dir = env::temp_dir().join(format!("forseti-cache-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let cache = ResultCache { dir: dir.clone() };
+        let result = CachedResult {
+            diagnostics: Vec::new(),
+            metrics: BTreeMap::from([("rules".to_string(), 3.0)]),
+            artifacts: Vec::new(),
+            version: Some("1.2.0".to_string()),
+        };
+        cache.put("good", &result).unwrap();
+        fs::write(cache.entry("bad"), "{").unwrap();
+
+        let read = cache.get("good").unwrap();
+        assert_eq!(read.metrics["rules"], 3.0);
+        assert_eq!(read.version.as_deref(), Some("1.2.0"));
+        assert!(cache.get("bad").is_none());
+        assert!(cache.get("missing").is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn stamp(modified: u128, size: u64) -> Option<FileStamp> {
+        Some(FileStamp { modified, size })
+    }
+
+    #[test]
+    fn files_are_unchanged_by_stamp_or_else_by_content() {
+        let mut state = IncrementalState::default();
+        state.record(
+            "a.rs".to_string(),
+            IncrementalFile::new(stamp(1, 5), "TODO\n"),
+        );
+
+        // A matching stamp is trusted without looking at the content
+        assert!(state.unchanged("a.rs", stamp(1, 5), "").is_some());
+        // A touched file with the same content is still unchanged
+        assert!(state.unchanged("a.rs", stamp(2, 5), "TODO\n").is_some());
+        assert!(state.unchanged("a.rs", None, "TODO\n").is_some());
+        assert!(state.unchanged("a.rs", stamp(2, 5), "DONE\n").is_none());
+        assert!(state.unchanged("b.rs", stamp(1, 5), "TODO\n").is_none());
+        state.forget("a.rs");
+        assert!(state.unchanged("a.rs", stamp(1, 5), "TODO\n").is_none());
+    }
+
+    #[test]
+    fn results_are_reused_only_from_the_same_ruleset() {
+        let base = config("todo = \"warn\"");
+        let key = IncrementalState::ruleset_key("/bin/base:1", &base);
+        let mut file = IncrementalFile::new(None, "TODO\n");
+        file.insert(
+            "base",
+            key.clone(),
+            CachedResult {
+                diagnostics: Vec::new(),
+                metrics: BTreeMap::new(),
+                artifacts: Vec::new(),
+                version: None,
+            },
+        );
+
+        assert!(file.result("base", &key).is_some());
+        assert!(file.result("other", &key).is_none());
+        let upgraded = IncrementalState::ruleset_key("/bin/base:2", &base);
+        assert!(file.result("base", &upgraded).is_none());
+        let reconfigured = IncrementalState::ruleset_key("/bin/base:1", &config("todo = \"off\""));
+        assert!(file.result("base", &reconfigured).is_none());
+    }
+
+    #[test]
+    fn state_from_another_version_is_dropped() {
+        let root = env::temp_dir().join(format!("forseti-incremental-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("a.rs"), "TODO\n").unwrap();
+        let project = ProjectState::new(&root);
+        let mut state = IncrementalState::default();
+        state.record("a.rs".to_string(), IncrementalFile::new(None, "TODO\n"));
+        state.record("deleted.rs".to_string(), IncrementalFile::new(None, ""));
+        state.save(&project, &root).unwrap();
+
+        let loaded = IncrementalState::load(&project);
+        assert!(loaded.unchanged("a.rs", None, "TODO\n").is_some());
+        assert!(loaded.unchanged("deleted.rs", None, "").is_none());
+        let path = project.incremental_state();
+        let text = fs::read_to_string(&path).unwrap();
+        let older = text.replace(
+            &format!("\"version\":\"{}\"", CACHE_VERSION),
+            "\"version\":\"0\"",
+        );
+        assert_ne!(text, older);
+        fs::write(&path, older).unwrap();
+        assert!(
+            IncrementalState::load(&project)
+                .unchanged("a.rs", None, "TODO\n")
+                .is_none()
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
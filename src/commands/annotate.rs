@@ -0,0 +1,103 @@
+use crate::commands::lint::{Workspace, collect_files, lint_files};
+use crate::context::GlobalContext;
+use crate::languages;
+use crate::line_index::LineIndex;
+use crate::rules;
+use crate::suppressions::NEXT_LINE_DIRECTIVE;
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Insert a suppression comment above every current violation of `rule`
+pub fn run(ctx: &GlobalContext, rule: &str, path: &Path, justification: &str) -> Result<()> {
+    ctx.log_verbose(&format!(
+        "Annotating violations of {} in: {}",
+        rule,
+        path.display()
+    ));
+    let workspace = Workspace::load(ctx, path)?;
+    let files = collect_files(&path.to_path_buf(), true, true)?;
+    let run = lint_files(
+        ctx,
+        &workspace,
+        files,
+        workspace.jobs(None),
+        true,
+        false,
+        &mut |_| Ok(()),
+    )?;
+
+    // Zero-based lines to annotate, per file
+    let mut targets: BTreeMap<PathBuf, BTreeSet<usize>> = BTreeMap::new();
+    for result in &run.file_results {
+        for finding in &result.findings {
+            let id = rules::qualify(&result.ruleset_id, &finding.diagnostic.rule_id);
+            if rule == id || rule == rules::short(&id) {
+                targets
+                    .entry(result.path.clone())
+                    .or_default()
+                    .insert(finding.diagnostic.range.start.line as usize);
+            }
+        }
+    }
+
+    if targets.is_empty() {
+        println!("No violations of {} found", rule);
+        return Ok(());
+    }
+
+    let mut annotated = 0;
+    let mut annotated_files = 0;
+    for (file, lines) in &targets {
+        let Some((open, close)) = languages::detect(file).and_then(|l| l.comment) else {
+            eprintln!(
+                "Skipping {}: no known comment syntax for this file type",
+                file.display()
+            );
+            continue;
+        };
+
+        let content = fs::read_to_string(file)
+            .with_context(|| format!("Failed to read file: {}", file.display()))?;
+        let index = LineIndex::new(&content);
+
+        // Rebuild the file line by line so every line keeps its own ending,
+        // including in files mixing `\n` and `\r\n`
+        let mut annotated_content = String::with_capacity(content.len());
+        let mut comments = 0;
+        for line in 0..index.line_count() {
+            let text = index.line(line).unwrap_or_default();
+            if lines.contains(&line) {
+                let indent: String = text.chars().take_while(|c| c.is_whitespace()).collect();
+                let ending = match index.ending(line) {
+                    "" => index.dominant_ending(),
+                    ending => ending,
+                };
+                annotated_content.push_str(&format!(
+                    "{}{} {} {} -- {}{}{}",
+                    indent, open, NEXT_LINE_DIRECTIVE, rule, justification, close, ending
+                ));
+                comments += 1;
+            }
+            annotated_content.push_str(text);
+            annotated_content.push_str(index.ending(line));
+        }
+
+        fs::write(file, annotated_content)
+            .with_context(|| format!("Failed to write file: {}", file.display()))?;
+        ctx.log_verbose(&format!(
+            "Inserted {} suppression(s) into {}",
+            comments,
+            file.display()
+        ));
+        annotated += comments;
+        annotated_files += 1;
+    }
+
+    println!(
+        "Inserted {} suppression comment(s) for {} across {} file(s)",
+        annotated, rule, annotated_files
+    );
+    Ok(())
+}
@@ -0,0 +1,104 @@
+use crate::commands::lint::{Workspace, collect_files, lint_files};
+use crate::context::GlobalContext;
+use crate::rules;
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Lint `path` and report which configured rules fired, which are enabled
+/// but never fired, and which rules the rulesets offer that the config
+/// doesn't mention
+pub fn run(ctx: &GlobalContext, path: &Path) -> Result<()> {
+    ctx.log_verbose(&format!("Measuring rule coverage in: {}", path.display()));
+    let workspace = Workspace::load(ctx, path)?;
+    let files = collect_files(&path.to_path_buf(), true, true)?;
+    let file_count = files.len();
+    let run = lint_files(
+        ctx,
+        &workspace,
+        files,
+        workspace.jobs(None),
+        true,
+        false,
+        &mut |_| Ok(()),
+    )?;
+
+    let mut fired: BTreeMap<String, usize> = BTreeMap::new();
+    for result in &run.file_results {
+        for finding in &result.findings {
+            let id = rules::qualify(&result.ruleset_id, &finding.diagnostic.rule_id);
+            *fired.entry(id).or_default() += 1;
+        }
+    }
+
+    // Canonical id → configured severity, across enabled rulesets
+    let mut configured: BTreeMap<String, String> = BTreeMap::new();
+    for (ruleset_id, ruleset_cfg) in &workspace.config.ruleset {
+        if !ruleset_cfg.enabled {
+            continue;
+        }
+        for (rule, severity) in rules::configured(&ruleset_cfg.config) {
+            configured.insert(rules::qualify(ruleset_id, &rule), severity);
+        }
+    }
+
+    println!("Rule coverage across {} file(s)", file_count);
+
+    println!();
+    println!("Fired:");
+    if fired.is_empty() {
+        println!("  (none)");
+    }
+    for (id, count) in &fired {
+        if configured.contains_key(id) {
+            println!("  {} ({})", id, count);
+        } else {
+            println!("  {} ({}, ruleset default)", id, count);
+        }
+    }
+
+    println!();
+    println!("Enabled but never fired:");
+    let silent: Vec<&String> = configured
+        .iter()
+        .filter(|(id, severity)| *severity != "off" && !fired.contains_key(*id))
+        .map(|(id, _)| id)
+        .collect();
+    if silent.is_empty() {
+        println!("  (none)");
+    }
+    for id in silent {
+        println!("  {}", id);
+    }
+
+    println!();
+    println!("Available but not configured:");
+    let mut unlisted = Vec::new();
+    let mut unconfigured = Vec::new();
+    for (ruleset_id, available) in workspace.available_rules() {
+        let Some(available) = available else {
+            unlisted.push(ruleset_id);
+            continue;
+        };
+        for rule in available {
+            let id = rules::qualify(&ruleset_id, &rule);
+            if !configured.contains_key(&id) {
+                unconfigured.push(id);
+            }
+        }
+    }
+    unconfigured.sort();
+    if unconfigured.is_empty() {
+        println!("  (none)");
+    }
+    for id in &unconfigured {
+        println!("  {}", id);
+    }
+    if !unlisted.is_empty() {
+        println!(
+            "  Not known for {}, which don't list their rules",
+            unlisted.join(", ")
+        );
+    }
+    Ok(())
+}
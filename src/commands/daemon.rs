@@ -0,0 +1,465 @@
+#[cfg(unix)]
+use crate::cache::FileStamp;
+use crate::cache::IncrementalState;
+use crate::commands::lint::Analysis;
+#[cfg(unix)]
+use crate::commands::lint::{self, SessionPool, Workspace};
+use crate::context::GlobalContext;
+use crate::fingerprint;
+use crate::state::ProjectState;
+#[cfg(unix)]
+use anyhow::Context;
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+#[cfg(unix)]
+use std::collections::HashMap;
+use std::fmt;
+#[cfg(unix)]
+use std::io::{BufRead, BufReader, Write};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+#[cfg(unix)]
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// Set to a non-empty value to keep lint runs from handing files to a daemon
+pub const NO_DAEMON_ENV: &str = "FORSETI_NO_DAEMON";
+
+/// How long the daemon waits for a connected client to send its request
+#[cfg(unix)]
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What a client asks for, one JSON line per connection
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum Request {
+    /// Analyze files with a ruleset; `key` is what the client expects it to
+    /// be (see [`IncrementalState::ruleset_key`]), so a daemon running
+    /// another config or binary refuses instead of answering differently
+    Analyze {
+        ruleset: String,
+        key: String,
+        files: Vec<RequestFile>,
+    },
+    /// The hash of the config the daemon serves, reloaded first when the
+    /// file changed on disk
+    Status,
+    Stop,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RequestFile {
+    uri: String,
+    content: String,
+}
+
+/// The daemon's answer, one JSON line
+#[derive(Default, Serialize, Deserialize)]
+struct Response {
+    /// Set when the request as a whole failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    /// Answering [`Request::Status`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    config_hash: Option<String>,
+    /// One per requested file, in order
+    #[serde(default)]
+    analyses: Vec<Outcome>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Outcome {
+    seconds: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    analysis: Option<Analysis>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// A daemon serving a workspace, which lint runs hand their files to
+pub(crate) struct Client {
+    socket: PathBuf,
+    /// Set once the daemon failed a request; the rest of the run doesn't
+    /// wait on it again
+    failed: AtomicBool,
+}
+
+impl fmt::Display for Client {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.socket.display())
+    }
+}
+
+impl Client {
+    /// The daemon serving the workspace at `root`, when one is listening,
+    /// serves the config hashing to `config_hash` and `FORSETI_NO_DAEMON`
+    /// doesn't rule it out
+    pub(crate) fn find(root: &Path, config_hash: &str) -> Option<Self> {
+        if std::env::var_os(NO_DAEMON_ENV).is_some_and(|value| !value.is_empty()) {
+            return None;
+        }
+        let client = Self {
+            socket: ProjectState::new(root).daemon_socket(),
+            failed: AtomicBool::new(false),
+        };
+        // A daemon serving another config would answer differently than
+        // analyzing files in this process
+        let served = client.send(&Request::Status).ok()?.config_hash;
+        (served.as_deref() == Some(config_hash)).then_some(client)
+    }
+
+    #[cfg(unix)]
+    fn listening(&self) -> bool {
+        UnixStream::connect(&self.socket).is_ok()
+    }
+
+    #[cfg(not(unix))]
+    fn listening(&self) -> bool {
+        false
+    }
+
+    /// Analyze `files` (uri and content) with the daemon's sessions of a
+    /// ruleset, as [`lint::run_ruleset`] would; `None` when the daemon can't,
+    /// e.g. because it runs another config or version of the ruleset
+    pub(crate) fn analyze(
+        &self,
+        ruleset_id: &str,
+        identity: &str,
+        config: &toml::value::Table,
+        files: &[(&str, &str)],
+    ) -> Option<Vec<(Duration, Result<Analysis>)>> {
+        if self.failed.load(Ordering::Relaxed) {
+            return None;
+        }
+        let request = Request::Analyze {
+            ruleset: ruleset_id.to_string(),
+            key: IncrementalState::ruleset_key(identity, config),
+            files: files
+                .iter()
+                .map(|(uri, content)| RequestFile {
+                    uri: uri.to_string(),
+                    content: content.to_string(),
+                })
+                .collect(),
+        };
+        let response = match self.send(&request) {
+            Ok(response) if response.error.is_none() && response.analyses.len() == files.len() => {
+                response
+            }
+            _ => {
+                self.failed.store(true, Ordering::Relaxed);
+                return None;
+            }
+        };
+        let analyses = response
+            .analyses
+            .into_iter()
+            .map(|outcome| {
+                let analysis = match (outcome.analysis, outcome.error) {
+                    (Some(analysis), _) => Ok(analysis),
+                    (None, error) => Err(anyhow!(error.unwrap_or_default())),
+                };
+                (Duration::from_secs_f64(outcome.seconds), analysis)
+            })
+            .collect();
+        Some(analyses)
+    }
+
+    #[cfg(unix)]
+    fn send(&self, request: &Request) -> Result<Response> {
+        let mut stream = UnixStream::connect(&self.socket)
+            .with_context(|| format!("Failed to connect to {}", self.socket.display()))?;
+        writeln!(stream, "{}", serde_json::to_string(request)?)?;
+        let mut line = String::new();
+        BufReader::new(stream).read_line(&mut line)?;
+        serde_json::from_str(&line).context("Invalid response from the daemon")
+    }
+
+    #[cfg(not(unix))]
+    fn send(&self, _request: &Request) -> Result<Response> {
+        Err(anyhow!("forseti daemon is only supported on Unix"))
+    }
+}
+
+/// Hash of a config file, which a run and the daemon compare to tell
+/// whether they lint with the same config
+pub(crate) fn config_hash(config_path: &Path) -> Option<String> {
+    let config = std::fs::read(config_path).ok()?;
+    Some(fingerprint::hash_bytes(&config))
+}
+
+/// Keep the rulesets of the project at `path` initialized, analyzing files
+/// for lint runs over `.forseti/daemon.sock` until `forseti daemon --stop`
+pub fn run(ctx: &GlobalContext, path: &Path, stop: bool) -> Result<()> {
+    let config_path = ctx.resolve_config_path(path);
+    let root = crate::paths::canonicalize(&crate::state::workspace_root(&config_path));
+    if stop {
+        let client = Client {
+            socket: ProjectState::new(&root).daemon_socket(),
+            failed: AtomicBool::new(false),
+        };
+        if !client.listening() {
+            return Err(anyhow!("No daemon is running for {}", root.display()));
+        }
+        client.send(&Request::Stop)?;
+        println!("Stopped the daemon at {}", client);
+        return Ok(());
+    }
+    serve(ctx, path)
+}
+
+/// What the daemon keeps between requests
+#[cfg(unix)]
+struct Served {
+    workspace: Workspace,
+    pool: SessionPool,
+    /// Config file as it was when loaded
+    config_stamp: Option<FileStamp>,
+    config_hash: Option<String>,
+    /// Key of every enabled ruleset when loaded, which a reinstalled binary
+    /// changes
+    keys: HashMap<String, String>,
+}
+
+#[cfg(unix)]
+impl Served {
+    fn load(ctx: &GlobalContext, path: &Path) -> Result<Self> {
+        // The daemon analyzes files itself rather than asking a daemon
+        let workspace = Workspace::open(ctx, path, false, lint::RunInputs::new(None, false))?;
+        let config_stamp = FileStamp::of(&workspace.config_path);
+        let config_hash = config_hash(&workspace.config_path);
+        let keys = workspace
+            .config
+            .ruleset
+            .keys()
+            .filter_map(|id| Some((id.clone(), Self::key(&workspace, id)?)))
+            .collect();
+        Ok(Self {
+            workspace,
+            pool: SessionPool::default(),
+            config_stamp,
+            config_hash,
+            keys,
+        })
+    }
+
+    /// Current key of an enabled ruleset
+    fn key(workspace: &Workspace, ruleset_id: &str) -> Option<String> {
+        let (ruleset, config) = workspace.enabled_ruleset(ruleset_id)?;
+        Some(IncrementalState::ruleset_key(&ruleset.identity(), config))
+    }
+
+    /// Whether the config changed on disk since loading
+    fn config_changed(&self) -> bool {
+        FileStamp::of(&self.workspace.config_path) != self.config_stamp
+    }
+
+    /// Whether the config or the ruleset changed on disk since loading
+    fn stale(&self, ruleset_id: &str) -> bool {
+        self.config_changed()
+            || Self::key(&self.workspace, ruleset_id).as_ref() != self.keys.get(ruleset_id)
+    }
+}
+
+#[cfg(not(unix))]
+fn serve(_ctx: &GlobalContext, _path: &Path) -> Result<()> {
+    Err(anyhow!("forseti daemon is only supported on Unix"))
+}
+
+#[cfg(unix)]
+fn serve(ctx: &GlobalContext, path: &Path) -> Result<()> {
+    let served = Served::load(ctx, path)?;
+    let state = ProjectState::new(&served.workspace.root);
+    let socket = state.daemon_socket();
+    // A daemon that exited without cleaning up leaves its socket behind
+    if UnixStream::connect(&socket).is_ok() {
+        return Err(anyhow!(
+            "A daemon is already running for {}; stop it with 'forseti daemon --stop'",
+            served.workspace.root.display()
+        ));
+    }
+    {
+        // Creates the state directory and its .gitignore on first use
+        let _lock = state.lock()?;
+        let _ = std::fs::remove_file(&socket);
+    }
+    let listener = UnixListener::bind(&socket)
+        .with_context(|| format!("Failed to listen on {}", socket.display()))?;
+    println!("Listening on {}", socket.display());
+    println!("Lint runs in this project now use its rulesets; stop with 'forseti daemon --stop'");
+
+    let served = RwLock::new(served);
+    std::thread::scope(|scope| {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    ctx.log_verbose(&format!("Failed to accept a connection: {}", e));
+                    continue;
+                }
+            };
+            let _ = stream.set_read_timeout(Some(REQUEST_TIMEOUT));
+            let mut reader = BufReader::new(&stream);
+            let mut line = String::new();
+            // Clients checking whether the daemon listens send nothing
+            if reader.read_line(&mut line).unwrap_or_default() == 0 {
+                continue;
+            }
+            match serde_json::from_str::<Request>(&line) {
+                Ok(Request::Stop) => {
+                    let _ = respond(&stream, &Response::default());
+                    break;
+                }
+                Ok(Request::Status) => {
+                    let response = status(ctx, path, &served);
+                    let _ = respond(&stream, &response);
+                }
+                Ok(Request::Analyze {
+                    ruleset,
+                    key,
+                    files,
+                }) => {
+                    let served = &served;
+                    scope.spawn(move || {
+                        let response = analyze(ctx, path, served, &ruleset, &key, &files);
+                        if let Err(e) = respond(&stream, &response) {
+                            ctx.log_verbose(&format!("Failed to answer a client: {:#}", e));
+                        }
+                    });
+                }
+                Err(e) => {
+                    let response = Response {
+                        error: Some(format!("Invalid request: {}", e)),
+                        ..Response::default()
+                    };
+                    let _ = respond(&stream, &response);
+                }
+            }
+        }
+    });
+
+    let _ = std::fs::remove_file(&socket);
+    let served = served.into_inner().unwrap_or_else(|e| e.into_inner());
+    for (ruleset_id, status) in served.pool.take_exits() {
+        eprintln!(
+            "Ruleset {} process exited with {} between files",
+            ruleset_id, status
+        );
+    }
+    for (ruleset_id, e) in served.pool.shutdown() {
+        eprintln!("Ruleset {} failed to shut down: {:#}", ruleset_id, e);
+    }
+    println!("Daemon stopped");
+    Ok(())
+}
+
+#[cfg(unix)]
+fn respond(mut stream: &UnixStream, response: &Response) -> Result<()> {
+    writeln!(stream, "{}", serde_json::to_string(response)?)?;
+    Ok(())
+}
+
+/// Reload the workspace when `stale` says it changed on disk, unless another
+/// request already did
+#[cfg(unix)]
+fn reload(
+    ctx: &GlobalContext,
+    path: &Path,
+    served: &RwLock<Served>,
+    stale: impl Fn(&Served) -> bool,
+) -> Result<()> {
+    if !stale(&served.read().unwrap()) {
+        return Ok(());
+    }
+    let mut current = served.write().unwrap();
+    if !stale(&current) {
+        return Ok(());
+    }
+    ctx.log_verbose("Configuration or rulesets changed; reloading");
+    let reloaded = Served::load(ctx, path).context("Failed to reload the workspace")?;
+    let previous = std::mem::replace(&mut *current, reloaded);
+    drop(current);
+    for (ruleset_id, e) in previous.pool.shutdown() {
+        ctx.log_verbose(&format!(
+            "Ruleset {} failed to shut down: {:#}",
+            ruleset_id, e
+        ));
+    }
+    Ok(())
+}
+
+/// Answer a status request with the hash of the config being served
+#[cfg(unix)]
+fn status(ctx: &GlobalContext, path: &Path, served: &RwLock<Served>) -> Response {
+    if let Err(e) = reload(ctx, path, served, Served::config_changed) {
+        return Response {
+            error: Some(format!("{:#}", e)),
+            ..Response::default()
+        };
+    }
+    Response {
+        config_hash: served.read().unwrap().config_hash.clone(),
+        ..Response::default()
+    }
+}
+
+/// Answer an analyze request, first reloading the workspace when its config
+/// or the ruleset changed on disk
+#[cfg(unix)]
+fn analyze(
+    ctx: &GlobalContext,
+    path: &Path,
+    served: &RwLock<Served>,
+    ruleset_id: &str,
+    key: &str,
+    files: &[RequestFile],
+) -> Response {
+    let fail = |error: String| Response {
+        error: Some(error),
+        ..Response::default()
+    };
+    if let Err(e) = reload(ctx, path, served, |current| current.stale(ruleset_id)) {
+        return fail(format!("{:#}", e));
+    }
+
+    let current = served.read().unwrap();
+    if current.keys.get(ruleset_id).map(String::as_str) != Some(key) {
+        return fail(format!(
+            "The daemon runs another config or version of ruleset {}",
+            ruleset_id
+        ));
+    }
+    let Some((ruleset, config)) = current.workspace.enabled_ruleset(ruleset_id) else {
+        return fail(format!("Ruleset {} is not enabled", ruleset_id));
+    };
+    ctx.log_verbose(&format!(
+        "Analyzing {} file(s) with ruleset {}",
+        files.len(),
+        ruleset_id
+    ));
+    let files: Vec<(&str, &str)> = files
+        .iter()
+        .map(|file| (file.uri.as_str(), file.content.as_str()))
+        .collect();
+    let analyses = lint::run_ruleset(&current.pool, &current.workspace, ruleset, config, &files)
+        .into_iter()
+        .map(|(elapsed, analysis)| match analysis {
+            Ok(analysis) => Outcome {
+                seconds: elapsed.as_secs_f64(),
+                analysis: Some(analysis),
+                error: None,
+            },
+            Err(e) => Outcome {
+                seconds: elapsed.as_secs_f64(),
+                analysis: None,
+                error: Some(format!("{:#}", e)),
+            },
+        })
+        .collect();
+    Response {
+        analyses,
+        ..Response::default()
+    }
+}
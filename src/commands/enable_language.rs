@@ -0,0 +1,95 @@
+use crate::commands::install;
+use crate::context::GlobalContext;
+use crate::i18n::Msg;
+use crate::languages::{self, LANGUAGES};
+use anyhow::{Context, Result, anyhow};
+use std::fs;
+use std::path::Path;
+
+/// Add the recommended rulesets for a language to the config and install them
+pub fn run(ctx: &GlobalContext, language: &str, path: &Path, no_install: bool) -> Result<()> {
+    let language = LANGUAGES
+        .iter()
+        .find(|l| l.id.eq_ignore_ascii_case(language) || l.name.eq_ignore_ascii_case(language))
+        .ok_or_else(|| {
+            let ids: Vec<&str> = LANGUAGES.iter().map(|l| l.id).collect();
+            anyhow!(
+                "Unknown language '{}' (expected one of: {})",
+                language,
+                ids.join(", ")
+            )
+        })?;
+
+    let config_path = ctx.resolve_config_path(path);
+    if !config_path.exists() {
+        return Err(anyhow!(
+            ctx.lang.format(Msg::NoConfig, &[&config_path.display()])
+        ));
+    }
+    let content = fs::read_to_string(&config_path)
+        .with_context(|| format!("Failed to read config file: {}", config_path.display()))?;
+    let table: toml::Table = toml::from_str(&content)
+        .with_context(|| format!("Failed to parse config file: {}", config_path.display()))?;
+    let configured = table.get("ruleset").and_then(toml::Value::as_table);
+
+    // Appended rather than rewritten, so comments and layout survive
+    let mut added = Vec::new();
+    for ruleset_id in languages::recommended_rulesets(language) {
+        match configured.and_then(|rulesets| rulesets.get(ruleset_id)) {
+            Some(existing) => {
+                let enabled = existing
+                    .get("enabled")
+                    .and_then(toml::Value::as_bool)
+                    .unwrap_or(true);
+                if enabled {
+                    println!("Ruleset {} is already configured", ruleset_id);
+                } else {
+                    println!(
+                        "Ruleset {} is configured but disabled; leaving it as is",
+                        ruleset_id
+                    );
+                }
+            }
+            None => {
+                added.push(ruleset_id);
+            }
+        }
+    }
+
+    if added.is_empty() {
+        println!("Nothing to add for {}", language.name);
+        return Ok(());
+    }
+    let tables: Vec<String> = added
+        .iter()
+        .map(|ruleset_id| format!("[ruleset.{}]\nenabled = true\n", ruleset_id))
+        .collect();
+    let addition = format!(
+        "\n# Added by `forseti enable-language {}`\n{}",
+        language.id,
+        tables.join("\n")
+    );
+    let separator = if content.ends_with('\n') || content.is_empty() {
+        ""
+    } else {
+        "\n"
+    };
+    fs::write(
+        &config_path,
+        format!("{}{}{}", content, separator, addition),
+    )
+    .with_context(|| format!("Failed to write config file: {}", config_path.display()))?;
+    println!(
+        "Added {} ruleset(s) for {} to {}: {}",
+        added.len(),
+        language.name,
+        config_path.display(),
+        added.join(", ")
+    );
+
+    if no_install {
+        println!("Run `forseti install` to download them");
+        return Ok(());
+    }
+    install::run(ctx, None, false, path, false, false)
+}
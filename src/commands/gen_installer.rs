@@ -0,0 +1,296 @@
+use crate::context::GlobalContext;
+use crate::i18n::Msg;
+use crate::paths;
+use crate::settings::{DOWNLOAD_PLATFORMS, Download, Settings};
+use crate::state::{self, GITIGNORE, STATE_DIR, VENDOR_DIR};
+use anyhow::{Context, Result, anyhow};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+/// Scripts written next to `.forseti.toml`
+const SHELL_SCRIPT: &str = "install-rulesets.sh";
+const POWERSHELL_SCRIPT: &str = "install-rulesets.ps1";
+
+/// Pinned downloads of one ruleset, by platform
+type Pins = BTreeMap<String, Download>;
+
+/// Write shell and PowerShell scripts that download the pinned rulesets of
+/// a project, verify their digests and place them in `.forseti/vendor/`,
+/// where `forseti lint` finds them without `forseti install` or cargo
+pub fn run(ctx: &GlobalContext, path: &Path) -> Result<()> {
+    let config_path = ctx.resolve_config_path(path);
+    if !config_path.exists() {
+        return Err(anyhow!(ctx.lang.format(Msg::NoConfig, &[&path.display()])));
+    }
+    ctx.log_verbose(&format!("Using config file: {}", config_path.display()));
+    let settings = Settings::load(&config_path)?;
+    let config = settings.load_config(&config_path, ctx.ci)?;
+
+    let mut enabled: Vec<&String> = config
+        .ruleset
+        .iter()
+        .filter(|(_, cfg)| cfg.enabled)
+        .map(|(id, _)| id)
+        .collect();
+    enabled.sort();
+
+    let mut pinned: BTreeMap<&str, Pins> = BTreeMap::new();
+    for id in enabled {
+        let ruleset = settings.ruleset(id);
+        if ruleset.image.is_some() {
+            println!("Skipping {}: runs from a container image", id);
+        } else if ruleset.download.is_empty() {
+            println!("Skipping {}: no [ruleset.{}.download] pins", id, id);
+        } else {
+            pinned.insert(id, ruleset.download);
+        }
+    }
+    if pinned.is_empty() {
+        return Err(anyhow!(
+            "No enabled ruleset has [ruleset.<id>.download] pins to install"
+        ));
+    }
+
+    let root = state::workspace_root(&config_path);
+    for (name, script) in [
+        (SHELL_SCRIPT, shell_script(&pinned)),
+        (POWERSHELL_SCRIPT, powershell_script(&pinned)),
+    ] {
+        let script_path = root.join(name);
+        ctx.check_write(&script_path, &paths::canonicalize(&root))?;
+        fs::write(&script_path, script)
+            .with_context(|| format!("Failed to write {}", script_path.display()))?;
+        #[cfg(unix)]
+        if name == SHELL_SCRIPT {
+            use std::os::unix::fs::PermissionsExt;
+            let mut perms = fs::metadata(&script_path)?.permissions();
+            perms.set_mode(perms.mode() | 0o111);
+            fs::set_permissions(&script_path, perms)?;
+        }
+        println!("Wrote {}", script_path.display());
+    }
+    println!(
+        "Contributors can now run ./{} (or {} on Windows) to fetch {} ruleset(s)",
+        SHELL_SCRIPT,
+        POWERSHELL_SCRIPT,
+        pinned.len()
+    );
+    Ok(())
+}
+
+/// POSIX sh installer for the Linux and macOS pins
+fn shell_script(pinned: &BTreeMap<&str, Pins>) -> String {
+    let mut sh = String::new();
+    let _ = writeln!(sh, "#!/bin/sh");
+    let _ = writeln!(
+        sh,
+        "# Generated by `forseti gen-installer` from .forseti.toml; regenerate it instead of editing"
+    );
+    let _ = writeln!(
+        sh,
+        "# Downloads the pinned rulesets into {}/{}, where `forseti lint` finds them",
+        STATE_DIR, VENDOR_DIR
+    );
+    let _ = writeln!(sh, "set -eu");
+    let _ = writeln!(sh, "cd \"$(dirname \"$0\")\"");
+    let _ = writeln!(sh);
+    let _ = writeln!(sh, "case \"$(uname -s)-$(uname -m)\" in");
+    let _ = writeln!(sh, "  Linux-x86_64 | Linux-amd64) platform=linux-x86_64 ;;");
+    let _ = writeln!(
+        sh,
+        "  Linux-aarch64 | Linux-arm64) platform=linux-aarch64 ;;"
+    );
+    let _ = writeln!(sh, "  Darwin-x86_64) platform=macos-x86_64 ;;");
+    let _ = writeln!(sh, "  Darwin-arm64) platform=macos-aarch64 ;;");
+    let _ = writeln!(
+        sh,
+        "  *) echo \"Unsupported platform: $(uname -s) $(uname -m)\" >&2; exit 1 ;;"
+    );
+    let _ = writeln!(sh, "esac");
+    let _ = writeln!(sh);
+    let _ = writeln!(sh, "vendor={}/{}", STATE_DIR, VENDOR_DIR);
+    let _ = writeln!(sh, "mkdir -p \"$vendor\"");
+    let _ = writeln!(
+        sh,
+        "[ -f {dir}/.gitignore ] || printf '{}' > {dir}/.gitignore",
+        GITIGNORE.replace('\n', "\\n"),
+        dir = STATE_DIR
+    );
+    let _ = writeln!(sh, "incomplete=0");
+    sh.push_str(
+        r#"
+fetch() {
+  if command -v curl > /dev/null 2>&1; then
+    curl -fsSL "$1" -o "$2"
+  else
+    wget -q "$1" -O "$2"
+  fi
+}
+
+sha256() {
+  if command -v sha256sum > /dev/null 2>&1; then
+    sha256sum "$1" | cut -d ' ' -f 1
+  else
+    shasum -a 256 "$1" | cut -d ' ' -f 1
+  fi
+}
+
+# fetch_ruleset <id> <url> <sha256>
+fetch_ruleset() {
+  target="$vendor/forseti_ruleset_$1"
+  if [ -f "$target" ] && [ "$(sha256 "$target")" = "$3" ]; then
+    echo "Ruleset $1 is up to date"
+    return
+  fi
+  echo "Installing ruleset $1"
+  part="$vendor/.download-$1"
+  fetch "$2" "$part"
+  actual="$(sha256 "$part")"
+  if [ "$actual" != "$3" ]; then
+    rm -f "$part"
+    echo "Checksum mismatch for ruleset $1: expected $3, got $actual" >&2
+    exit 1
+  fi
+  chmod +x "$part"
+  mv -f "$part" "$target"
+}
+
+# missing_ruleset <id>
+missing_ruleset() {
+  echo "No pinned download of ruleset $1 for $platform; install it with 'forseti install'" >&2
+  incomplete=1
+}
+
+"#,
+    );
+    let _ = writeln!(sh, "case \"$platform\" in");
+    for platform in DOWNLOAD_PLATFORMS
+        .iter()
+        .filter(|platform| !platform.starts_with("windows-"))
+    {
+        let _ = writeln!(sh, "  {})", platform);
+        for (id, pins) in pinned {
+            match pins.get(*platform) {
+                Some(download) => {
+                    let _ = writeln!(
+                        sh,
+                        "    fetch_ruleset {} {} {}",
+                        sh_quote(id),
+                        sh_quote(&download.url),
+                        download.sha256.to_ascii_lowercase()
+                    );
+                }
+                None => {
+                    let _ = writeln!(sh, "    missing_ruleset {}", sh_quote(id));
+                }
+            }
+        }
+        let _ = writeln!(sh, "    ;;");
+    }
+    let _ = writeln!(sh, "esac");
+    let _ = writeln!(sh, "exit \"$incomplete\"");
+    sh
+}
+
+/// PowerShell installer for the Windows pins
+fn powershell_script(pinned: &BTreeMap<&str, Pins>) -> String {
+    let mut ps = String::new();
+    let _ = writeln!(
+        ps,
+        "# Generated by `forseti gen-installer` from .forseti.toml; regenerate it instead of editing"
+    );
+    let _ = writeln!(
+        ps,
+        "# Downloads the pinned rulesets into {}\\{}, where `forseti lint` finds them",
+        STATE_DIR, VENDOR_DIR
+    );
+    let _ = writeln!(ps, "$ErrorActionPreference = 'Stop'");
+    let _ = writeln!(ps, "Set-Location $PSScriptRoot");
+    let _ = writeln!(ps);
+    let _ = writeln!(ps, "$platform = switch ($env:PROCESSOR_ARCHITECTURE) {{");
+    let _ = writeln!(ps, "    'AMD64' {{ 'windows-x86_64' }}");
+    let _ = writeln!(ps, "    'ARM64' {{ 'windows-aarch64' }}");
+    let _ = writeln!(
+        ps,
+        "    default {{ throw \"Unsupported architecture: $env:PROCESSOR_ARCHITECTURE\" }}"
+    );
+    let _ = writeln!(ps, "}}");
+    let _ = writeln!(ps);
+    let _ = writeln!(ps, "$vendor = Join-Path '{}' '{}'", STATE_DIR, VENDOR_DIR);
+    let _ = writeln!(
+        ps,
+        "New-Item -ItemType Directory -Force -Path $vendor | Out-Null"
+    );
+    let _ = writeln!(ps, "$gitignore = Join-Path '{}' '.gitignore'", STATE_DIR);
+    let _ = writeln!(
+        ps,
+        "if (-not (Test-Path $gitignore)) {{ Set-Content -NoNewline -Path $gitignore -Value \"{}\" }}",
+        GITIGNORE.replace('\n', "`n")
+    );
+    let _ = writeln!(ps, "$incomplete = $false");
+    ps.push_str(
+        r#"
+function Install-Ruleset($Id, $Url, $Sha256) {
+    $target = Join-Path $vendor "forseti_ruleset_$Id.exe"
+    if ((Test-Path $target) -and (Get-FileHash -Algorithm SHA256 $target).Hash -eq $Sha256) {
+        Write-Host "Ruleset $Id is up to date"
+        return
+    }
+    Write-Host "Installing ruleset $Id"
+    $part = Join-Path $vendor ".download-$Id"
+    Invoke-WebRequest -UseBasicParsing -Uri $Url -OutFile $part
+    $actual = (Get-FileHash -Algorithm SHA256 $part).Hash
+    if ($actual -ne $Sha256) {
+        Remove-Item $part
+        throw "Checksum mismatch for ruleset ${Id}: expected $Sha256, got $actual"
+    }
+    Move-Item -Force $part $target
+}
+
+function Skip-Ruleset($Id) {
+    Write-Warning "No pinned download of ruleset $Id for $platform; install it with 'forseti install'"
+    $script:incomplete = $true
+}
+
+"#,
+    );
+    let _ = writeln!(ps, "switch ($platform) {{");
+    for platform in DOWNLOAD_PLATFORMS
+        .iter()
+        .filter(|platform| platform.starts_with("windows-"))
+    {
+        let _ = writeln!(ps, "    '{}' {{", platform);
+        for (id, pins) in pinned {
+            match pins.get(*platform) {
+                Some(download) => {
+                    let _ = writeln!(
+                        ps,
+                        "        Install-Ruleset {} {} {}",
+                        ps_quote(id),
+                        ps_quote(&download.url),
+                        ps_quote(&download.sha256)
+                    );
+                }
+                None => {
+                    let _ = writeln!(ps, "        Skip-Ruleset {}", ps_quote(id));
+                }
+            }
+        }
+        let _ = writeln!(ps, "    }}");
+    }
+    let _ = writeln!(ps, "}}");
+    let _ = writeln!(ps, "if ($incomplete) {{ exit 1 }}");
+    ps
+}
+
+/// Single-quote a word for sh
+fn sh_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Single-quote a string for PowerShell
+fn ps_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
@@ -1,50 +1,97 @@
+use crate::container;
 use crate::context::GlobalContext;
+use crate::i18n::Msg;
+use crate::manifest::{MANIFEST_FILE, Manifest};
+use crate::paths;
+use crate::policy::{Policy, RulesetSource};
+use crate::settings::{RulesetSettings, Settings};
 use anyhow::{Context, Result, anyhow};
 use forseti_sdk::config::{Config, RulesetCfg};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
-use std::{env, fs};
 
 pub fn run(
     ctx: &GlobalContext,
-    cache_path: &Path,
+    cache_path: Option<&Path>,
     enable_cache: bool,
     path: &Path,
     force: bool,
+    check: bool,
 ) -> Result<()> {
     let config_path = ctx.resolve_config_path(path);
     ctx.log_verbose(&format!("Using config file: {}", config_path.display()));
 
     if !config_path.exists() {
-        return Err(anyhow!(
-            "No .forseti.toml found at {}. Run 'forseti init' first.",
-            path.display()
-        ));
+        return Err(anyhow!(ctx.lang.format(Msg::NoConfig, &[&path.display()])));
     }
 
     if !ctx.verbose {
         println!("Loading configuration from {}...", config_path.display());
     }
-    let config = Config::load_from_path(&config_path).context("Failed to load configuration")?;
+    let settings = Settings::load(&config_path)?;
+    let config = settings.load_config(&config_path, ctx.ci)?;
 
-    let cache_dir = if enable_cache {
-        Some(cache_path.to_path_buf())
-    } else {
-        None
+    let cache_dir = match cache_path.filter(|_| enable_cache) {
+        Some(cache_path) => cache_path.to_path_buf(),
+        None => ctx.ruleset_cache_dir()?,
     };
 
-    install_dependencies(&config, cache_dir.as_ref(), force)?;
+    if check {
+        return check_installed(&config, &settings, &cache_dir);
+    }
+
+    // The default cache lives in the home directory, outside any workspace
+    if ctx.ci && !enable_cache && ctx.install_root.is_none() {
+        return Err(anyhow!(
+            "In --ci mode pass --install-root <dir> (or --enable-cache --cache-path <dir>) so installs only write to an explicit directory"
+        ));
+    }
+
+    let policy = Policy::from_env()?;
+    if let Some(policy) = &policy {
+        ctx.log_verbose(&format!(
+            "Enforcing organization policy from {}",
+            policy.origin.display()
+        ));
+    }
+
+    install_dependencies(&config, &settings, policy.as_ref(), &cache_dir, force)?;
 
     println!("Everything installed successfully!");
     Ok(())
 }
 
-fn install_dependencies(config: &Config, cache_dir: Option<&PathBuf>, force: bool) -> Result<()> {
+fn install_dependencies(
+    config: &Config,
+    settings: &Settings,
+    policy: Option<&Policy>,
+    cache_dir: &Path,
+    force: bool,
+) -> Result<()> {
+    // Reject policy violations before anything gets installed
+    if let Some(policy) = policy {
+        for (ruleset_id, ruleset_cfg) in &config.ruleset {
+            if ruleset_cfg.enabled {
+                let ext = settings.ruleset(ruleset_id);
+                policy.check(ruleset_id, ruleset_source(ruleset_cfg, &ext))?;
+            }
+        }
+    }
+
     println!("Installing rulesets...");
     for (ruleset_id, ruleset_cfg) in &config.ruleset {
         if ruleset_cfg.enabled {
-            install_ruleset(ruleset_id, ruleset_cfg, cache_dir, force)
-                .with_context(|| format!("Failed to install ruleset '{}'", ruleset_id))?;
+            install_ruleset(
+                ruleset_id,
+                ruleset_cfg,
+                &settings.ruleset(ruleset_id),
+                cache_dir,
+                force,
+            )
+            .with_context(|| format!("Failed to install ruleset '{}'", ruleset_id))?;
         } else {
             println!("Skipping disabled ruleset: {}", ruleset_id);
         }
@@ -53,36 +100,179 @@ fn install_dependencies(config: &Config, cache_dir: Option<&PathBuf>, force: boo
     Ok(())
 }
 
+/// Determine where a ruleset would be installed from
+fn ruleset_source<'a>(cfg: &'a RulesetCfg, ext: &'a RulesetSettings) -> RulesetSource<'a> {
+    if let Some(image) = &ext.image {
+        RulesetSource::Image(image)
+    } else if let Some(package) = &ext.npm_package {
+        RulesetSource::Npm(package)
+    } else if let Some(package) = &ext.pip_package {
+        RulesetSource::Pip(package)
+    } else if let Some(local_path) = &cfg.path {
+        RulesetSource::Local(local_path)
+    } else if let Some(git_url) = &cfg.git {
+        RulesetSource::Git(git_url)
+    } else {
+        RulesetSource::CratesIo
+    }
+}
 
 fn install_ruleset(
     id: &str,
     cfg: &RulesetCfg,
-    cache_dir: Option<&PathBuf>,
+    ext: &RulesetSettings,
+    cache_dir: &Path,
     force: bool,
 ) -> Result<()> {
     println!("Installing ruleset: {}", id);
 
-    if let Some(local_path) = &cfg.path {
-        install_from_local("ruleset", id, local_path, cache_dir, force)?;
-    } else if let Some(git_url) = &cfg.git {
-        install_from_git("ruleset", id, git_url, cache_dir, force)?;
-    } else {
-        install_from_crates_io("ruleset", id, cache_dir, force)?;
+    let source = ruleset_source(cfg, ext);
+    let cache_path = get_cache_path(cache_dir, id);
+    let previous = installed_binary(&cache_path, id)
+        .map(|binary| file_checksum(&binary))
+        .transpose()?;
+
+    match source {
+        RulesetSource::Image(image) => {
+            println!("  Pulling container image: {}", image);
+            container::pull(image)?
+        }
+        RulesetSource::Local(local_path) => {
+            install_from_local("ruleset", id, local_path, cache_dir, force)?
+        }
+        RulesetSource::Git(git_url) => install_from_git("ruleset", id, git_url, cache_dir, force)?,
+        RulesetSource::Npm(package) => {
+            install_from_npm("ruleset", id, package, ext.bin.as_deref(), cache_dir, force)?
+        }
+        RulesetSource::Pip(package) => {
+            install_from_pip("ruleset", id, package, ext.bin.as_deref(), cache_dir, force)?
+        }
+        RulesetSource::CratesIo => install_from_crates_io("ruleset", id, cache_dir, force)?,
+    }
+
+    if let Some(binary) = installed_binary(&cache_path, id) {
+        let checksum = file_checksum(&binary)?;
+        let record_path = cache_path.join(INSTALL_RECORD);
+        let recorded = fs::read_to_string(&record_path)
+            .ok()
+            .and_then(|content| toml::from_str::<InstallRecord>(&content).ok());
+        let record = match recorded {
+            // Skipped installs keep the record of whatever put the binary
+            // there, refreshing checksums recorded before they were SHA-256
+            Some(recorded) if previous.as_ref() == Some(&checksum) => InstallRecord {
+                checksum,
+                ..recorded
+            },
+            _ => InstallRecord {
+                source: source_label(source),
+                checksum,
+            },
+        };
+        fs::write(&record_path, toml::to_string(&record)?)
+            .context("Failed to write install record")?;
     }
 
     Ok(())
 }
 
+/// Check every enabled ruleset against its install record without changing
+/// anything
+fn check_installed(config: &Config, settings: &Settings, cache_dir: &Path) -> Result<()> {
+    println!("Checking installed rulesets...");
+    let mut enabled: Vec<_> = config
+        .ruleset
+        .iter()
+        .filter(|(_, cfg)| cfg.enabled)
+        .collect();
+    enabled.sort_by_key(|(id, _)| id.as_str());
+
+    let mut out_of_sync = 0;
+    for (id, cfg) in &enabled {
+        let ext = settings.ruleset(id);
+        let problem = check_ruleset(id, ruleset_source(cfg, &ext), cache_dir)
+            .unwrap_or_else(|e| Some(format!("{:#}", e)));
+        match problem {
+            None => println!("  {}: up to date", id),
+            Some(problem) => {
+                println!("  {}: {}", id, problem);
+                out_of_sync += 1;
+            }
+        }
+    }
+
+    if out_of_sync > 0 {
+        return Err(anyhow!(
+            "{} of {} ruleset(s) don't match the configuration; run `forseti install`",
+            out_of_sync,
+            enabled.len()
+        ));
+    }
+    println!("Cache matches the configuration");
+    Ok(())
+}
+
+/// What is out of sync for one ruleset, if anything
+fn check_ruleset(id: &str, source: RulesetSource, cache_dir: &Path) -> Result<Option<String>> {
+    if let RulesetSource::Image(image) = source {
+        let missing = !container::is_present(image);
+        return Ok(missing.then(|| format!("image {} not pulled", image)));
+    }
+
+    let cache_path = get_cache_path(cache_dir, id);
+    let Some(binary) = installed_binary(&cache_path, id) else {
+        return Ok(Some("missing".to_string()));
+    };
+    let Ok(content) = fs::read_to_string(cache_path.join(INSTALL_RECORD)) else {
+        return Ok(Some(
+            "no install record (reinstall with --force)".to_string(),
+        ));
+    };
+    let record: InstallRecord =
+        toml::from_str(&content).context("Failed to parse install record")?;
+
+    let expected = source_label(source);
+    if record.source != expected {
+        return Ok(Some(format!(
+            "installed from {} but the config requires {}",
+            record.source, expected
+        )));
+    }
+    // Records from before checksums were SHA-256 can't be compared
+    if record.checksum.len() != 64 {
+        return Ok(Some(
+            "install record has an old checksum (reinstall with --force)".to_string(),
+        ));
+    }
+    let checksum = file_checksum(&binary)?;
+    if record.checksum != checksum {
+        return Ok(Some(format!(
+            "checksum drift: {} changed since install",
+            binary.display()
+        )));
+    }
+    // Local sources can be compared directly; remote ones would need a fetch
+    if let RulesetSource::Local(local_path) = source {
+        let (source_binary, _) = resolve_local_binary(id, local_path)?;
+        if file_checksum(&source_binary)? != checksum {
+            return Ok(Some(format!(
+                "outdated: {} was rebuilt",
+                source_binary.display()
+            )));
+        }
+    }
+    Ok(None)
+}
+
 fn install_from_local(
     component_type: &str,
     id: &str,
     local_path: &str,
-    cache_dir: Option<&PathBuf>,
+    cache_dir: &Path,
     force: bool,
 ) -> Result<()> {
     println!("  Installing from local path: {}", local_path);
 
-    let cache_path = get_cache_path(cache_dir, id)?;
+    let cache_path = get_cache_path(cache_dir, id);
     let binary_name = format!("forseti_{}_{}", component_type, id);
     let binary_path = cache_path.join("bin").join(&binary_name);
 
@@ -92,14 +282,8 @@ fn install_from_local(
         return Ok(());
     }
 
-    let source_path = Path::new(local_path);
-    if !source_path.exists() {
-        return Err(anyhow!("Local path does not exist: {}", local_path));
-    }
-
-    if !source_path.is_file() {
-        return Err(anyhow!("Local path is not a file: {}", local_path));
-    }
+    let (source_path, manifest) = resolve_local_binary(id, local_path)?;
+    let source_path = source_path.as_path();
 
     // Check if source is executable (on Unix systems)
     #[cfg(unix)]
@@ -126,20 +310,71 @@ fn install_from_local(
         fs::set_permissions(&binary_path, perms)?;
     }
 
+    if let Some((manifest, manifest_path)) = &manifest {
+        install_manifest(&cache_path, manifest, manifest_path)?;
+    }
+
     println!("  Copied and installed to: {}", binary_path.display());
     Ok(())
 }
 
+/// The binary a local ruleset source provides, with its manifest when it has one
+fn resolve_local_binary(
+    id: &str,
+    local_path: &str,
+) -> Result<(PathBuf, Option<(Manifest, PathBuf)>)> {
+    let mut source_path = PathBuf::from(local_path);
+    if !source_path.exists() {
+        return Err(anyhow!("Local path does not exist: {}", local_path));
+    }
+
+    // A directory must carry a manifest naming the binary to install
+    let manifest = if source_path.is_dir() {
+        let manifest_path = source_path.join(MANIFEST_FILE);
+        if !manifest_path.is_file() {
+            return Err(anyhow!(
+                "Local path is a directory without a {}: {}",
+                MANIFEST_FILE,
+                local_path
+            ));
+        }
+        let manifest = Manifest::load(&manifest_path)?;
+        manifest.validate(id)?;
+        let entrypoint = manifest.entrypoint();
+        source_path = [
+            source_path.join(&entrypoint),
+            source_path.join("target").join("release").join(&entrypoint),
+        ]
+        .into_iter()
+        .find(|candidate| candidate.is_file())
+        .ok_or_else(|| {
+            anyhow!(
+                "Binary '{}' listed in {} not found (build the ruleset first)",
+                entrypoint,
+                manifest_path.display()
+            )
+        })?;
+        Some((manifest, manifest_path))
+    } else {
+        let manifest = Manifest::find(source_path.parent().unwrap_or(Path::new(".")))?;
+        if let Some((manifest, _)) = &manifest {
+            manifest.validate(id)?;
+        }
+        manifest
+    };
+    Ok((source_path, manifest))
+}
+
 fn install_from_git(
     component_type: &str,
     id: &str,
     git_url: &str,
-    cache_dir: Option<&PathBuf>,
+    cache_dir: &Path,
     force: bool,
 ) -> Result<()> {
     println!("  Installing from git: {}", git_url);
 
-    let cache_path = get_cache_path(cache_dir, id)?;
+    let cache_path = get_cache_path(cache_dir, id);
     let repo_path = cache_path.join(format!("{}-repo", id));
     let binary_name = format!("forseti_{}_{}", component_type, id);
     let binary_path = cache_path.join("bin").join(&binary_name);
@@ -214,6 +449,30 @@ fn install_from_git(
         return Err(anyhow!("Release directory not found after build"));
     }
 
+    // The manifest names the entrypoint, so no guessing is needed
+    let manifest_path = repo_path.join(MANIFEST_FILE);
+    if manifest_path.is_file() {
+        let manifest = Manifest::load(&manifest_path)?;
+        manifest.validate(id)?;
+        let built = release_dir.join(manifest.entrypoint());
+        if !built.is_file() {
+            return Err(anyhow!(
+                "Binary '{}' listed in {} was not built",
+                manifest.entrypoint(),
+                MANIFEST_FILE
+            ));
+        }
+        fs::create_dir_all(binary_path.parent().unwrap())?;
+        fs::copy(&built, &binary_path)?;
+        install_manifest(&cache_path, &manifest, &manifest_path)?;
+        println!("  Built and installed to: {}", binary_path.display());
+        return Ok(());
+    }
+    println!(
+        "  No {} found, using the first executable built",
+        MANIFEST_FILE
+    );
+
     // Look for executable files in the release directory
     let entries = fs::read_dir(&release_dir)?;
     let mut binary_found = false;
@@ -272,12 +531,12 @@ fn install_from_git(
 fn install_from_crates_io(
     component_type: &str,
     id: &str,
-    cache_dir: Option<&PathBuf>,
+    cache_dir: &Path,
     force: bool,
 ) -> Result<()> {
     println!("  Installing from crates.io: {}", id);
 
-    let cache_path = get_cache_path(cache_dir, id)?;
+    let cache_path = get_cache_path(cache_dir, id);
     let binary_name = format!("forseti_{}_{}", component_type, id);
     let binary_path = cache_path.join("bin").join(&binary_name);
 
@@ -386,16 +645,286 @@ fn try_cargo_binstall(crate_name: &str, install_path: &Path, force: bool) -> Res
     Ok(())
 }
 
-fn get_cache_path(cache_dir: Option<&PathBuf>, id: &str) -> Result<PathBuf> {
-    let base_path = if let Some(cache) = cache_dir {
-        cache.clone()
+fn install_from_npm(
+    component_type: &str,
+    id: &str,
+    package: &str,
+    bin: Option<&str>,
+    cache_dir: &Path,
+    force: bool,
+) -> Result<()> {
+    println!("  Installing from npm: {}", package);
+
+    let cache_path = get_cache_path(cache_dir, id);
+    let launcher_path = launcher_path(&cache_path, component_type, id);
+
+    if launcher_path.exists() && !force {
+        println!("  Binary already exists (use --force to overwrite)");
+        return Ok(());
+    }
+
+    let prefix = cache_path.join("npm");
+    if force && prefix.exists() {
+        fs::remove_dir_all(&prefix)?;
+    }
+    fs::create_dir_all(&prefix)?;
+
+    let output = Command::new("npm")
+        .args(["install", "--no-save", "--prefix"])
+        .arg(&prefix)
+        .arg(package)
+        .output()
+        .context("Failed to run npm install. Make sure Node.js is installed.")?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to install from npm: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    let bin_name = bin
+        .map(str::to_string)
+        .unwrap_or_else(|| npm_bin_name(package));
+    let target = prefix.join("node_modules").join(".bin").join(&bin_name);
+    if !target.exists() {
+        return Err(anyhow!(
+            "npm package {} does not provide an executable named '{}' (set `bin` to choose one)",
+            package,
+            bin_name
+        ));
+    }
+
+    write_launcher(&launcher_path, &target)?;
+    println!("  Installed launcher to: {}", launcher_path.display());
+    Ok(())
+}
+
+fn install_from_pip(
+    component_type: &str,
+    id: &str,
+    package: &str,
+    bin: Option<&str>,
+    cache_dir: &Path,
+    force: bool,
+) -> Result<()> {
+    println!("  Installing from pip: {}", package);
+
+    let cache_path = get_cache_path(cache_dir, id);
+    let launcher_path = launcher_path(&cache_path, component_type, id);
+
+    if launcher_path.exists() && !force {
+        println!("  Binary already exists (use --force to overwrite)");
+        return Ok(());
+    }
+
+    let venv = cache_path.join("venv");
+    if force && venv.exists() {
+        fs::remove_dir_all(&venv)?;
+    }
+    fs::create_dir_all(&cache_path)?;
+
+    let python = if cfg!(windows) { "python" } else { "python3" };
+    let output = Command::new(python)
+        .args(["-m", "venv"])
+        .arg(&venv)
+        .output()
+        .context("Failed to create virtualenv. Make sure Python 3 is installed.")?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to create virtualenv: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    let scripts_dir = if cfg!(windows) {
+        venv.join("Scripts")
+    } else {
+        venv.join("bin")
+    };
+
+    let output = Command::new(scripts_dir.join("pip"))
+        .args(["install", "--disable-pip-version-check", package])
+        .output()
+        .context("Failed to run pip install")?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to install from pip: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    let bin_name = bin
+        .map(str::to_string)
+        .unwrap_or_else(|| pip_bin_name(package));
+    let target = if cfg!(windows) {
+        scripts_dir.join(format!("{}.exe", bin_name))
+    } else {
+        scripts_dir.join(&bin_name)
+    };
+    if !target.exists() {
+        return Err(anyhow!(
+            "pip package {} does not provide an executable named '{}' (set `bin` to choose one)",
+            package,
+            bin_name
+        ));
+    }
+
+    write_launcher(&launcher_path, &target)?;
+    println!("  Installed launcher to: {}", launcher_path.display());
+    Ok(())
+}
+
+/// Digest of the packages behind the npm or pip launcher at `launcher`: the
+/// lockfile npm writes listing every installed version, or the names and
+/// versions of the distributions in the virtualenv; `None` for other binaries
+pub(crate) fn installed_packages(launcher: &Path) -> Option<String> {
+    let bin_dir = launcher.parent()?;
+    if bin_dir.file_name()? != "bin" {
+        return None;
+    }
+    let cache_path = bin_dir.parent()?;
+
+    let lockfile = cache_path
+        .join("npm")
+        .join("node_modules")
+        .join(".package-lock.json");
+    if let Ok(lockfile) = fs::read(&lockfile) {
+        return Some(format!("{:x}", Sha256::digest(&lockfile)));
+    }
+
+    let venv = cache_path.join("venv");
+    let site_packages: Vec<PathBuf> = if cfg!(windows) {
+        vec![venv.join("Lib").join("site-packages")]
     } else {
-        // Default cache location
-        let home = env::var("HOME")
-            .or_else(|_| env::var("USERPROFILE"))
-            .context("Could not determine home directory")?;
-        PathBuf::from(home).join(".forseti").join("cache")
+        fs::read_dir(venv.join("lib"))
+            .ok()?
+            .flatten()
+            .map(|entry| entry.path().join("site-packages"))
+            .collect()
     };
+    // `<name>-<version>.dist-info` for each installed distribution
+    let mut distributions: Vec<String> = site_packages
+        .iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            name.ends_with(".dist-info").then_some(name)
+        })
+        .collect();
+    if distributions.is_empty() {
+        return None;
+    }
+    distributions.sort();
+    Some(format!("{:x}", Sha256::digest(distributions.join("\n"))))
+}
+
+/// Executable name for an npm spec like `@org/forseti-ruleset-ts@1.2`
+fn npm_bin_name(package: &str) -> String {
+    let unscoped = package.rsplit('/').next().unwrap_or(package);
+    unscoped.split('@').next().unwrap_or(unscoped).to_string()
+}
+
+/// Executable name for a pip requirement like `forseti-ruleset-py==1.2`
+fn pip_bin_name(package: &str) -> String {
+    package
+        .split(|c: char| "=<>!~[; ".contains(c))
+        .next()
+        .unwrap_or(package)
+        .to_string()
+}
+
+fn launcher_path(cache_path: &Path, component_type: &str, id: &str) -> PathBuf {
+    let name = format!("forseti_{}_{}", component_type, id);
+    if cfg!(windows) {
+        cache_path.join("bin").join(format!("{}.cmd", name))
+    } else {
+        cache_path.join("bin").join(name)
+    }
+}
+
+/// Write a script that execs `target`, so discovery treats it like any other ruleset binary
+fn write_launcher(launcher_path: &Path, target: &Path) -> Result<()> {
+    fs::create_dir_all(launcher_path.parent().unwrap())?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let script = format!("#!/bin/sh\nexec \"{}\" \"$@\"\n", target.display());
+        fs::write(launcher_path, script)?;
+        let mut perms = fs::metadata(launcher_path)?.permissions();
+        perms.set_mode(perms.mode() | 0o111);
+        fs::set_permissions(launcher_path, perms)?;
+    }
+
+    #[cfg(not(unix))]
+    {
+        let script = format!("@echo off\r\n\"{}\" %*\r\n", target.display());
+        fs::write(launcher_path, script)?;
+    }
+
+    Ok(())
+}
+
+/// Keep the manifest next to the installed binary so discovery can read its metadata
+fn install_manifest(cache_path: &Path, manifest: &Manifest, manifest_path: &Path) -> Result<()> {
+    fs::create_dir_all(cache_path)?;
+    fs::copy(manifest_path, cache_path.join(MANIFEST_FILE))
+        .context("Failed to copy ruleset manifest")?;
+    println!("  Ruleset {} version {}", manifest.id, manifest.version);
+    Ok(())
+}
+
+/// Record of where a cached ruleset came from, written by `install` and
+/// compared by `install --check`
+const INSTALL_RECORD: &str = "install.toml";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct InstallRecord {
+    /// Source the config named at install time, e.g. `git+https://...`
+    source: String,
+    /// SHA-256 of the installed binary, hex encoded
+    checksum: String,
+}
+
+/// Stable description of a source, so a changed config entry is detectable
+fn source_label(source: RulesetSource) -> String {
+    match source {
+        RulesetSource::CratesIo => "crates-io".to_string(),
+        RulesetSource::Local(path) => format!("path+{}", path),
+        RulesetSource::Git(url) => format!("git+{}", url),
+        RulesetSource::Image(image) => format!("image+{}", image),
+        RulesetSource::Npm(package) => format!("npm+{}", package),
+        RulesetSource::Pip(package) => format!("pip+{}", package),
+    }
+}
+
+/// The ruleset binary or launcher in a ruleset's cache directory
+fn installed_binary(cache_path: &Path, id: &str) -> Option<PathBuf> {
+    [
+        cache_path
+            .join("bin")
+            .join(format!("forseti_ruleset_{}", id)),
+        launcher_path(cache_path, "ruleset", id),
+    ]
+    .into_iter()
+    .find(|path| path.is_file())
+}
+
+/// SHA-256 of a file, hex encoded
+fn file_checksum(path: &Path) -> Result<String> {
+    let mut file =
+        fs::File::open(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
 
-    Ok(base_path.join(id))
+fn get_cache_path(cache_dir: &Path, id: &str) -> PathBuf {
+    paths::extended(cache_dir.join(id))
 }
@@ -0,0 +1,98 @@
+use crate::commands::lint::collect_files;
+use crate::context::GlobalContext;
+use crate::languages::{self, Language};
+use crate::settings::Settings;
+use anyhow::Result;
+use forseti_sdk::config::Config;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+#[derive(Default)]
+struct LanguageStats {
+    files: usize,
+    bytes: u64,
+}
+
+pub fn run(ctx: &GlobalContext, path: &Path) -> Result<()> {
+    ctx.log_verbose(&format!("Scanning languages in: {}", path.display()));
+
+    // The config is optional here: without one we still report the breakdown
+    let config_path = ctx.resolve_config_path(path);
+    let config = if config_path.exists() {
+        ctx.log_verbose(&format!("Using config file: {}", config_path.display()));
+        Some(Settings::load(&config_path)?.load_config(&config_path, ctx.ci)?)
+    } else {
+        ctx.log_verbose("No config file found, ruleset coverage will not be shown");
+        None
+    };
+
+    let files = collect_files(&path.to_path_buf(), true, true)?;
+    ctx.log_verbose(&format!("Found {} file(s)", files.len()));
+
+    let mut stats: HashMap<Option<&'static str>, LanguageStats> = HashMap::new();
+    for file in &files {
+        let language = languages::detect_file(file).map(|l| l.id);
+        let entry = stats.entry(language).or_default();
+        entry.files += 1;
+        entry.bytes += fs::metadata(file).map(|m| m.len()).unwrap_or(0);
+    }
+
+    let mut rows: Vec<(Option<&'static Language>, LanguageStats)> = stats
+        .into_iter()
+        .map(|(id, s)| (id.and_then(languages::by_id), s))
+        .collect();
+    // Largest languages first, unrecognized files always last
+    rows.sort_by(|(la, a), (lb, b)| {
+        la.is_none()
+            .cmp(&lb.is_none())
+            .then_with(|| b.files.cmp(&a.files))
+            .then_with(|| la.map(|l| l.name).cmp(&lb.map(|l| l.name)))
+    });
+
+    println!("{:<14} {:>8} {:>12}  Ruleset", "Language", "Files", "Size");
+    for (language, stats) in &rows {
+        let (name, coverage) = match language {
+            Some(language) => (language.name, ruleset_coverage(language, config.as_ref())),
+            None => ("Other", String::new()),
+        };
+        println!(
+            "{:<14} {:>8} {:>12}  {}",
+            name,
+            stats.files,
+            format_size(stats.bytes),
+            coverage
+        );
+    }
+
+    println!();
+    println!("Total: {} file(s)", files.len());
+    Ok(())
+}
+
+/// Which configured ruleset (if any) covers a language
+fn ruleset_coverage(language: &Language, config: Option<&Config>) -> String {
+    match config {
+        Some(config) => match config.ruleset.get(language.id) {
+            Some(cfg) if cfg.enabled => language.id.to_string(),
+            Some(_) => format!("{} (disabled)", language.id),
+            None => "(none)".to_string(),
+        },
+        None => "-".to_string(),
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
@@ -1,534 +1,4675 @@
-use crate::commands::OutputFormat;
+use crate::artifacts::{Artifact, WrittenArtifact};
+use crate::cache::{CachedResult, FileStamp, IncrementalFile, IncrementalState, ResultCache};
+use crate::commands::output::{
+    Report, SarifBaseline, jsonl_line, output_results, pair_outputs, write_step_summary,
+};
+use crate::commands::{OutputFormat, OutputSpec};
+use crate::commands::{daemon, install};
+use crate::container;
 use crate::context::GlobalContext;
+use crate::filters::{self, FileScope, ReportFilters};
+use crate::fingerprint;
+use crate::git;
+use crate::i18n::Msg;
+use crate::languages::{self, Language};
+use crate::line_index::LineIndex;
+use crate::manifest::{MANIFEST_FILE, Manifest, PROTOCOL_VERSION};
+use crate::metadata::RunMetadata;
+use crate::paths;
+use crate::rules::{self, RuleAliases};
+use crate::settings::{ScoreSettings, Settings};
+use crate::state;
+#[cfg(feature = "store")]
+use crate::store::{ResultStore, StoredDiagnostic};
+use crate::suppressions::{self, Aged, Expired, Suppressions, Verdict};
 use anyhow::{Context, Result};
+use clap::{Args, ValueEnum};
 use forseti_sdk::config::Config;
 use forseti_sdk::core::Diagnostic;
-use serde_json::{json, Value};
+use globset::GlobSet;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
-use std::process::{Command, Stdio};
-use std::io::{BufRead, BufReader, Write};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
 
-/// Basic lint command implementation
-pub fn run(
-    ctx: &GlobalContext,
-    path: &PathBuf,
-    _fix: bool,
-    recursive: bool,
-    output: OutputFormat,
-    output_file: Option<PathBuf>,
-) -> Result<()> {
-    ctx.log_verbose(&format!("Starting lint operation in: {}", path.display()));
-    let config_path = ctx.resolve_config_path(path);
-    ctx.log_verbose(&format!("Using config file: {}", config_path.display()));
+/// Exit code used when `--strict-plugins` is set and a plugin misbehaved
+const EXIT_PLUGIN_ANOMALY: i32 = 3;
 
-    if !config_path.exists() {
-        return Err(anyhow::anyhow!(
-            "No .forseti.toml found at {}. Run 'forseti init' first.",
-            config_path.display()
-        ));
-    }
+/// Protocol lines buffered from a ruleset before its stdout stops being read
+const MESSAGE_BUFFER: usize = 64;
 
-    // Load configuration
-    ctx.log_verbose("Loading configuration...");
-    let config = Config::load_from_path(&config_path).context("Failed to load configuration")?;
+/// Largest protocol line accepted from a ruleset
+const MAX_MESSAGE_BYTES: usize = 16 * 1024 * 1024;
 
-    // Get cache directory for rulesets
-    let cache_dir = dirs::home_dir()
-        .ok_or_else(|| anyhow::anyhow!("Unable to determine home directory"))?
-        .join(".forseti")
-        .join("cache");
+/// Most files sent to a ruleset in one `analyzeBatch` request
+const MAX_BATCH_FILES: usize = 32;
 
-    ctx.log_verbose("Discovering rulesets...");
+/// How often accessible mode reports how far a run got
+const PROGRESS_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
 
-    // Discover available rulesets
-    let rulesets = discover_rulesets(&cache_dir, &config)?;
-    ctx.log_verbose(&format!("Found {} ruleset(s)", rulesets.len()));
+#[derive(Args, Debug)]
+pub struct LintArgs {
+    /// Path to lint (file or directory). Defaults to current directory.
+    #[arg(default_value = ".")]
+    pub path: PathBuf,
 
-    // Collect files to lint
-    let files = collect_files(path, recursive)?;
-    ctx.log_verbose(&format!("Found {} file(s) to lint", files.len()));
+    /// Apply automatic fixes where possible (experimental)
+    #[arg(long)]
+    pub fix: bool,
 
-    let mut file_results = Vec::new();
+    /// Recursively scan all subdirectories
+    #[arg(short, long)]
+    pub recursive: bool,
 
-    // Process files with rulesets
-    for file_path in files {
-        ctx.log_verbose(&format!("Processing: {}", file_path.display()));
+    /// Also lint files that .gitignore, .ignore or git's excludes leave out,
+    /// which are skipped by default
+    #[arg(long)]
+    pub no_ignore: bool,
 
-        // Read file content
-        let content = fs::read_to_string(&file_path)
-            .with_context(|| format!("Failed to read file: {}", file_path.display()))?;
+    /// Only lint files git reports as added or modified since BASE (default
+    /// `origin/main`, else `HEAD`), uncommitted and untracked ones included,
+    /// anywhere under the path
+    #[arg(
+        long,
+        value_name = "BASE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = ""
+    )]
+    pub changed: Option<String>,
 
-        let file_uri = format!("file://{}", file_path.display());
+    /// Only lint files staged for the next commit, anywhere under the path,
+    /// as staged rather than as they are in the work tree; for pre-commit hooks
+    #[arg(long, conflicts_with = "changed")]
+    pub staged: bool,
 
-        // Try each enabled ruleset
-        for ruleset in &rulesets {
-            if let Some(ruleset_cfg) = config.ruleset.get(&ruleset.id) {
-                if ruleset_cfg.enabled {
-                    ctx.log_verbose(&format!(
-                        "Trying ruleset {} for file {}",
-                        ruleset.id,
-                        file_path.display()
-                    ));
+    /// Lint every file of the git tree REV names (a commit, tag or tree)
+    /// under the path, read from the object store without a checkout; for
+    /// pre-receive hooks, which can run in a bare repository
+    #[arg(long, value_name = "REV", conflicts_with_all = ["changed", "staged"])]
+    pub git_tree: Option<String>,
 
-                    match analyze_file_with_ruleset(ctx, ruleset, &file_uri, &content, &ruleset_cfg.config) {
-                        Ok(diagnostics) => {
-                            ctx.log_verbose(&format!(
-                                "Ruleset {} processed {} and found {} diagnostic(s)",
-                                ruleset.id,
-                                file_path.display(),
-                                diagnostics.len()
-                            ));
-                            for diagnostic in &diagnostics {
-                                ctx.log_verbose(&format!(
-                                    "  Diagnostic: {} at {}:{} - {}",
-                                    diagnostic.rule_id,
-                                    diagnostic.range.start.line + 1,
-                                    diagnostic.range.start.character + 1,
-                                    diagnostic.message
-                                ));
-                            }
-                            if !diagnostics.is_empty() {
-                                file_results.push((
-                                    file_path.clone(),
-                                    diagnostics,
-                                    ruleset.id.clone(),
-                                ));
-                            }
-                        }
-                        Err(e) => {
-                            ctx.log_verbose(&format!(
-                                "Ruleset {} failed for file {}: {}",
-                                ruleset.id,
-                                file_path.display(),
-                                e
-                            ));
-                        }
-                    }
-                } else {
-                    ctx.log_verbose(&format!("Ruleset {} is disabled", ruleset.id));
-                }
-            } else {
-                ctx.log_verbose(&format!("No configuration found for ruleset {}", ruleset.id));
-            }
-        }
-    }
+    /// Drop diagnostics that don't touch a line added or modified since BASE
+    /// (chosen like `--changed`), so only issues a change introduces are
+    /// reported and fail the run
+    #[arg(
+        long,
+        value_name = "BASE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = ""
+    )]
+    pub diff_filter: Option<String>,
 
-    // Count total diagnostics
-    let total_diagnostics = file_results
-        .iter()
-        .map(|(_, diags, _)| diags.len())
-        .sum::<usize>();
+    /// Lint content read from stdin as if it were the file named by
+    /// `--stdin-filename`, e.g. an editor's unsaved buffer; the config is
+    /// still found from the path
+    #[arg(
+        long,
+        requires = "stdin_filename",
+        conflicts_with_all = ["changed", "staged", "git_tree", "diff_filter"]
+    )]
+    pub stdin: bool,
 
-    // Output results
-    output_results(ctx, &file_results, total_diagnostics, output, output_file)?;
+    /// File the `--stdin` content stands for, which decides the rulesets and
+    /// rules that apply and is the path diagnostics are reported against
+    #[arg(long, value_name = "PATH", requires = "stdin")]
+    pub stdin_filename: Option<PathBuf>,
 
-    // Return error code if there were diagnostics
-    if total_diagnostics > 0 && config.linter.fail_on_error {
-        std::process::exit(1);
+    /// Output format for results: text, json, json-canonical, junit, sarif, github,
+    /// gitlab, codeclimate, tap, teamcity, compact, csv, or `exec:<command>` to pipe
+    /// the canonical JSON report through an external formatter. Repeat for several
+    /// reports.
+    #[arg(short, long, default_value = "text")]
+    pub output: Vec<OutputSpec>,
+
+    /// Write results to file (defaults to stdout). When repeated, files go to the
+    /// last outputs in order, e.g. `-o text -o sarif --output-file report.sarif`
+    #[arg(long)]
+    pub output_file: Vec<PathBuf>,
+
+    /// Fail the run (exit code 3) on any plugin crash, timeout, protocol violation or dropped diagnostic
+    #[arg(long)]
+    pub strict_plugins: bool,
+
+    /// Record which ruleset process and request produced each diagnostic, written
+    /// next to the report as `<output-file>.provenance.json` (stderr without one)
+    #[arg(long)]
+    pub debug_provenance: bool,
+
+    /// Show the offending source lines under each diagnostic in text output
+    #[arg(long)]
+    pub code_frame: bool,
+
+    /// Group text output under a header per file or per rule, with counts
+    #[arg(long, value_enum)]
+    pub group_by: Option<GroupBy>,
+
+    /// Only report diagnostics at or above this severity; the others are counted
+    /// as hidden and still fail the run
+    #[arg(long, value_enum)]
+    pub severity: Option<SeverityThreshold>,
+
+    /// How text, JSON and JUnit reports write file paths (as discovered when unset)
+    #[arg(long, value_enum)]
+    pub path_style: Option<PathStyle>,
+
+    /// Stop printing after this many diagnostics and count the rest; reports
+    /// written to files stay complete and the exit code reflects every finding
+    #[arg(long, value_name = "N")]
+    pub max_diagnostics: Option<usize>,
+
+    /// Lint only this share of the files, e.g. `10%`, to estimate lint health
+    /// or trial a ruleset on a huge codebase; the same seed picks the same files
+    #[arg(long, value_name = "PERCENT", value_parser = parse_percent)]
+    pub sample: Option<f64>,
+
+    /// Seed for `--sample` (defaults to 0), also handed to rulesets as the
+    /// run seed (random unless given or `--deterministic`)
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Make repeated runs on identical input byte-identical, for build system
+    /// caching: rulesets get a fixed seed and a normalized environment,
+    /// events carrying wall-clock times are rejected, and the run id and
+    /// start time are derived from the inputs
+    #[arg(long)]
+    pub deterministic: bool,
+
+    /// Fail the run when the lint score (0-100) is below this, overriding
+    /// `[score] min`
+    #[arg(long, value_name = "SCORE")]
+    pub min_score: Option<f64>,
+
+    /// Earlier SARIF report whose findings are marked as suppressed in SARIF
+    /// output, so code scanning only flags new ones
+    #[arg(long, value_name = "FILE")]
+    pub sarif_baseline: Option<PathBuf>,
+
+    /// What happens to an existing `--output-file`
+    #[arg(long, value_enum, default_value = "overwrite")]
+    pub output_mode: OutputMode,
+
+    /// Files linted at once, overriding `linter.parallelism` (0 uses every CPU)
+    #[arg(short, long, value_name = "N")]
+    pub jobs: Option<usize>,
+
+    /// Add issue counts per directory to the text summary: `dir` for top-level
+    /// directories, `dir:2` for two levels down
+    #[arg(long, value_name = "dir[:DEPTH]", value_parser = parse_summary_grouping)]
+    pub group_summary_by: Option<usize>,
+
+    /// Analyze every file, neither reading nor writing `.forseti/cache/results`
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// Only run rulesets on files changed since the last incremental run,
+    /// reusing its results for the rest
+    #[arg(long)]
+    pub incremental: bool,
+
+    /// Also record the run and its diagnostics in a SQLite database, created
+    /// if needed, which accumulates runs for queries by file, rule or commit
+    #[cfg(feature = "store")]
+    #[arg(long, value_name = "FILE")]
+    pub store: Option<PathBuf>,
+
+    /// Write the artifacts rulesets produce, e.g. reports or graphs, under
+    /// DIR/<ruleset>/ instead of .forseti/artifacts/
+    #[arg(long, value_name = "DIR")]
+    pub artifacts_dir: Option<PathBuf>,
+
+    /// Skip files larger than SIZE (e.g. `512KB`, `10MB`) instead of reading
+    /// them and sending them to rulesets; overrides `[linter] max_file_size`
+    #[arg(long, value_name = "SIZE", value_parser = parse_file_size)]
+    pub max_file_size: Option<u64>,
+}
+
+/// How `--output-mode` treats a report file that already exists
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputMode {
+    /// Replace it
+    Overwrite,
+    /// Add this run's report after it
+    Append,
+    /// Combine it with this run's report (json and sarif only)
+    Merge,
+}
+
+/// `--sample` percentage, from `10%` or `10`
+fn parse_percent(text: &str) -> Result<f64, String> {
+    let percent: f64 = text
+        .trim_end_matches('%')
+        .parse()
+        .map_err(|_| format!("'{}' is not a percentage like 10%", text))?;
+    if percent > 0.0 && percent <= 100.0 {
+        Ok(percent)
+    } else {
+        Err(format!("'{}' must be above 0% and at most 100%", text))
     }
+}
 
-    Ok(())
+/// `--max-file-size` in bytes, from `2048`, `512KB`, `10MB`, ...
+fn parse_file_size(text: &str) -> Result<u64, String> {
+    crate::settings::parse_size(text).map_err(|e| e.to_string())
 }
 
-fn collect_files(path: &PathBuf, recursive: bool) -> Result<Vec<PathBuf>> {
-    let mut files = Vec::new();
+/// Directory depth for `--group-summary-by`, from `dir` or `dir:<depth>`
+fn parse_summary_grouping(text: &str) -> Result<usize, String> {
+    let depth = match text.split_once(':') {
+        Some(("dir", depth)) => depth
+            .parse()
+            .map_err(|_| format!("'{}' is not a directory depth", depth))?,
+        None if text == "dir" => 1,
+        _ => return Err(format!("'{}' is not dir or dir:<depth>", text)),
+    };
+    if depth == 0 {
+        return Err("directory depth must be at least 1".to_string());
+    }
+    Ok(depth)
+}
 
-    if path.is_file() {
-        files.push(path.clone());
-    } else if path.is_dir() {
-        if recursive {
-            for entry in walkdir::WalkDir::new(path)
-                .into_iter()
-                .filter_map(|e| e.ok())
-            {
-                if entry.file_type().is_file() {
-                    files.push(entry.into_path());
-                }
-            }
-        } else {
-            for entry in fs::read_dir(path)? {
-                let entry = entry?;
-                if entry.file_type()?.is_file() {
-                    files.push(entry.path());
-                }
-            }
+/// How `--group-by` arranges text output
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GroupBy {
+    /// One section per file, findings in line order
+    File,
+    /// One section per rule, noisiest rule first
+    Rule,
+}
+
+/// Lowest severity `--severity` lets into reports
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SeverityThreshold {
+    Error,
+    Warn,
+    Info,
+}
+
+impl SeverityThreshold {
+    fn name(self) -> &'static str {
+        match self {
+            SeverityThreshold::Error => "error",
+            SeverityThreshold::Warn => "warn",
+            SeverityThreshold::Info => "info",
         }
     }
 
-    Ok(files)
+    fn admits(self, severity: &str) -> bool {
+        severity_rank(severity) >= severity_rank(self.name())
+    }
+}
+
+/// Order of severities from `hint` up to `error`; unknown ones rank as `warn`
+fn severity_rank(severity: &str) -> u8 {
+    match severity {
+        "error" => 3,
+        "info" => 1,
+        HINT => 0,
+        _ => 2,
+    }
+}
+
+/// How `--path-style` writes file paths
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PathStyle {
+    /// Relative to the current directory
+    Relative,
+    /// Absolute, with symlinks resolved
+    Absolute,
+    /// Relative to the workspace root, the same on every machine
+    FromRoot,
+}
+
+/// How many files a lint pass saw and what became of them
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct FileCounts {
+    /// Files collected from the lint path
+    pub discovered: usize,
+    /// Files at least one ruleset analyzed successfully
+    pub analyzed: usize,
+    /// Files no ruleset analyzed (unreadable, binary, or every ruleset failed)
+    pub skipped: usize,
+    /// Files with at least one reported diagnostic
+    pub with_issues: usize,
+}
+
+/// Work one ruleset did during a lint pass
+#[derive(Debug, Default, Clone)]
+pub(crate) struct RulesetStats {
+    pub files: usize,
+    pub elapsed: std::time::Duration,
+}
+
+/// Something that went wrong while talking to a ruleset process
+#[derive(Debug)]
+struct PluginAnomaly {
+    ruleset_id: String,
+    /// File being analyzed, `None` for a process failing at shutdown or an
+    /// artifact that couldn't be written
+    file_path: Option<PathBuf>,
+    message: String,
 }
 
+/// A diagnostic together with the metadata forseti attaches to it
 #[derive(Debug, Clone)]
-struct RulesetInfo {
-    id: String,
-    binary_path: PathBuf,
+pub(crate) struct Finding {
+    pub diagnostic: Diagnostic,
+    /// Stable identity across runs, see [`fingerprint::compute`]
+    pub fingerprint: String,
+    /// One-based start column as editors display it, with tabs expanded to
+    /// `[output] tab_width`
+    pub column: usize,
+    pub provenance: Provenance,
 }
 
-fn discover_rulesets(cache_dir: &PathBuf, config: &Config) -> Result<Vec<RulesetInfo>> {
-    let mut rulesets = Vec::new();
+/// Where a diagnostic came from, for triaging duplicated or missing findings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Provenance {
+    pub ruleset_id: String,
+    /// Process id of the ruleset process, 0 for results reused from the cache
+    pub pid: u32,
+    pub request_id: String,
+    /// Position of the diagnostics event among those answering the request
+    pub event: usize,
+    /// When the request was sent (RFC 3339)
+    pub requested_at: String,
+    /// When the event carrying the diagnostic was received (RFC 3339)
+    pub received_at: String,
+}
 
-    // First, check for rulesets configured with local paths
-    for (ruleset_id, ruleset_cfg) in &config.ruleset {
-        if let Some(local_path) = &ruleset_cfg.path {
-            let path = PathBuf::from(local_path);
-            if path.exists() && path.is_file() {
-                rulesets.push(RulesetInfo {
-                    id: ruleset_id.clone(),
-                    binary_path: path,
-                });
-            }
+/// Findings one ruleset reported for one file
+#[derive(Debug, Clone)]
+pub(crate) struct FileResult {
+    pub path: PathBuf,
+    pub ruleset_id: String,
+    pub findings: Vec<Finding>,
+    /// The content that was analyzed, shared by the results for one file;
+    /// with `--stdin`, `--staged` or `--git-tree` the file on disk may differ
+    /// or be missing
+    pub source: Arc<str>,
+}
+
+/// Diagnostics returned by a ruleset for a single file
+#[derive(Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Analysis {
+    pub diagnostics: Vec<Diagnostic>,
+    /// Where each diagnostic came from, in the same order
+    provenance: Vec<Provenance>,
+    /// Diagnostics the ruleset sent that could not be decoded
+    pub dropped: usize,
+    /// Non-protocol output the ruleset wrote to stdout, skipped while reading
+    pub framing_errors: Vec<String>,
+    /// Version the ruleset reported during initialize
+    pub version: Option<String>,
+    /// Numbers from `metrics` events, summed per name
+    pub metrics: std::collections::BTreeMap<String, f64>,
+    /// Files from `artifact` events, in the order they arrived
+    pub artifacts: Vec<Artifact>,
+    /// How the process exited when it died before answering, keeping the
+    /// diagnostics it sent until then
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit: Option<String>,
+}
+
+impl Analysis {
+    /// An analysis answered from the result cache, attributed to pid 0
+    fn from_cache(ruleset_id: &str, cached: CachedResult) -> Self {
+        let now = timestamp();
+        let provenance = cached
+            .diagnostics
+            .iter()
+            .map(|_| Provenance {
+                ruleset_id: ruleset_id.to_string(),
+                pid: 0,
+                request_id: "cache".to_string(),
+                event: 0,
+                requested_at: now.clone(),
+                received_at: now.clone(),
+            })
+            .collect();
+        Self {
+            diagnostics: cached.diagnostics,
+            provenance,
+            metrics: cached.metrics,
+            artifacts: cached.artifacts,
+            version: cached.version,
+            ..Self::default()
         }
     }
 
-    // Then, look for rulesets in cache directory
-    if cache_dir.exists() {
-        let entries = fs::read_dir(cache_dir)?;
-        for entry in entries {
-            let entry = entry?;
-            let path = entry.path();
+    /// Whether the ruleset answered without anomalies, so the result can be
+    /// reused; otherwise the anomalies should show up again next run
+    fn clean(&self) -> bool {
+        self.dropped == 0 && self.framing_errors.is_empty() && self.exit.is_none()
+    }
 
-            if path.is_dir() {
-                let bin_dir = path.join("bin");
-                if bin_dir.exists() {
-                    let bin_entries = fs::read_dir(bin_dir)?;
-                    for bin_entry in bin_entries {
-                        let bin_entry = bin_entry?;
-                        let bin_path = bin_entry.path();
+    fn to_cache(&self) -> CachedResult {
+        CachedResult {
+            diagnostics: self.diagnostics.clone(),
+            metrics: self.metrics.clone(),
+            artifacts: self.artifacts.clone(),
+            version: self.version.clone(),
+        }
+    }
+}
 
-                        if bin_path.is_file() {
-                            let file_name = bin_path.file_name().unwrap().to_string_lossy();
-                            if file_name.starts_with("forseti_ruleset_") {
-                                let ruleset_id = file_name
-                                    .strip_prefix("forseti_ruleset_")
-                                    .unwrap()
-                                    .to_string();
+/// Everything a lint pass needs to know about the project being linted
+pub(crate) struct Workspace {
+    pub config_path: PathBuf,
+    pub config: Config,
+    pub settings: Settings,
+    /// Directory containing the config file, used to relativize paths
+    pub root: PathBuf,
+    rulesets: Vec<RulesetInfo>,
+    /// Sessions for `preload = true` rulesets, initializing in the background
+    preloaded: Mutex<HashMap<String, JoinHandle<Result<RulesetSession>>>>,
+    /// Where file content comes from in `--staged`, `--git-tree` and
+    /// `--stdin` runs
+    content: Option<Content>,
+    /// A running `forseti daemon` analyzing files with its warm rulesets
+    daemon: Option<daemon::Client>,
+    /// Seed and determinism every ruleset is initialized with
+    run: RunInputs,
+    /// Files larger than this many bytes are skipped unread
+    max_file_size: Option<u64>,
+}
 
-                                // Only add if not already found via local path
-                                if !rulesets.iter().any(|r| r.id == ruleset_id) {
-                                    rulesets.push(RulesetInfo {
-                                        id: ruleset_id,
-                                        binary_path: bin_path,
-                                    });
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+/// File content read from somewhere other than the work tree
+enum Content {
+    Git(GitContent),
+    /// A `--stdin` buffer, standing for the file at `path`
+    Stdin {
+        path: PathBuf,
+        /// `None` when stdin held more than the maximum file size
+        text: Option<String>,
+        /// Bytes read from stdin
+        size: u64,
+    },
+}
+
+impl Content {
+    /// The files there is content for, which are the ones to lint
+    fn files(&self) -> Vec<PathBuf> {
+        match self {
+            Content::Git(git_content) => git_content.blobs.keys().cloned().collect(),
+            Content::Stdin { path, .. } => vec![path.clone()],
         }
     }
+}
 
-    Ok(rulesets)
+/// Blobs of the files a `--staged` or `--git-tree` run lints, read instead
+/// of the work tree so a hook checks exactly what will be committed or pushed
+struct GitContent {
+    toplevel: PathBuf,
+    /// Blob ids by path as handed to the rulesets
+    blobs: HashMap<PathBuf, String>,
 }
 
-fn analyze_file_with_ruleset(
-    _ctx: &GlobalContext,
-    ruleset: &RulesetInfo,
-    file_uri: &str,
-    content: &str,
-    config: &toml::value::Table,
-) -> Result<Vec<Diagnostic>> {
-    // Start the ruleset process
-    let mut child = Command::new(&ruleset.binary_path)
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .with_context(|| format!("Failed to start ruleset: {}", ruleset.id))?;
-
-    let stdin = child.stdin.take().unwrap();
-    let stdout = child.stdout.take().unwrap();
-
-    // Create channels for communication
-    let (tx, rx) = std::sync::mpsc::channel();
-
-    // Start thread to read responses
-    let tx_clone = tx.clone();
-    std::thread::spawn(move || {
-        let reader = BufReader::new(stdout);
-        for line in reader.lines() {
-            if let Ok(line) = line {
-                if tx_clone.send(line).is_err() {
-                    break;
-                }
-            }
+impl Workspace {
+    /// Resolve and load the configuration for `path` and discover its rulesets
+    pub(crate) fn load(ctx: &GlobalContext, path: &Path) -> Result<Self> {
+        Self::open(ctx, path, true, RunInputs::new(None, false))
+    }
+
+    /// [`Workspace::load`], handing analysis to a running daemon when
+    /// `delegate` allows and one is listening, and initializing rulesets
+    /// with `run`
+    pub(crate) fn open(
+        ctx: &GlobalContext,
+        path: &Path,
+        delegate: bool,
+        run: RunInputs,
+    ) -> Result<Self> {
+        let config_path = ctx.resolve_config_path(path);
+        ctx.log_verbose(&format!("Using config file: {}", config_path.display()));
+
+        if !config_path.exists() {
+            return Err(anyhow::anyhow!(
+                ctx.lang.format(Msg::NoConfig, &[&config_path.display()])
+            ));
         }
-    });
 
-    // Send initialization request
-    let mut writer = stdin;
-    let init_request = json!({
-        "v": 1,
-        "kind": "req",
-        "type": "initialize",
-        "id": "init",
-        "payload": {
-            "rulesetId": ruleset.id,
-            "workspaceRoot": ".",
-            "rulesetConfig": config
+        // Load configuration
+        ctx.log_verbose("Loading configuration...");
+        let settings = Settings::load(&config_path)?;
+        let config = settings.load_config(&config_path, ctx.ci)?;
+        let root = paths::canonicalize(&state::workspace_root(&config_path));
+        let max_file_size = settings.linter.max_file_size()?;
+
+        // Get cache directory for rulesets
+        let cache_dir = ctx.ruleset_cache_dir()?;
+
+        ctx.log_verbose("Discovering rulesets...");
+
+        // Discover available rulesets
+        let system_dirs = ctx.system_plugin_dirs();
+        ctx.log_verbose(&format!("System ruleset directories: {:?}", system_dirs));
+        let rulesets = discover_rulesets(&cache_dir, &system_dirs, &config, &settings, &root)?;
+        ctx.log_verbose(&format!("Found {} ruleset(s)", rulesets.len()));
+
+        let daemon = if delegate {
+            daemon::config_hash(&config_path).and_then(|hash| daemon::Client::find(&root, &hash))
+        } else {
+            None
+        };
+        if let Some(daemon) = &daemon {
+            ctx.log_verbose(&format!("Analyzing files with the daemon at {}", daemon));
         }
-    });
 
-    writeln!(writer, "{}", serde_json::to_string(&init_request)?)?;
+        // Start heavy rulesets now so their startup overlaps file collection,
+        // unless the daemon already keeps them running
+        let mut preloaded = HashMap::new();
+        for ruleset in rulesets.iter().filter(|_| daemon.is_none()) {
+            let Some(ruleset_cfg) = config.ruleset.get(&ruleset.id) else {
+                continue;
+            };
+            if ruleset_cfg.enabled && settings.ruleset(&ruleset.id).preload {
+                ctx.log_verbose(&format!("Preloading ruleset {}", ruleset.id));
+                let ruleset = ruleset.clone();
+                let ruleset_config = ruleset_cfg.config.clone();
+                preloaded.insert(
+                    ruleset.id.clone(),
+                    std::thread::spawn(move || {
+                        RulesetSession::start(&ruleset, &ruleset_config, run)
+                    }),
+                );
+            }
+        }
 
-    // Wait for initialization response
-    let init_response = rx.recv_timeout(std::time::Duration::from_secs(5))
-        .context("Timeout waiting for initialization response")?;
-    let _init_res: Value = serde_json::from_str(&init_response)?;
+        Ok(Self {
+            config_path,
+            config,
+            settings,
+            root,
+            rulesets,
+            preloaded: Mutex::new(preloaded),
+            content: None,
+            daemon,
+            run,
+            max_file_size,
+        })
+    }
 
-    // Send analyze file request
-    let analyze_request = json!({
-        "v": 1,
-        "kind": "req",
-        "type": "analyzeFile",
-        "id": "analyze",
-        "payload": {
-            "uri": file_uri,
-            "content": content
+    /// An enabled ruleset with its config
+    pub(crate) fn enabled_ruleset(
+        &self,
+        ruleset_id: &str,
+    ) -> Option<(&RulesetInfo, &toml::value::Table)> {
+        let ruleset = self
+            .rulesets
+            .iter()
+            .find(|ruleset| ruleset.id == ruleset_id)?;
+        let ruleset_cfg = self.config.ruleset.get(ruleset_id)?;
+        ruleset_cfg
+            .enabled
+            .then_some((ruleset, &ruleset_cfg.config))
+    }
+
+    /// Take the preloaded session for a ruleset, waiting for it to finish initializing
+    fn take_preloaded(&self, ruleset_id: &str) -> Option<Result<RulesetSession>> {
+        let handle = self.preloaded.lock().unwrap().remove(ruleset_id)?;
+        Some(handle.join().unwrap_or_else(|_| {
+            Err(anyhow::anyhow!(
+                "Preloading ruleset {} panicked",
+                ruleset_id
+            ))
+        }))
+    }
+
+    /// Files to lint at once: `requested` when given, else `linter.parallelism`,
+    /// with 0 meaning one per available CPU
+    pub(crate) fn jobs(&self, requested: Option<usize>) -> usize {
+        match requested.unwrap_or(usize::from(self.config.linter.parallelism)) {
+            0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+            jobs => jobs,
         }
-    });
+    }
 
-    writeln!(writer, "{}", serde_json::to_string(&analyze_request)?)?;
-
-    // Collect diagnostics
-    let mut diagnostics = Vec::new();
-    let mut analyze_complete = false;
-
-    while !analyze_complete {
-        let response = rx.recv_timeout(std::time::Duration::from_secs(10))
-            .context("Timeout waiting for analysis response")?;
-        let msg: Value = serde_json::from_str(&response)?;
-
-        if let Some(kind) = msg.get("kind").and_then(|k| k.as_str()) {
-            match kind {
-                "event" => {
-                    if let Some(msg_type) = msg.get("type").and_then(|t| t.as_str()) {
-                        if msg_type == "diagnostics" {
-                            if let Some(payload) = msg.get("payload") {
-                                if let Some(diags) = payload.get("diagnostics").and_then(|d| d.as_array()) {
-                                    for diag in diags {
-                                        if let Ok(diagnostic) = serde_json::from_value::<Diagnostic>(diag.clone()) {
-                                            diagnostics.push(diagnostic);
-                                        }
-                                    }
-                                }
-                            }
-                        }
+    /// Rules each enabled ruleset lists when initialized, `None` for rulesets
+    /// that don't list them, aren't installed or fail to start
+    pub(crate) fn available_rules(
+        &self,
+    ) -> std::collections::BTreeMap<String, Option<Vec<String>>> {
+        self.config
+            .ruleset
+            .iter()
+            .filter(|(_, ruleset_cfg)| ruleset_cfg.enabled)
+            .map(|(id, ruleset_cfg)| {
+                let session = self.take_preloaded(id).or_else(|| {
+                    let ruleset = self.rulesets.iter().find(|r| &r.id == id)?;
+                    Some(RulesetSession::start(
+                        ruleset,
+                        &ruleset_cfg.config,
+                        self.run,
+                    ))
+                });
+                let rules = match session {
+                    Some(Ok(mut session)) => {
+                        let rules = session.rules.take();
+                        let _ = session.shutdown();
+                        rules
                     }
+                    _ => None,
+                };
+                (id.clone(), rules)
+            })
+            .collect()
+    }
+
+    /// Check every enabled ruleset is installed, launchable and answers the
+    /// initialize handshake, without analyzing any files
+    pub(crate) fn verify_rulesets(&self) -> Vec<RulesetCheck> {
+        let mut enabled: Vec<_> = self
+            .config
+            .ruleset
+            .iter()
+            .filter(|(_, ruleset_cfg)| ruleset_cfg.enabled)
+            .collect();
+        enabled.sort_by_key(|(id, _)| id.as_str());
+
+        enabled
+            .into_iter()
+            .map(|(id, ruleset_cfg)| {
+                let started = std::time::Instant::now();
+                let mut check = RulesetCheck {
+                    ruleset_id: id.clone(),
+                    source: None,
+                    version: None,
+                    elapsed: std::time::Duration::ZERO,
+                    problem: None,
+                };
+                let Some(ruleset) = self.rulesets.iter().find(|r| &r.id == id) else {
+                    check.problem = Some("not installed (run `forseti install`)".to_string());
+                    return check;
+                };
+                check.source = Some(match &ruleset.launcher {
+                    Launcher::Binary(path) => paths::display(path),
+                    Launcher::Container { image, .. } => format!("image {}", image),
+                });
+                if let Launcher::Binary(path) = &ruleset.launcher
+                    && !is_executable(path)
+                {
+                    check.problem = Some("binary is not executable".to_string());
+                    return check;
                 }
-                "res" => {
-                    if let Some(id) = msg.get("id").and_then(|i| i.as_str()) {
-                        if id == "analyze" {
-                            analyze_complete = true;
+
+                let session = self.take_preloaded(id).unwrap_or_else(|| {
+                    RulesetSession::start(ruleset, &ruleset_cfg.config, self.run)
+                });
+                match session {
+                    Ok(session) => {
+                        check.version = session.version.clone();
+                        if let Err(e) = session.shutdown() {
+                            check.problem = Some(format!("shutdown failed: {:#}", e));
+                        } else if let (Some(declared), Some(reported)) =
+                            (&ruleset.version, &check.version)
+                            && declared != reported
+                        {
+                            check.problem = Some(format!(
+                                "manifest declares version {} but the ruleset reports {}",
+                                declared, reported
+                            ));
                         }
                     }
+                    Err(e) => check.problem = Some(format!("{:#}", e)),
                 }
-                _ => {}
-            }
-        }
+                check.elapsed = started.elapsed();
+                check
+            })
+            .collect()
     }
+}
 
-    // Send shutdown request
-    let shutdown_request = json!({
-        "v": 1,
-        "kind": "req",
-        "type": "shutdown",
-        "id": "shutdown"
-    });
-
-    let _ = writeln!(writer, "{}", serde_json::to_string(&shutdown_request)?);
+/// Outcome of `Workspace::verify_rulesets` for one ruleset
+pub(crate) struct RulesetCheck {
+    pub ruleset_id: String,
+    /// Binary path or container image the ruleset runs from
+    pub source: Option<String>,
+    /// Version reported during the handshake
+    pub version: Option<String>,
+    /// Time to start, initialize and shut down the ruleset
+    pub elapsed: std::time::Duration,
+    pub problem: Option<String>,
+}
 
-    // Wait for process to finish
-    let _ = child.wait();
+/// Everything a lint pass produced, before any reporting
+pub(crate) struct LintRun {
+    pub file_results: Vec<FileResult>,
+    anomalies: Vec<PluginAnomaly>,
+    suppressed_count: usize,
+    /// Findings muted by `[[output.filters]]`
+    filtered_count: usize,
+    expired_suppressions: Vec<Expired>,
+    /// Suppressions past the resurfacing age whose findings were reported
+    aged_suppressions: Vec<Aged>,
+    ambiguous_rules: Vec<AmbiguousRule>,
+    /// Version reported by each ruleset that ran
+    plugin_versions: std::collections::BTreeMap<String, String>,
+    pub ruleset_stats: std::collections::BTreeMap<String, RulesetStats>,
+    pub file_counts: FileCounts,
+    /// Files at least one ruleset analyzed, in lint order
+    pub analyzed_files: Vec<PathBuf>,
+    /// Lines across the analyzed files
+    analyzed_lines: usize,
+    /// Text files no ruleset received, or only rulesets for other languages
+    unclaimed_files: Vec<PathBuf>,
+    /// Time every ruleset together spent on each file
+    file_elapsed: Vec<(PathBuf, std::time::Duration)>,
+    /// Project-wide totals of the metrics rulesets reported, keyed by
+    /// qualified metric name
+    pub metrics: std::collections::BTreeMap<String, f64>,
+    /// Artifacts rulesets sent, with the ruleset that sent each
+    artifacts: Vec<(String, Artifact)>,
+}
 
-    Ok(diagnostics)
+/// A bare rule id in config that more than one ruleset reported findings for
+struct AmbiguousRule {
+    /// Where the id was written
+    origin: String,
+    rule: String,
+    rulesets: Vec<String>,
 }
 
-fn output_results(
-    _ctx: &GlobalContext,
-    file_results: &[(PathBuf, Vec<Diagnostic>, String)],
-    total_diagnostics: usize,
-    output: OutputFormat,
-    output_file: Option<PathBuf>,
-) -> Result<()> {
-    match output {
-        OutputFormat::Text => {
-            let mut error_count = 0;
-            let mut warn_count = 0;
-            let mut info_count = 0;
-            let mut files_with_issues = std::collections::HashSet::new();
-
-            for (file_path, diagnostics, ruleset_id) in file_results {
-                for diagnostic in diagnostics {
-                    // Count diagnostics by severity
-                    match diagnostic.severity.as_str() {
-                        "error" => error_count += 1,
-                        "warn" => warn_count += 1,
-                        "info" => info_count += 1,
-                        _ => warn_count += 1, // Default to warn for unknown severities
-                    }
+/// Basic lint command implementation
+pub fn run(ctx: &GlobalContext, args: LintArgs) -> Result<()> {
+    let LintArgs {
+        path,
+        fix: _fix,
+        recursive,
+        no_ignore,
+        changed,
+        staged,
+        git_tree,
+        diff_filter,
+        stdin,
+        stdin_filename,
+        output,
+        output_file,
+        strict_plugins,
+        debug_provenance,
+        code_frame,
+        group_by,
+        severity,
+        path_style,
+        max_diagnostics,
+        sample,
+        seed,
+        deterministic,
+        min_score,
+        sarif_baseline,
+        output_mode,
+        jobs,
+        group_summary_by,
+        no_cache,
+        incremental,
+        #[cfg(feature = "store")]
+        store,
+        artifacts_dir,
+        max_file_size,
+    } = args;
+    let started = std::time::Instant::now();
+    let path = &path;
+    let outputs = pair_outputs(output, output_file.clone())?;
 
-                    files_with_issues.insert(file_path.clone());
+    ctx.log_verbose(&format!("Starting lint operation in: {}", path.display()));
+    // A daemon's rulesets were started for other runs, so deterministic
+    // runs start their own
+    let run = RunInputs::new(seed, deterministic);
+    let mut workspace = Workspace::open(ctx, path, !deterministic, run)?;
+    if max_file_size.is_some() {
+        workspace.max_file_size = max_file_size;
+    }
+    let content = match (stdin_filename.filter(|_| stdin), &git_tree, staged) {
+        (Some(file), _, _) => Some(read_stdin(file, workspace.max_file_size)?),
+        (None, Some(rev), _) => Some(Content::Git(collect_tree_files(path, rev)?)),
+        (None, None, true) => Some(Content::Git(collect_staged_files(path)?)),
+        (None, None, false) => None,
+    };
+    let listed_files = content.as_ref().map(Content::files);
+    workspace.content = content;
+    let Workspace {
+        config_path,
+        config,
+        settings,
+        root: workspace_root,
+        ..
+    } = &workspace;
 
-                    let docs_part = if let Some(ref docs_url) = diagnostic.docs_url {
-                        format!(" ({})", docs_url)
-                    } else {
-                        String::new()
-                    };
+    // Collect files to lint
+    let mut files = match (&changed, listed_files) {
+        (Some(base), _) => collect_changed_files(path, base)?,
+        (None, Some(mut listed_files)) => {
+            listed_files.sort();
+            listed_files
+        }
+        (None, None) => collect_files(path, recursive, !no_ignore)?,
+    };
+    ctx.log_verbose(&format!("Found {} file(s) to lint", files.len()));
+    if let Some(percent) = sample {
+        let seed = seed.unwrap_or_default();
+        let found = files.len();
+        files = sample_files(files, percent, seed, workspace_root);
+        eprintln!(
+            "Sampling {} of {} file(s) ({}%, seed {})",
+            files.len(),
+            found,
+            percent,
+            seed
+        );
+    }
 
-                    println!(
-                        "{}:{}:{}: {} [{}@{}]{}",
-                        file_path.display(),
-                        diagnostic.range.start.line + 1,
-                        diagnostic.range.start.character + 1,
-                        diagnostic.message,
-                        diagnostic.rule_id,
-                        ruleset_id,
-                        docs_part
-                    );
-                }
+    let changed_lines = match diff_filter.as_deref() {
+        Some(base) => {
+            let target = match (&git_tree, staged) {
+                (Some(rev), _) => git::DiffTarget::Tree(rev),
+                (None, true) => git::DiffTarget::Staged,
+                (None, false) => git::DiffTarget::WorkTree,
+            };
+            let head = git_tree.as_deref().unwrap_or("HEAD");
+            let (toplevel, base, commit) = git_base(path, base, head)?;
+            eprintln!(
+                "Only reporting diagnostics on lines changed since {} ({})",
+                base,
+                &commit[..commit.len().min(12)]
+            );
+            Some(git::ChangedLines::since(&toplevel, &commit, target)?)
+        }
+        None => None,
+    };
+
+    let mut metadata = RunMetadata::capture(config_path, workspace_root)?;
+    if deterministic {
+        metadata.pin(source_date_epoch());
+    }
+    ctx.log_verbose(&format!("Run id: {}", metadata.run_id));
+
+    for file in &output_file {
+        ctx.check_write(file, workspace_root)?;
+    }
+    // Opened up front so a store that can't be used fails the run early
+    #[cfg(feature = "store")]
+    let mut store = match &store {
+        Some(file) => {
+            ctx.check_write(file, workspace_root)?;
+            Some((file, ResultStore::open(file)?))
+        }
+        None => None,
+    };
+    if let Some(dir) = &artifacts_dir {
+        ctx.check_write(dir, workspace_root)?;
+    }
+    let routes = &settings.output.routes;
+    for route in routes.values() {
+        if let Some(file) = &route.file {
+            ctx.check_write(&workspace_root.join(file), workspace_root)?;
+        }
+    }
+    // Routed rulesets get their own reports and leave the main one
+    let routed: std::collections::HashSet<&str> = routes
+        .values()
+        .flat_map(|route| route.rulesets.iter().map(String::as_str))
+        .collect();
+
+    if output_mode == OutputMode::Merge {
+        let route_specs = routes
+            .values()
+            .filter(|route| route.file.is_some())
+            .filter_map(|route| route.format.parse().ok());
+        let file_specs = outputs
+            .iter()
+            .filter(|(_, file)| file.is_some())
+            .map(|(spec, _)| spec.clone());
+        for spec in file_specs.chain(route_specs) {
+            if !matches!(
+                spec,
+                OutputSpec::Builtin(OutputFormat::Json | OutputFormat::Sarif)
+            ) {
+                return Err(anyhow::anyhow!(
+                    "--output-mode merge only works for json and sarif reports"
+                ));
             }
+        }
+    }
 
-            // Print summary
-            if total_diagnostics > 0 {
-                println!();
-                println!("Summary:");
-                println!("  Files checked: {}", file_results.len());
-                println!("  Files with issues: {}", files_with_issues.len());
-                println!("  Total issues: {}", total_diagnostics);
-                if error_count > 0 {
-                    println!("    Errors: {}", error_count);
+    // JSON Lines goes out as each file finishes instead of after the run,
+    // into a temporary file renamed into place once the run is complete
+    let mut streams: Vec<Box<dyn Write>> = Vec::new();
+    let mut pending_streams: Vec<(PathBuf, &PathBuf)> = Vec::new();
+    for (spec, file) in &outputs {
+        if *spec == OutputSpec::Builtin(OutputFormat::Jsonl) {
+            streams.push(match file {
+                Some(file) if output_mode == OutputMode::Append => {
+                    Box::new(std::io::BufWriter::new(
+                        fs::OpenOptions::new()
+                            .create(true)
+                            .append(true)
+                            .open(file)
+                            .with_context(|| format!("Failed to open {}", file.display()))?,
+                    ))
                 }
-                if warn_count > 0 {
-                    println!("    Warnings: {}", warn_count);
+                Some(file) => {
+                    let temp = paths::temp_sibling(file);
+                    let stream = fs::File::create(&temp)
+                        .with_context(|| format!("Failed to create {}", temp.display()))?;
+                    pending_streams.push((temp, file));
+                    Box::new(std::io::BufWriter::new(stream))
                 }
-                if info_count > 0 {
-                    println!("    Info: {}", info_count);
-                }
-            } else {
-                println!();
-                println!("✓ No issues found in {} file(s)", file_results.len());
-            }
+                None => Box::new(std::io::stdout()),
+            });
         }
-        OutputFormat::Json => {
-            // Create a JSON output with file->diagnostics mapping
-            let json_output: std::collections::HashMap<
-                String,
-                Vec<&Diagnostic>,
-            > = file_results
-                .iter()
-                .map(|(path, diags, _)| (path.display().to_string(), diags.iter().collect()))
-                .collect();
-            let json = serde_json::to_string_pretty(&json_output)?;
-            if let Some(output_file) = output_file {
-                fs::write(output_file, json)?;
-            } else {
-                println!("{}", json);
-            }
+    }
+    // The per-rule and per-file limits count streamed findings as files finish
+    let mut stream_counts = LimitCounts::default();
+    let mut stream_results = |results: &[FileResult]| -> Result<()> {
+        if streams.is_empty() {
+            return Ok(());
         }
-        OutputFormat::Junit => {
-            let junit_xml = generate_junit_xml(file_results, total_diagnostics)?;
-            if let Some(output_file) = output_file {
-                fs::write(output_file, junit_xml)?;
-            } else {
-                println!("{}", junit_xml);
+        let mut lines = Vec::new();
+        for result in results {
+            if routed.contains(result.ruleset_id.as_str()) {
+                continue;
+            }
+            let changes = changed_lines
+                .as_ref()
+                .map(|changed| changed.file(&result.path));
+            for finding in &result.findings {
+                if severity.is_some_and(|t| !t.admits(&finding.diagnostic.severity)) {
+                    continue;
+                }
+                if changes.is_some_and(|changes| !in_diff(changes, finding)) {
+                    continue;
+                }
+                let id = rules::qualify(&result.ruleset_id, &finding.diagnostic.rule_id);
+                if stream_counts
+                    .admit(
+                        &id,
+                        &result.path,
+                        settings.linter.max_diagnostics_per_rule,
+                        settings.linter.max_diagnostics_per_file,
+                    )
+                    .is_some()
+                {
+                    continue;
+                }
+                lines.push(jsonl_line(result, finding, workspace_root)?);
             }
         }
-        _ => {
-            return Err(anyhow::anyhow!(
-                "Output format {:?} not yet implemented",
-                output
-            ));
+        for stream in &mut streams {
+            for line in &lines {
+                writeln!(stream, "{}", line)?;
+            }
+            stream.flush()?;
         }
-    }
-    Ok(())
-}
+        Ok(())
+    };
 
-fn generate_junit_xml(
-    file_results: &[(PathBuf, Vec<Diagnostic>, String)],
-    total_diagnostics: usize,
-) -> Result<String> {
-    use std::fmt::Write;
+    // Accessible mode reports progress in plain lines now and then
+    let total_files = files.len();
+    let mut linted = 0;
+    let mut last_progress = std::time::Instant::now();
+    let mut on_file = |results: &[FileResult]| -> Result<()> {
+        linted += 1;
+        if ctx.ascii && last_progress.elapsed() >= PROGRESS_INTERVAL {
+            eprintln!(
+                "{}",
+                ctx.lang.format(Msg::Progress, &[&linted, &total_files])
+            );
+            last_progress = std::time::Instant::now();
+        }
+        stream_results(results)
+    };
 
-    let mut xml = String::new();
+    let LintRun {
+        file_results,
+        mut anomalies,
+        suppressed_count,
+        filtered_count,
+        expired_suppressions,
+        aged_suppressions,
+        ambiguous_rules,
+        plugin_versions,
+        ruleset_stats,
+        mut file_counts,
+        analyzed_files,
+        analyzed_lines,
+        unclaimed_files,
+        file_elapsed,
+        metrics,
+        artifacts,
+    } = lint_files(
+        ctx,
+        &workspace,
+        files,
+        workspace.jobs(jobs),
+        !no_cache,
+        incremental,
+        &mut on_file,
+    )?;
+    metadata.plugin_versions = plugin_versions;
+    let suggestions = suggest_rulesets(&unclaimed_files, config_path);
+    drop(streams);
+    let artifacts = write_artifacts(
+        ctx,
+        workspace_root,
+        artifacts_dir.as_deref(),
+        artifacts,
+        &mut anomalies,
+    )?;
+    // Findings off the changed lines are dropped before anything counts them
+    let (file_results, outside_diff) = match &changed_lines {
+        Some(changed) => filter_to_diff(file_results, changed),
+        None => (file_results, 0),
+    };
+    if outside_diff > 0 {
+        file_counts.with_issues = file_results
+            .iter()
+            .map(|result| &result.path)
+            .collect::<std::collections::HashSet<_>>()
+            .len();
+        eprintln!(
+            "{}",
+            ctx.lang
+                .format(Msg::OutsideDiff, &[&thousands(outside_diff)])
+        );
+    }
+    for (temp, file) in pending_streams {
+        fs::rename(&temp, file)
+            .with_context(|| format!("Failed to write report: {}", file.display()))?;
+    }
+    let score = lint_score(&file_results, analyzed_lines, &settings.score);
+    let sarif_baseline = sarif_baseline
+        .map(|file| SarifBaseline::load(&file))
+        .transpose()?;
 
-    // XML header
-    writeln!(xml, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    // Findings below --severity stay out of reports but still count
+    let shown = severity.and_then(|threshold| {
+        retain_findings(&file_results, |finding| {
+            threshold.admits(&finding.diagnostic.severity)
+        })
+    });
+    let reported = shown.as_deref().unwrap_or(&file_results);
 
-    // Count statistics
-    let total_files = file_results.len();
-    let files_with_issues = file_results
+    // Count total diagnostics
+    let total_diagnostics = reported
         .iter()
-        .filter(|(_, diags, _)| !diags.is_empty())
-        .count();
-    let failures = total_diagnostics;
-
-    // Testsuite opening tag
-    writeln!(
-        xml,
-        r#"<testsuite name="Forseti Linter" tests="{}" failures="{}" errors="0" skipped="{}">"#,
-        total_files,
-        failures,
-        total_files - files_with_issues
-    )?;
+        .map(|result| result.findings.len())
+        .sum::<usize>();
+    let hidden_count = file_results
+        .iter()
+        .map(|result| result.findings.len())
+        .sum::<usize>()
+        - total_diagnostics;
 
-    // Generate test cases for each file
-    for (file_path, diagnostics, ruleset_id) in file_results {
-        let file_name = file_path.display().to_string();
-        let has_issues = !diagnostics.is_empty();
-
-        if has_issues {
-            // File with issues - create failure test case
-            writeln!(
-                xml,
-                r#"  <testcase classname="forseti.{}" name="{}" time="0">"#,
-                ruleset_id,
-                html_escape(&file_name)
-            )?;
-
-            // Add failures for each diagnostic
-            for diagnostic in diagnostics {
-                let failure_message = format!(
-                    "{}:{}: {} [{}@{}]",
-                    diagnostic.range.start.line + 1,
-                    diagnostic.range.start.character + 1,
-                    diagnostic.message,
-                    diagnostic.rule_id,
-                    ruleset_id
-                );
+    // Per-rule and per-file limits bound every report, not just stdout
+    let limited = limit_findings(
+        reported,
+        settings.linter.max_diagnostics_per_rule,
+        settings.linter.max_diagnostics_per_file,
+    );
+    let (reported, limit_notes) = match &limited {
+        Some((results, notes)) => (results.as_slice(), notes.as_slice()),
+        None => (reported, &[][..]),
+    };
+    let total_diagnostics =
+        total_diagnostics - limit_notes.iter().map(|note| note.more).sum::<usize>();
+    // The text summary lists these itself
+    if !outputs
+        .iter()
+        .any(|(spec, _)| *spec == OutputSpec::Builtin(OutputFormat::Text))
+    {
+        for note in limit_notes {
+            eprintln!(
+                "{}",
+                ctx.lang
+                    .format(Msg::MoreFrom, &[&thousands(note.more), &note.origin])
+            );
+        }
+    }
+
+    let provenance_file = output_file.first().map(|file| {
+        let mut name = file.clone().into_os_string();
+        name.push(".provenance.json");
+        PathBuf::from(name)
+    });
+
+    // Output results
+    let report = Report {
+        file_results: reported,
+        total_diagnostics,
+        workspace_root,
+        metadata: &metadata,
+        ruleset_stats: &ruleset_stats,
+        file_counts: &file_counts,
+        analyzed_files: &analyzed_files,
+        output_settings: &settings.output,
+        code_frame,
+        group_by,
+        group_summary_by,
+        path_style,
+        score,
+        sarif_baseline: sarif_baseline.as_ref(),
+        metrics: &metrics,
+        artifacts: &artifacts,
+        limit_notes,
+        omitted: limit_notes.iter().map(|note| note.more).sum(),
+    };
+    let unrouted: Vec<FileResult> = if routed.is_empty() {
+        Vec::new()
+    } else {
+        reported
+            .iter()
+            .filter(|result| !routed.contains(result.ruleset_id.as_str()))
+            .cloned()
+            .collect()
+    };
+    let main_report = if routed.is_empty() {
+        report
+    } else {
+        report.with_results(&unrouted)
+    };
+    // Only what goes to stdout is capped
+    let max_diagnostics = max_diagnostics.or(settings.linter.max_diagnostics);
+    let capped = max_diagnostics.and_then(|max| cap_findings(main_report.file_results, max));
+    let printed_report = match &capped {
+        Some(capped) => {
+            let printed = main_report.with_results(capped);
+            let cut = main_report.total_diagnostics - printed.total_diagnostics;
+            Report {
+                omitted: main_report.omitted + cut,
+                ..printed
+            }
+        }
+        None => main_report,
+    };
+    let mut truncated = 0;
+    for (spec, file) in &outputs {
+        // Already streamed
+        if *spec == OutputSpec::Builtin(OutputFormat::Jsonl) {
+            continue;
+        }
+        // The text summary says so itself
+        if file.is_none() && *spec != OutputSpec::Builtin(OutputFormat::Text) {
+            truncated = main_report.total_diagnostics - printed_report.total_diagnostics;
+        }
+        if file.is_none() {
+            output_results(ctx, &printed_report, spec, None, output_mode)?;
+        } else {
+            output_results(ctx, &main_report, spec, file.clone(), output_mode)?;
+        }
+    }
+    if truncated > 0 {
+        eprintln!(
+            "{}",
+            ctx.lang
+                .format(Msg::MoreDiagnostics, &[&thousands(truncated)])
+        );
+    }
+    for (name, route) in routes {
+        let results: Vec<FileResult> = reported
+            .iter()
+            .filter(|result| route.rulesets.contains(&result.ruleset_id))
+            .cloned()
+            .collect();
+        let spec: OutputSpec = route
+            .format
+            .parse()
+            .map_err(|e: String| anyhow::anyhow!(e))?;
+        ctx.log_verbose(&format!(
+            "Writing {} report for route {}",
+            route.format, name
+        ));
+        let file = route.file.as_ref().map(|file| workspace_root.join(file));
+        output_results(
+            ctx,
+            &report.with_results(&results),
+            &spec,
+            file,
+            output_mode,
+        )?;
+    }
+    let without_hints = strip_hints(report.file_results);
+    write_step_summary(
+        &report.with_results(without_hints.as_deref().unwrap_or(report.file_results)),
+    )?;
+
+    if debug_provenance {
+        write_provenance(&file_results, workspace_root, provenance_file)?;
+    }
+    #[cfg(feature = "store")]
+    if let Some((file, store)) = &mut store {
+        record_run(
+            store,
+            &metadata,
+            &file_results,
+            analyzed_files.len(),
+            workspace_root,
+        )?;
+        ctx.log_verbose(&format!(
+            "Recorded run {} in {}",
+            metadata.run_id,
+            file.display()
+        ));
+    }
+
+    if suppressed_count > 0 {
+        ctx.log_verbose(&format!("Suppressed {} diagnostic(s)", suppressed_count));
+    }
+    if let Some(threshold) = severity
+        && hidden_count > 0
+    {
+        eprintln!(
+            "{}",
+            ctx.lang
+                .format(Msg::BelowSeverity, &[&hidden_count, &threshold.name()])
+        );
+    }
+    if filtered_count > 0 {
+        ctx.log_verbose(&format!(
+            "Muted {} diagnostic(s) with [[output.filters]]",
+            filtered_count
+        ));
+    }
+    for expired in &expired_suppressions {
+        eprintln!(
+            "Expired suppression at {} ({}, until {}) no longer applies",
+            expired.origin, expired.rule, expired.until
+        );
+    }
+    for aged in &aged_suppressions {
+        eprintln!(
+            "Suppression at {} ({}, since {}) is at least {} day(s) old; its findings are \
+             reported again, at most as {}",
+            aged.origin,
+            aged.rule,
+            aged.since,
+            settings
+                .linter
+                .resurface_suppressed_after_days
+                .unwrap_or_default(),
+            settings.linter.resurface_severity()
+        );
+    }
+    for ambiguous in &ambiguous_rules {
+        eprintln!(
+            "Warning: {} names rule '{}', which rulesets {} all report; write '<ruleset>/{}' \
+             to target one",
+            ambiguous.origin,
+            ambiguous.rule,
+            ambiguous.rulesets.join(", "),
+            ambiguous.rule
+        );
+    }
+
+    let text_on_console = outputs
+        .iter()
+        .any(|(spec, file)| *spec == OutputSpec::Builtin(OutputFormat::Text) && file.is_none());
+    if text_on_console && !ctx.ci {
+        for suggestion in &suggestions {
+            eprintln!(
+                "Found {} {} file(s) that no {} ruleset handles {} try `forseti add {}`",
+                suggestion.count,
+                suggestion.kind,
+                suggestion.language.name,
+                ctx.glyph("—", "-"),
+                suggestion.language.id
+            );
+        }
+    }
+
+    let elapsed = started.elapsed();
+    if let Some(budget) = settings.linter.soft_time_budget()?
+        && elapsed > budget
+    {
+        eprint!(
+            "{}",
+            time_budget_guidance(elapsed, budget, &ruleset_stats, file_elapsed)
+        );
+    }
+    eprintln!(
+        "{}",
+        status_line(&file_results, analyzed_files.len(), elapsed)
+    );
+
+    if strict_plugins && !anomalies.is_empty() {
+        eprintln!("Plugin anomalies detected (--strict-plugins):");
+        for anomaly in &anomalies {
+            match &anomaly.file_path {
+                Some(file_path) => eprintln!(
+                    "  {} on {}: {}",
+                    anomaly.ruleset_id,
+                    file_path.display(),
+                    anomaly.message
+                ),
+                None => eprintln!("  {}: {}", anomaly.ruleset_id, anomaly.message),
+            }
+        }
+        std::process::exit(EXIT_PLUGIN_ANOMALY);
+    }
+
+    // Rules with a budget only count against the gate once they exceed it
+    let aliases = RuleAliases::new(&settings.aliases);
+    let gate = evaluate_limits(&file_results, &settings.limits, &aliases);
+    for rule in &gate.within_budget {
+        ctx.log_verbose(&format!(
+            "Rule {} has {} issue(s), within its budget of {}",
+            rule.rule, rule.count, rule.budget
+        ));
+    }
+    for rule in &gate.over_budget {
+        eprintln!(
+            "Rule {} has {} issue(s), exceeding its budget of {}",
+            rule.rule, rule.count, rule.budget
+        );
+    }
+
+    // Return error code if there were diagnostics
+    let failing = gate.unbudgeted + gate.over_budget.len();
+    if failing > 0 && config.linter.fail_on_error {
+        std::process::exit(1);
+    }
+    if let Some(min) = min_score.or(settings.score.min)
+        && score < min
+    {
+        eprintln!("Lint score {:.1} is below the minimum of {}", score, min);
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+/// `[score]` of a run: 100 minus weighted diagnostics per thousand analyzed
+/// lines, floored at 0
+fn lint_score(file_results: &[FileResult], lines: usize, settings: &ScoreSettings) -> f64 {
+    let weighted: f64 = file_results
+        .iter()
+        .flat_map(|result| &result.findings)
+        .map(|finding| settings.weight(&finding.diagnostic.severity))
+        .sum();
+    (100.0 - weighted * 1000.0 / lines.max(1) as f64).max(0.0)
+}
+
+/// At most `max` findings, in report order, or `None` when there are no more
+/// than that
+///
+/// The most severe findings are kept, and every rule keeps at least its most
+/// severe one while there's room, so a single noisy rule can't crowd out the
+/// rest. Ties go to whichever comes first in the report.
+fn cap_findings(file_results: &[FileResult], max: usize) -> Option<Vec<FileResult>> {
+    let mut order: Vec<(usize, usize)> = file_results
+        .iter()
+        .enumerate()
+        .flat_map(|(file, result)| (0..result.findings.len()).map(move |index| (file, index)))
+        .collect();
+    if order.len() <= max {
+        return None;
+    }
+    // Stable, so report order breaks ties
+    order.sort_by_key(|&(file, index)| {
+        let severity = &file_results[file].findings[index].diagnostic.severity;
+        std::cmp::Reverse(severity_rank(severity))
+    });
+
+    let mut kept = std::collections::HashSet::new();
+    let mut rules_seen = std::collections::HashSet::new();
+    for &(file, index) in &order {
+        let result = &file_results[file];
+        let diagnostic = &result.findings[index].diagnostic;
+        let rule_id = rules::qualify(&result.ruleset_id, &diagnostic.rule_id);
+        if kept.len() < max && rules_seen.insert(rule_id) {
+            kept.insert((file, index));
+        }
+    }
+    for &position in &order {
+        if kept.len() == max {
+            break;
+        }
+        kept.insert(position);
+    }
+
+    let capped = file_results
+        .iter()
+        .enumerate()
+        .filter_map(|(file, result)| {
+            let findings: Vec<Finding> = result
+                .findings
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| kept.contains(&(file, *index)))
+                .map(|(_, finding)| finding.clone())
+                .collect();
+            (!findings.is_empty()).then(|| FileResult {
+                findings,
+                ..result.clone()
+            })
+        })
+        .collect();
+    Some(capped)
+}
+
+/// How reports say they're missing `omitted` findings
+pub(crate) fn truncation_note(omitted: usize) -> String {
+    format!(
+        "Report truncated: {} more diagnostic(s) omitted",
+        thousands(omitted)
+    )
+}
+
+/// Findings a per-rule or per-file limit left out of the reports
+pub(crate) struct LimitNote {
+    /// `rule <id>` or `file <path>`
+    pub origin: String,
+    pub more: usize,
+}
+
+/// The limit a finding went over
+#[derive(Debug, PartialEq)]
+enum Over {
+    Rule,
+    File,
+}
+
+/// Findings kept so far, by qualified rule id and by file
+#[derive(Default)]
+struct LimitCounts {
+    rules: HashMap<String, usize>,
+    files: HashMap<PathBuf, usize>,
+}
+
+impl LimitCounts {
+    /// Counts a finding unless it goes over the per-rule limit or, failing
+    /// that, the per-file one
+    fn admit(
+        &mut self,
+        rule: &str,
+        path: &Path,
+        per_rule: Option<usize>,
+        per_file: Option<usize>,
+    ) -> Option<Over> {
+        let rule_count = self.rules.get(rule).copied().unwrap_or(0);
+        let file_count = self.files.get(path).copied().unwrap_or(0);
+        if per_rule.is_some_and(|max| rule_count >= max) {
+            return Some(Over::Rule);
+        }
+        if per_file.is_some_and(|max| file_count >= max) {
+            return Some(Over::File);
+        }
+        *self.rules.entry(rule.to_string()).or_default() += 1;
+        *self.files.entry(path.to_path_buf()).or_default() += 1;
+        None
+    }
+}
+
+/// The findings within `[linter] max_diagnostics_per_rule` and
+/// `max_diagnostics_per_file`, in report order, with a note per rule or file
+/// that went over; `None` when nothing did
+fn limit_findings(
+    file_results: &[FileResult],
+    per_rule: Option<usize>,
+    per_file: Option<usize>,
+) -> Option<(Vec<FileResult>, Vec<LimitNote>)> {
+    if per_rule.is_none() && per_file.is_none() {
+        return None;
+    }
+    let mut counts = LimitCounts::default();
+    let mut rule_dropped: std::collections::BTreeMap<String, usize> =
+        std::collections::BTreeMap::new();
+    let mut file_dropped: Vec<(&Path, usize)> = Vec::new();
+    let mut limited = Vec::new();
+    for result in file_results {
+        let mut kept = Vec::new();
+        for finding in &result.findings {
+            let id = rules::qualify(&result.ruleset_id, &finding.diagnostic.rule_id);
+            match counts.admit(&id, &result.path, per_rule, per_file) {
+                Some(Over::Rule) => *rule_dropped.entry(id).or_default() += 1,
+                Some(Over::File) => match file_dropped
+                    .iter_mut()
+                    .find(|(path, _)| *path == result.path)
+                {
+                    Some((_, more)) => *more += 1,
+                    None => file_dropped.push((&result.path, 1)),
+                },
+                None => kept.push(finding.clone()),
+            }
+        }
+        if !kept.is_empty() {
+            limited.push(FileResult {
+                findings: kept,
+                ..result.clone()
+            });
+        }
+    }
+    if rule_dropped.is_empty() && file_dropped.is_empty() {
+        return None;
+    }
+    let notes = rule_dropped
+        .into_iter()
+        .map(|(id, more)| LimitNote {
+            origin: format!("rule {}", id),
+            more,
+        })
+        .chain(file_dropped.into_iter().map(|(path, more)| LimitNote {
+            origin: format!("file {}", paths::display(path)),
+            more,
+        }))
+        .collect();
+    Some((limited, notes))
+}
+
+/// `4821` as `4,821`
+pub(crate) fn thousands(count: usize) -> String {
+    let digits = count.to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+/// `percent` of `files`, rounded up, picked by hashing each
+/// workspace-relative path with `seed` so every machine picks the same ones
+fn sample_files(files: Vec<PathBuf>, percent: f64, seed: u64, root: &Path) -> Vec<PathBuf> {
+    let keep = (files.len() as f64 * percent / 100.0).ceil() as usize;
+    let mut keyed: Vec<(u64, usize, PathBuf)> = files
+        .into_iter()
+        .enumerate()
+        .map(|(position, file)| {
+            let key = fingerprint::sample_key(seed, &relative_path(&file, root));
+            (key, position, file)
+        })
+        .collect();
+    keyed.sort_unstable_by_key(|(key, position, _)| (*key, *position));
+    keyed.truncate(keep);
+    // Back to discovery order
+    keyed.sort_unstable_by_key(|(_, position, _)| *position);
+    keyed.into_iter().map(|(_, _, file)| file).collect()
+}
+
+/// Where the time went when a run exceeds `[linter] soft_time_budget`
+fn time_budget_guidance(
+    elapsed: std::time::Duration,
+    budget: std::time::Duration,
+    ruleset_stats: &std::collections::BTreeMap<String, RulesetStats>,
+    mut file_elapsed: Vec<(PathBuf, std::time::Duration)>,
+) -> String {
+    use std::fmt::Write;
+    const SHOWN: usize = 5;
+
+    let mut guidance = String::new();
+    let _ = writeln!(
+        guidance,
+        "Lint took {:.1}s, over the soft time budget of {:.1}s",
+        elapsed.as_secs_f64(),
+        budget.as_secs_f64()
+    );
+    let mut rulesets: Vec<_> = ruleset_stats.iter().collect();
+    rulesets.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.elapsed));
+    let _ = writeln!(guidance, "  Slowest rulesets:");
+    for (id, stats) in rulesets.into_iter().take(SHOWN) {
+        let _ = writeln!(
+            guidance,
+            "    {:<20} {:>8.1}s over {} file(s)",
+            id,
+            stats.elapsed.as_secs_f64(),
+            stats.files
+        );
+    }
+    file_elapsed.sort_by_key(|(_, elapsed)| std::cmp::Reverse(*elapsed));
+    let _ = writeln!(guidance, "  Slowest files:");
+    for (file, elapsed) in file_elapsed.iter().take(SHOWN) {
+        let _ = writeln!(
+            guidance,
+            "    {:>8.1}s  {}",
+            elapsed.as_secs_f64(),
+            file.display()
+        );
+    }
+    let _ = writeln!(
+        guidance,
+        "  To speed up: scope slow rulesets with [ruleset.<id>.files], set `preload = true` \
+         for slow-starting ones, or shard the run across CI jobs by path (`forseti lint <dir>`)"
+    );
+    guidance
+}
+
+/// Final `forseti: 3 errors, 12 warnings, 841 files, 4.2s` line, printed on
+/// stderr whatever the outputs; the labels never change so scripts can parse it
+fn status_line(file_results: &[FileResult], files: usize, elapsed: std::time::Duration) -> String {
+    let severities = file_results
+        .iter()
+        .flat_map(|result| &result.findings)
+        .map(|finding| finding.diagnostic.severity.as_str());
+    let (mut errors, mut warnings) = (0, 0);
+    for severity in severities {
+        match severity {
+            "error" => errors += 1,
+            "info" | HINT => {}
+            // Unknown severities count as warnings, as in the text summary
+            _ => warnings += 1,
+        }
+    }
+    format!(
+        "forseti: {} errors, {} warnings, {} files, {:.1}s",
+        errors,
+        warnings,
+        files,
+        elapsed.as_secs_f64()
+    )
+}
+
+/// Run every enabled ruleset over `files`, applying suppressions and
+/// `[[output.filters]]`
+///
+/// Files are analyzed on `jobs` threads and their findings collected in the
+/// order of `files`, so the result doesn't depend on which finished first.
+/// `on_file` sees each file's findings as soon as the file and all before it
+/// are done.
+pub(crate) fn lint_files(
+    ctx: &GlobalContext,
+    workspace: &Workspace,
+    files: Vec<PathBuf>,
+    jobs: usize,
+    use_cache: bool,
+    incremental: bool,
+    on_file: &mut dyn FnMut(&[FileResult]) -> Result<()>,
+) -> Result<LintRun> {
+    let project_state = state::ProjectState::new(&workspace.root);
+    // A cache that can't be opened only costs speed
+    let cache = if use_cache {
+        match ResultCache::open(&project_state) {
+            Ok(cache) => Some(cache),
+            Err(e) => {
+                ctx.log_verbose(&format!("Not caching lint results: {:#}", e));
+                None
+            }
+        }
+    } else {
+        None
+    };
+    // The previous run's state is read by the workers while this run's is
+    // recorded, starting from it so files linted then but not now keep theirs
+    let previous = incremental.then(|| IncrementalState::load(&project_state));
+    let mut recorded = previous.clone();
+    let ruleset_keys: HashMap<&str, String> = workspace
+        .rulesets
+        .iter()
+        .filter_map(|ruleset| {
+            let ruleset_cfg = workspace.config.ruleset.get(&ruleset.id)?;
+            let key = IncrementalState::ruleset_key(&ruleset.identity(), &ruleset_cfg.config);
+            Some((ruleset.id.as_str(), key))
+        })
+        .collect();
+    let linter = &workspace.settings.linter;
+    let suppressions = Suppressions::load(&workspace.root, &workspace.settings.suppressions)?
+        .resurface_after(linter.resurface_suppressed_after_days);
+    let aliases = RuleAliases::new(&workspace.settings.aliases);
+    let filters = ReportFilters::new(&workspace.settings.output.filters)?;
+    let mut scopes = HashMap::new();
+    for ruleset in &workspace.rulesets {
+        let settings = workspace.settings.ruleset(&ruleset.id);
+        let scope = FileScope::new(&settings.files, settings.file_patterns.as_deref())?;
+        if !scope.is_empty() {
+            scopes.insert(ruleset.id.as_str(), scope);
+        }
+    }
+    let mut filtered_count = 0;
+    let mut expired_suppressions: Vec<Expired> = suppressions.expired_entries();
+    let mut aged_suppressions: Vec<Aged> = Vec::new();
+    let mut suppressed_count = 0;
+
+    let mut file_results = Vec::new();
+    let mut anomalies = Vec::new();
+    let mut plugin_versions = std::collections::BTreeMap::new();
+    let mut ruleset_stats = std::collections::BTreeMap::<String, RulesetStats>::new();
+    let mut file_counts = FileCounts {
+        discovered: files.len(),
+        ..FileCounts::default()
+    };
+    let mut analyzed_files = Vec::new();
+    let mut analyzed_lines = 0;
+    let mut unclaimed_files = Vec::new();
+    let mut metrics = std::collections::BTreeMap::<String, f64>::new();
+    let mut artifacts = Vec::new();
+
+    let mut log = RulesetLog::new(ctx);
+
+    let mut file_elapsed = Vec::new();
+
+    // Findings are post-processed here, on this thread, in file order
+    let mut handle_file = |file_path: &Path, analysis: Result<FileAnalysis>| -> Result<()> {
+        ctx.log_verbose(&format!("Processing: {}", file_path.display()));
+        let FileAnalysis {
+            content,
+            stamp,
+            passes,
+            elapsed,
+        } = analysis?;
+        let content = match content {
+            Source::Text(content) => content,
+            Source::Binary => {
+                ctx.log_verbose(&format!("Skipping binary file: {}", file_path.display()));
+                return Ok(());
+            }
+            Source::TooLarge(size) => {
+                ctx.log_verbose(&format!(
+                    "Skipping {} ({} bytes, over the maximum file size)",
+                    file_path.display(),
+                    size
+                ));
+                return Ok(());
+            }
+        };
+        let mut analyzed = false;
+        let mut claimed = false;
+        let language = languages::detect(file_path);
+        let file_start = file_results.len();
+
+        let relative = relative_path(file_path, &workspace.root);
+        let inline_suppressions = suppressions::parse_inline(&content);
+        let mut source: Option<Arc<str>> = None;
+        // Left out when any ruleset failed, so the next run tries again
+        let mut recorded_file = recorded
+            .is_some()
+            .then(|| IncrementalFile::new(stamp, &content));
+
+        for RulesetPass {
+            ruleset,
+            message,
+            analysis,
+        } in passes
+        {
+            log.ruleset(&ruleset.id, &message);
+            let Some((ruleset_elapsed, analysis)) = analysis else {
+                continue;
+            };
+            claimed |= ruleset.claims(language);
+            let stats = ruleset_stats.entry(ruleset.id.clone()).or_default();
+            stats.elapsed += ruleset_elapsed;
+            if analysis.is_ok() {
+                stats.files += 1;
+                analyzed = true;
+            }
+            if let Some(file) = &mut recorded_file {
+                match &analysis {
+                    Ok(fresh) if fresh.clean() => {
+                        let key = ruleset_keys.get(ruleset.id.as_str()).cloned();
+                        file.insert(&ruleset.id, key.unwrap_or_default(), fresh.to_cache());
+                    }
+                    _ => recorded_file = None,
+                }
+            }
+            match analysis {
+                Ok(Analysis {
+                    mut diagnostics,
+                    provenance,
+                    dropped,
+                    framing_errors,
+                    version,
+                    metrics: file_metrics,
+                    artifacts: file_artifacts,
+                    exit,
+                }) => {
+                    for (name, value) in file_metrics {
+                        *metrics
+                            .entry(rules::qualify(&ruleset.id, &name))
+                            .or_default() += value;
+                    }
+                    for artifact in file_artifacts {
+                        artifacts.push((ruleset.id.clone(), artifact));
+                    }
+                    if let Some(version) = version.or_else(|| ruleset.version.clone()) {
+                        plugin_versions.insert(ruleset.id.clone(), version);
+                    }
+                    if workspace.settings.output.redact_snippets {
+                        redact_snippets(&mut diagnostics, &content);
+                    }
+                    log.ruleset(
+                        &ruleset.id,
+                        &format!(
+                            "Ruleset {} processed {} and found {} diagnostic(s)",
+                            ruleset.id,
+                            file_path.display(),
+                            diagnostics.len()
+                        ),
+                    );
+                    for (diagnostic, origin) in diagnostics.iter().zip(&provenance) {
+                        log.ruleset(
+                            &ruleset.id,
+                            &format!(
+                                "  Diagnostic: {} at {}:{} - {} (pid {}, request {})",
+                                diagnostic.rule_id,
+                                diagnostic.range.start.line + 1,
+                                diagnostic.range.start.character + 1,
+                                diagnostic.message,
+                                origin.pid,
+                                origin.request_id
+                            ),
+                        );
+                    }
+                    if dropped > 0 {
+                        let message = format!("dropped {} malformed diagnostic(s)", dropped);
+                        log.ruleset(
+                            &ruleset.id,
+                            &format!(
+                                "Ruleset {} {} for file {}",
+                                ruleset.id,
+                                message,
+                                file_path.display()
+                            ),
+                        );
+                        anomalies.push(PluginAnomaly {
+                            ruleset_id: ruleset.id.clone(),
+                            file_path: Some(file_path.to_path_buf()),
+                            message,
+                        });
+                    }
+                    if let Some(first) = framing_errors.first() {
+                        for error in &framing_errors {
+                            log.ruleset(
+                                &ruleset.id,
+                                &format!(
+                                    "Ruleset {} framing error for file {}: {}",
+                                    ruleset.id,
+                                    file_path.display(),
+                                    error
+                                ),
+                            );
+                        }
+                        anomalies.push(PluginAnomaly {
+                            ruleset_id: ruleset.id.clone(),
+                            file_path: Some(file_path.to_path_buf()),
+                            message: format!(
+                                "recovered from {} framing error(s), first: {}",
+                                framing_errors.len(),
+                                first
+                            ),
+                        });
+                    }
+                    if let Some(exit) = exit {
+                        log.ruleset(
+                            &ruleset.id,
+                            &format!(
+                                "Ruleset {} {} for file {}",
+                                ruleset.id,
+                                exit,
+                                file_path.display()
+                            ),
+                        );
+                        anomalies.push(PluginAnomaly {
+                            ruleset_id: ruleset.id.clone(),
+                            file_path: Some(file_path.to_path_buf()),
+                            message: exit,
+                        });
+                    }
+                    let mut diagnostics: Vec<(Diagnostic, Provenance)> =
+                        diagnostics.into_iter().zip(provenance).collect();
+                    diagnostics.retain_mut(|(diagnostic, _)| {
+                        let id = rules::qualify(&ruleset.id, &diagnostic.rule_id);
+                        match suppressions.check(
+                            &aliases.names(&id),
+                            &relative,
+                            diagnostic.range.start.line as usize,
+                            &inline_suppressions,
+                        ) {
+                            Verdict::NotSuppressed => true,
+                            Verdict::Suppressed => {
+                                suppressed_count += 1;
+                                false
+                            }
+                            Verdict::Expired(expired) => {
+                                if !expired_suppressions.contains(&expired) {
+                                    expired_suppressions.push(expired);
+                                }
+                                true
+                            }
+                            Verdict::Aged(aged) => {
+                                let resurfaced = linter.resurface_severity();
+                                if severity_rank(resurfaced) < severity_rank(&diagnostic.severity) {
+                                    diagnostic.severity = resurfaced.to_string();
+                                }
+                                if !aged_suppressions.contains(&aged) {
+                                    aged_suppressions.push(aged);
+                                }
+                                true
+                            }
+                        }
+                    });
+                    if !diagnostics.is_empty() {
+                        let findings = fingerprint_diagnostics(
+                            diagnostics,
+                            &ruleset.id,
+                            &relative,
+                            &content,
+                            workspace.settings.output.tab_width(),
+                            &aliases,
+                        );
+                        file_results.push(FileResult {
+                            path: file_path.to_path_buf(),
+                            ruleset_id: ruleset.id.clone(),
+                            findings,
+                            source: source
+                                .get_or_insert_with(|| Arc::from(content.as_str()))
+                                .clone(),
+                        });
+                    }
+                }
+                Err(e) => {
+                    log.ruleset(
+                        &ruleset.id,
+                        &format!(
+                            "Ruleset {} failed for file {}: {:#}",
+                            ruleset.id,
+                            file_path.display(),
+                            e
+                        ),
+                    );
+                    anomalies.push(PluginAnomaly {
+                        ruleset_id: ruleset.id.clone(),
+                        file_path: Some(file_path.to_path_buf()),
+                        message: format!("{:#}", e),
+                    });
+                }
+            }
+        }
+        if let Some(recorded) = &mut recorded {
+            match recorded_file {
+                Some(file) => recorded.record(relative.clone(), file),
+                None => recorded.forget(&relative),
+            }
+        }
+        file_elapsed.push((file_path.to_path_buf(), elapsed));
+        if analyzed {
+            file_counts.analyzed += 1;
+            analyzed_lines += content.lines().count();
+            analyzed_files.push(file_path.to_path_buf());
+        }
+        if !claimed {
+            unclaimed_files.push(file_path.to_path_buf());
+        }
+
+        // Muted findings are dropped before anything counts or reports them
+        if !filters.is_empty() {
+            for result in &mut file_results[file_start..] {
+                let before = result.findings.len();
+                result.findings.retain(|finding| {
+                    let diagnostic = &finding.diagnostic;
+                    let id = rules::qualify(&result.ruleset_id, &diagnostic.rule_id);
+                    !aliases.names(&id).iter().any(|name| {
+                        filters.mutes(name, &diagnostic.severity, &relative, &diagnostic.message)
+                    })
+                });
+                filtered_count += before - result.findings.len();
+            }
+            let kept: Vec<FileResult> = file_results
+                .drain(file_start..)
+                .filter(|result| !result.findings.is_empty())
+                .collect();
+            file_results.extend(kept);
+        }
+        on_file(&file_results[file_start..])
+    };
+    let pool = SessionPool::default();
+    let reuse = Reuse {
+        cache: cache.as_ref(),
+        previous: previous.as_ref(),
+    };
+    analyze_files(
+        &pool,
+        reuse,
+        workspace,
+        &scopes,
+        &files,
+        jobs,
+        &mut handle_file,
+    )?;
+    for (ruleset_id, status) in pool.take_exits() {
+        log.ruleset(
+            &ruleset_id,
+            &format!(
+                "Ruleset {} process exited with {} between files",
+                ruleset_id, status
+            ),
+        );
+        anomalies.push(PluginAnomaly {
+            ruleset_id,
+            file_path: None,
+            message: format!("process exited with {} between files", status),
+        });
+    }
+    for (ruleset_id, e) in pool.shutdown() {
+        log.ruleset(
+            &ruleset_id,
+            &format!("Ruleset {} failed to shut down: {:#}", ruleset_id, e),
+        );
+        anomalies.push(PluginAnomaly {
+            ruleset_id,
+            file_path: None,
+            message: format!("failed to shut down: {:#}", e),
+        });
+    }
+
+    log.flush();
+
+    if let Some(recorded) = recorded
+        && let Err(e) = recorded.save(&project_state, &workspace.root)
+    {
+        ctx.log_verbose(&format!("Not saving incremental state: {:#}", e));
+    }
+
+    file_counts.skipped = file_counts.discovered - file_counts.analyzed;
+    file_counts.with_issues = file_results
+        .iter()
+        .map(|result| &result.path)
+        .collect::<std::collections::HashSet<_>>()
+        .len();
+
+    // Preloaded rulesets that never got a file still need shutting down
+    let unused: Vec<String> = workspace
+        .preloaded
+        .lock()
+        .unwrap()
+        .keys()
+        .cloned()
+        .collect();
+    for ruleset_id in unused {
+        if let Some(Ok(session)) = workspace.take_preloaded(&ruleset_id) {
+            let _ = session.shutdown();
+        }
+    }
+
+    // Bare ids in config match a colliding rule in every ruleset reporting it
+    let collisions = rules::collisions(file_results.iter().flat_map(|result| {
+        result.findings.iter().map(|finding| {
+            (
+                result.ruleset_id.as_str(),
+                finding.diagnostic.rule_id.as_str(),
+            )
+        })
+    }));
+    let bare_limits = workspace
+        .settings
+        .limits
+        .keys()
+        .filter(|key| !key.contains('/'))
+        .map(|key| ("[limits]", key.as_str()));
+    let ambiguous_rules = suppressions
+        .bare_entries()
+        .chain(bare_limits)
+        .filter_map(|(origin, rule)| {
+            let rulesets = collisions.get(rule)?;
+            Some(AmbiguousRule {
+                origin: origin.to_string(),
+                rule: rule.to_string(),
+                rulesets: rulesets.iter().cloned().collect(),
+            })
+        })
+        .collect();
+
+    Ok(LintRun {
+        file_results,
+        anomalies,
+        suppressed_count,
+        filtered_count,
+        expired_suppressions,
+        aged_suppressions,
+        ambiguous_rules,
+        plugin_versions,
+        ruleset_stats,
+        file_counts,
+        analyzed_files,
+        analyzed_lines,
+        unclaimed_files,
+        file_elapsed,
+        metrics,
+        artifacts,
+    })
+}
+
+/// What the rulesets returned for one file, before suppressions and filters
+struct FileAnalysis<'w> {
+    content: Source,
+    /// Taken before the file was read, in incremental runs
+    stamp: Option<FileStamp>,
+    /// One per ruleset, in discovery order
+    passes: Vec<RulesetPass<'w>>,
+    elapsed: std::time::Duration,
+}
+
+/// One ruleset's turn at a file
+struct RulesetPass<'w> {
+    ruleset: &'w RulesetInfo,
+    /// Verbose line saying whether and why the ruleset ran
+    message: String,
+    /// Time taken and outcome, when the ruleset ran
+    analysis: Option<(std::time::Duration, Result<Analysis>)>,
+}
+
+/// Where results of unchanged files can come from instead of the rulesets
+#[derive(Clone, Copy)]
+struct Reuse<'a> {
+    cache: Option<&'a ResultCache>,
+    /// State of the last `--incremental` run, in incremental runs
+    previous: Option<&'a IncrementalState>,
+}
+
+/// Analyze `files` on up to `jobs` threads, handing each file's analysis to
+/// `on_result` in the order of `files` whatever order they finish in
+///
+/// Threads take files in chunks so rulesets supporting `analyzeBatch` get
+/// several per request. Stops handing out files once `on_result` fails.
+fn analyze_files<'w>(
+    pool: &SessionPool,
+    reuse: Reuse,
+    workspace: &'w Workspace,
+    scopes: &HashMap<&str, FileScope>,
+    files: &[PathBuf],
+    jobs: usize,
+    on_result: &mut dyn FnMut(&Path, Result<FileAnalysis<'w>>) -> Result<()>,
+) -> Result<()> {
+    // Small enough that every thread gets several chunks
+    let chunk = (files.len() / (jobs * 4).max(1)).clamp(1, MAX_BATCH_FILES);
+    let next = AtomicUsize::new(0);
+    std::thread::scope(|scope| {
+        let (tx, rx) = std::sync::mpsc::channel();
+        for _ in 0..jobs.min(files.len()) {
+            let tx = tx.clone();
+            let next = &next;
+            scope.spawn(move || {
+                loop {
+                    let first = next.fetch_add(chunk, Ordering::Relaxed);
+                    if first >= files.len() {
+                        break;
+                    }
+                    let batch = &files[first..(first + chunk).min(files.len())];
+                    let analyses = analyze_batch(pool, reuse, workspace, scopes, batch);
+                    // The receiver is gone once `on_result` has failed
+                    if tx.send((first, analyses)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(tx);
+
+        // Hold early finishers back until every file before them is handed on
+        let mut finished = std::collections::BTreeMap::new();
+        let mut waiting = 0;
+        for (first, analyses) in rx {
+            for (offset, analysis) in analyses.into_iter().enumerate() {
+                finished.insert(first + offset, analysis);
+            }
+            while let Some(analysis) = finished.remove(&waiting) {
+                on_result(&files[waiting], analysis)?;
+                waiting += 1;
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Run every enabled ruleset over the files in `batch` its scope covers,
+/// returning one analysis per file in order
+fn analyze_batch<'w>(
+    pool: &SessionPool,
+    reuse: Reuse,
+    workspace: &'w Workspace,
+    scopes: &HashMap<&str, FileScope>,
+    batch: &[PathBuf],
+) -> Vec<Result<FileAnalysis<'w>>> {
+    let Reuse { cache, previous } = reuse;
+    let file_started = std::time::Instant::now();
+
+    // Stamped first, so a file changing while it's read looks changed next
+    // time; content read from elsewhere isn't what the stamp describes, so
+    // it's only hashed
+    let stamps: Vec<Option<FileStamp>> = batch
+        .iter()
+        .map(|file_path| {
+            previous
+                .filter(|_| workspace.content.is_none())
+                .and_then(|_| FileStamp::of(file_path))
+        })
+        .collect();
+    // Read file content, skipping binary files
+    let contents: Vec<Result<Source>> = batch
+        .iter()
+        .map(|file_path| read_source(workspace, file_path))
+        .collect();
+    let uris: Vec<String> = batch.iter().map(|path| paths::file_uri(path)).collect();
+    // Files as the previous incremental run saw them
+    let unchanged: Vec<Option<&IncrementalFile>> = batch
+        .iter()
+        .zip(&contents)
+        .zip(&stamps)
+        .map(|((file_path, content), stamp)| {
+            let (Some(previous), Ok(Source::Text(content))) = (previous, content) else {
+                return None;
+            };
+            previous.unchanged(&relative_path(file_path, &workspace.root), *stamp, content)
+        })
+        .collect();
+    let mut passes: Vec<Vec<RulesetPass>> = batch.iter().map(|_| Vec::new()).collect();
 
-                writeln!(
-                    xml,
-                    r#"    <failure message="{}" type="{}">{}</failure>"#,
-                    html_escape(&failure_message),
-                    html_escape(&diagnostic.rule_id),
-                    html_escape(&diagnostic.message)
-                )?;
+    // Try each enabled ruleset
+    for ruleset in &workspace.rulesets {
+        let ruleset_cfg = match workspace.config.ruleset.get(&ruleset.id) {
+            Some(ruleset_cfg) if ruleset_cfg.enabled => ruleset_cfg,
+            other => {
+                let message = match other {
+                    Some(_) => format!("Ruleset {} is disabled", ruleset.id),
+                    None => format!("No configuration found for ruleset {}", ruleset.id),
+                };
+                for file_passes in &mut passes {
+                    file_passes.push(RulesetPass {
+                        ruleset,
+                        message: message.clone(),
+                        analysis: None,
+                    });
+                }
+                continue;
+            }
+        };
+
+        // Files in scope, by position in the batch
+        let mut targets = Vec::new();
+        for (index, file_path) in batch.iter().enumerate() {
+            let Ok(Source::Text(content)) = &contents[index] else {
+                continue;
+            };
+            let relative = relative_path(file_path, &workspace.root);
+            let in_scope = scopes
+                .get(ruleset.id.as_str())
+                .is_none_or(|scope| scope.covers(&relative));
+            if in_scope {
+                targets.push((index, relative, content.as_str()));
+            } else {
+                passes[index].push(RulesetPass {
+                    ruleset,
+                    message: format!(
+                        "Ruleset {} excludes file {}",
+                        ruleset.id,
+                        file_path.display()
+                    ),
+                    analysis: None,
+                });
             }
+        }
 
-            writeln!(xml, r#"  </testcase>"#)?;
+        // Unchanged files are answered from the previous incremental run or
+        // the cache without the ruleset
+        let identity = ruleset.identity();
+        let ruleset_key =
+            previous.map(|_| IncrementalState::ruleset_key(&identity, &ruleset_cfg.config));
+        let mut misses = Vec::new();
+        for (index, relative, content) in targets {
+            let stored = unchanged[index]
+                .zip(ruleset_key.as_deref())
+                .and_then(|(file, key)| file.result(&ruleset.id, key));
+            if let Some(stored) = stored {
+                passes[index].push(RulesetPass {
+                    ruleset,
+                    message: format!(
+                        "Ruleset {} reused the previous run's results for file {}",
+                        ruleset.id,
+                        batch[index].display()
+                    ),
+                    analysis: Some((
+                        std::time::Duration::ZERO,
+                        Ok(Analysis::from_cache(&ruleset.id, stored.clone())),
+                    )),
+                });
+                continue;
+            }
+            let key = cache.map(|_| {
+                ResultCache::key(
+                    &ruleset.id,
+                    &identity,
+                    &ruleset_cfg.config,
+                    &relative,
+                    content,
+                )
+            });
+            let cached = cache
+                .zip(key.as_deref())
+                .and_then(|(cache, key)| cache.get(key));
+            match cached {
+                Some(cached) => passes[index].push(RulesetPass {
+                    ruleset,
+                    message: format!(
+                        "Ruleset {} reused cached results for file {}",
+                        ruleset.id,
+                        batch[index].display()
+                    ),
+                    analysis: Some((
+                        std::time::Duration::ZERO,
+                        Ok(Analysis::from_cache(&ruleset.id, cached)),
+                    )),
+                }),
+                None => misses.push((index, key, content)),
+            }
+        }
+        if misses.is_empty() {
+            continue;
+        }
+        // Patterns in config replace the ruleset's own, already applied by
+        // its scope; a daemon's sessions get every file
+        let configured = workspace.settings.ruleset(&ruleset.id).file_patterns;
+        let reported = if configured.is_none() && workspace.daemon.is_none() {
+            pool.reported_patterns(workspace, ruleset, &ruleset_cfg.config)
         } else {
-            // File with no issues - create passing test case
-            writeln!(
-                xml,
-                r#"  <testcase classname="forseti.{}" name="{}" time="0"/>"#,
-                ruleset_id,
-                html_escape(&file_name)
-            )?;
+            Ok(None)
+        };
+        match reported {
+            Ok(Some(patterns)) => misses.retain(|(index, _, _)| {
+                let relative = relative_path(&batch[*index], &workspace.root);
+                if patterns.is_match(&relative) {
+                    return true;
+                }
+                passes[*index].push(RulesetPass {
+                    ruleset,
+                    message: format!(
+                        "Ruleset {} doesn't handle file {}",
+                        ruleset.id,
+                        batch[*index].display()
+                    ),
+                    analysis: None,
+                });
+                false
+            }),
+            Ok(None) => {}
+            Err(e) => {
+                for (index, _, _) in misses {
+                    passes[index].push(RulesetPass {
+                        ruleset,
+                        message: format!(
+                            "Trying ruleset {} for file {}",
+                            ruleset.id,
+                            batch[index].display()
+                        ),
+                        analysis: Some((
+                            std::time::Duration::ZERO,
+                            Err(anyhow::anyhow!("{:#}", e)),
+                        )),
+                    });
+                }
+                continue;
+            }
+        }
+        if misses.is_empty() {
+            continue;
+        }
+
+        let files: Vec<(&str, &str)> = misses
+            .iter()
+            .map(|(index, _, content)| (uris[*index].as_str(), *content))
+            .collect();
+        let analyses = run_ruleset(pool, workspace, ruleset, &ruleset_cfg.config, &files);
+        for ((index, key, _), analysis) in misses.into_iter().zip(analyses) {
+            // Results with anomalies aren't kept, so the anomalies show up again
+            if let (Some(cache), Some(key), (_, Ok(fresh))) = (cache, &key, &analysis)
+                && fresh.clean()
+            {
+                // A cache that can't be written only costs speed
+                let _ = cache.put(key, &fresh.to_cache());
+            }
+            passes[index].push(RulesetPass {
+                ruleset,
+                message: format!(
+                    "Trying ruleset {} for file {}",
+                    ruleset.id,
+                    batch[index].display()
+                ),
+                analysis: Some(analysis),
+            });
+        }
+    }
+
+    // Files in a batch are analyzed together, so they share its time
+    let elapsed = file_started.elapsed() / batch.len() as u32;
+    contents
+        .into_iter()
+        .zip(stamps)
+        .zip(passes)
+        .map(|((content, stamp), passes)| {
+            Ok(FileAnalysis {
+                content: content?,
+                stamp,
+                passes,
+                elapsed,
+            })
+        })
+        .collect()
+}
+
+/// Analyze `files` (uri and content) with one ruleset: in a single
+/// `analyzeBatch` request when it declared support, else one request each
+///
+/// A running daemon gets the files first; whatever it can't answer is
+/// analyzed by sessions of this process.
+pub(crate) fn run_ruleset(
+    pool: &SessionPool,
+    workspace: &Workspace,
+    ruleset: &RulesetInfo,
+    config: &toml::value::Table,
+    files: &[(&str, &str)],
+) -> Vec<(std::time::Duration, Result<Analysis>)> {
+    if let Some(daemon) = &workspace.daemon
+        && let Some(analyses) = daemon.analyze(&ruleset.id, &ruleset.identity(), config, files)
+    {
+        return analyses;
+    }
+    if files.len() > 1 {
+        let started = std::time::Instant::now();
+        let analyses = pool.run(workspace, ruleset, config, |session| {
+            session
+                .batch
+                .then(|| session.analyze_batch(files))
+                .transpose()
+        });
+        let elapsed = started.elapsed() / files.len() as u32;
+        match analyses {
+            Ok(Some(analyses)) => {
+                return analyses
+                    .into_iter()
+                    .map(|analysis| (elapsed, Ok(analysis)))
+                    .collect();
+            }
+            Ok(None) => {}
+            Err(e) => {
+                return files
+                    .iter()
+                    .map(|_| (elapsed, Err(anyhow::anyhow!("{:#}", e))))
+                    .collect();
+            }
+        }
+    }
+
+    files
+        .iter()
+        .map(|(uri, content)| {
+            let started = std::time::Instant::now();
+            let analysis = pool.run(workspace, ruleset, config, |session| {
+                session.analyze(uri, content)
+            });
+            (started.elapsed(), analysis)
+        })
+        .collect()
+}
+
+/// Verbose output about individual rulesets, collected into one collapsible
+/// `::group::` block per ruleset when running in GitHub Actions
+struct RulesetLog<'a> {
+    ctx: &'a GlobalContext,
+    /// Buffered lines per ruleset, or `None` to print them as they come
+    groups: Option<std::collections::BTreeMap<String, Vec<String>>>,
+}
+
+impl<'a> RulesetLog<'a> {
+    fn new(ctx: &'a GlobalContext) -> Self {
+        let groups = (ctx.verbose && in_github_actions()).then(std::collections::BTreeMap::new);
+        Self { ctx, groups }
+    }
+
+    fn ruleset(&mut self, ruleset_id: &str, message: &str) {
+        match &mut self.groups {
+            Some(groups) => groups
+                .entry(ruleset_id.to_string())
+                .or_default()
+                .push(message.to_string()),
+            None => self.ctx.log_verbose(message),
+        }
+    }
+
+    fn flush(self) {
+        for (ruleset_id, lines) in self.groups.into_iter().flatten() {
+            eprintln!("::group::Ruleset {}", ruleset_id);
+            for line in lines {
+                self.ctx.log_verbose(&line);
+            }
+            eprintln!("::endgroup::");
         }
     }
+}
+
+/// Set by the GitHub Actions runner
+pub(crate) fn in_github_actions() -> bool {
+    std::env::var("GITHUB_ACTIONS").is_ok_and(|value| value == "true")
+}
 
-    // Close testsuite
-    writeln!(xml, r#"</testsuite>"#)?;
+/// A budgeted rule and how many issues it produced
+struct RuleBudget {
+    rule: String,
+    count: usize,
+    budget: usize,
+}
 
-    Ok(xml)
+/// Diagnostic counts split by whether their rule has a `[limits]` budget
+struct LimitGate {
+    /// Diagnostics from rules without a budget
+    unbudgeted: usize,
+    within_budget: Vec<RuleBudget>,
+    over_budget: Vec<RuleBudget>,
 }
 
-fn html_escape(s: &str) -> String {
-    s.replace('&', "&amp;")
-        .replace('<', "&lt;")
-        .replace('>', "&gt;")
-        .replace('"', "&quot;")
-        .replace('\'', "&#39;")
+fn evaluate_limits(
+    file_results: &[FileResult],
+    limits: &std::collections::HashMap<String, usize>,
+    aliases: &RuleAliases,
+) -> LimitGate {
+    let mut counts: std::collections::BTreeMap<String, (usize, usize)> =
+        std::collections::BTreeMap::new();
+    let mut unbudgeted = 0;
+
+    for result in file_results {
+        for finding in &result.findings {
+            if finding.diagnostic.severity == HINT {
+                continue;
+            }
+            let id = rules::qualify(&result.ruleset_id, &finding.diagnostic.rule_id);
+            let names = aliases.names(&id);
+            // Namespaced keys win over bare ones, the current id over aliases
+            let budget = names
+                .iter()
+                .map(String::as_str)
+                .chain(names.iter().map(|name| rules::short(name)))
+                .find_map(|key| limits.get(key).map(|budget| (key.to_string(), *budget)));
+            match budget {
+                Some((key, budget)) => counts.entry(key).or_insert((0, budget)).0 += 1,
+                None => unbudgeted += 1,
+            }
+        }
+    }
+
+    let (over_budget, within_budget) = counts
+        .into_iter()
+        .map(|(rule, (count, budget))| RuleBudget {
+            rule,
+            count,
+            budget,
+        })
+        .partition(|rule| rule.count > rule.budget);
+
+    LimitGate {
+        unbudgeted,
+        within_budget,
+        over_budget,
+    }
+}
+
+/// Attach fingerprints to a file's diagnostics from one ruleset
+fn fingerprint_diagnostics(
+    diagnostics: Vec<(Diagnostic, Provenance)>,
+    ruleset_id: &str,
+    path: &str,
+    content: &str,
+    tab_width: usize,
+    aliases: &RuleAliases,
+) -> Vec<Finding> {
+    let index = LineIndex::new(content);
+    let mut occurrences: std::collections::HashMap<(String, String), usize> =
+        std::collections::HashMap::new();
+
+    diagnostics
+        .into_iter()
+        .map(|(diagnostic, provenance)| {
+            let context = index
+                .line(diagnostic.range.start.line as usize)
+                .unwrap_or_default();
+            let key = (
+                diagnostic.rule_id.clone(),
+                fingerprint::normalize_context(context),
+            );
+            let occurrence = occurrences.entry(key).or_insert(0);
+            // Aliased rules keep their old identity so baselines still match
+            let id = rules::qualify(ruleset_id, &diagnostic.rule_id);
+            let (fingerprint_ruleset, fingerprint_rule) = match aliases.original(&id) {
+                original if original != id => original.split_once('/').unwrap_or(("", original)),
+                _ => (ruleset_id, diagnostic.rule_id.as_str()),
+            };
+            let fingerprint = fingerprint::compute(
+                fingerprint_ruleset,
+                fingerprint_rule,
+                path,
+                context,
+                *occurrence,
+            );
+            *occurrence += 1;
+            let column = display_column(
+                context,
+                diagnostic.range.start.character as usize,
+                tab_width,
+            ) + 1;
+            Finding {
+                diagnostic,
+                fingerprint,
+                column,
+                provenance,
+            }
+        })
+        .collect()
+}
+
+/// Zero-based display column of the `character`-th character of `line`,
+/// advancing to the next multiple of `tab_width` at each tab
+pub(crate) fn display_column(line: &str, character: usize, tab_width: usize) -> usize {
+    let tab_width = tab_width.max(1);
+    line.chars().take(character).fold(0, |column, c| {
+        if c == '\t' {
+            (column / tab_width + 1) * tab_width
+        } else {
+            column + 1
+        }
+    }) + character.saturating_sub(line.chars().count())
+}
+
+/// Placeholder substituted for flagged source text when redaction is enabled
+const REDACTED: &str = "[REDACTED]";
+
+/// Remove the source text each diagnostic points at from its message, so
+/// secrets matched by a rule never end up in a report
+fn redact_snippets(diagnostics: &mut [Diagnostic], content: &str) {
+    for diagnostic in diagnostics {
+        let start = (
+            diagnostic.range.start.line as usize,
+            diagnostic.range.start.character as usize,
+        );
+        let end = (
+            diagnostic.range.end.line as usize,
+            diagnostic.range.end.character as usize,
+        );
+        if let Some(snippet) = range_text(content, start, end) {
+            for part in snippet.lines().map(str::trim).filter(|p| !p.is_empty()) {
+                diagnostic.message = diagnostic.message.replace(part, REDACTED);
+            }
+        }
+    }
+}
+
+/// Source text between two zero-based (line, character) positions
+fn range_text(content: &str, start: (usize, usize), end: (usize, usize)) -> Option<String> {
+    let index = LineIndex::new(content);
+    let (start, end) = (index.offset(start.0, start.1)?, index.offset(end.0, end.1)?);
+    (start < end).then(|| content[start..end].to_string())
+}
+
+/// Files of a known language that no ruleset covered
+struct RulesetSuggestion {
+    /// Extension or file name the files share, e.g. `.tf`
+    kind: String,
+    count: usize,
+    language: &'static Language,
+}
+
+/// Suggestions for the files no ruleset claimed, most files first
+fn suggest_rulesets(unclaimed: &[PathBuf], config_path: &Path) -> Vec<RulesetSuggestion> {
+    let mut counts: std::collections::HashMap<String, (usize, &'static Language)> =
+        std::collections::HashMap::new();
+    let config_file = paths::canonicalize(config_path);
+
+    for file in unclaimed {
+        // The config itself says nothing about the project's languages
+        if file.file_name() == config_path.file_name() && paths::canonicalize(file) == config_file {
+            continue;
+        }
+        let Some(language) = languages::detect(file) else {
+            continue;
+        };
+        if let Some(kind) = languages::file_kind(file) {
+            counts.entry(kind).or_insert((0, language)).0 += 1;
+        }
+    }
+
+    let mut suggestions: Vec<RulesetSuggestion> = counts
+        .into_iter()
+        .map(|(kind, (count, language))| RulesetSuggestion {
+            kind,
+            count,
+            language,
+        })
+        .collect();
+    suggestions.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.kind.cmp(&b.kind)));
+    suggestions
+}
+
+/// The work tree containing `path`, the base ref `--changed` or
+/// `--diff-filter` compare against (the default one when `base` is empty),
+/// and the commit where `head` forked from it
+fn git_base(path: &Path, base: &str, head: &str) -> Result<(PathBuf, String, String)> {
+    let (dir, _) = git_scope(path);
+    let toplevel = git::toplevel(dir)?;
+    let base = Some(base).filter(|base| !base.is_empty());
+    let (base, commit) = git::merge_base(&toplevel, base, head)?;
+    Ok((toplevel, base, commit))
+}
+
+/// Directory to run git in for `path`, and `path` made absolute to match
+/// the paths git reports against
+fn git_scope(path: &Path) -> (&Path, PathBuf) {
+    let dir = if path.is_dir() {
+        path
+    } else {
+        path.parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .unwrap_or(Path::new("."))
+    };
+    (dir, paths::canonicalize(path))
+}
+
+/// A file git reported, spelled like a walk of `path` would find it; `None`
+/// outside `scope` (the absolute `path`) and in forseti's own state
+fn scoped_path(path: &Path, scope: &Path, file: &Path) -> Option<PathBuf> {
+    let relative = file.strip_prefix(scope).ok()?;
+    if relative
+        .components()
+        .any(|component| component.as_os_str() == state::STATE_DIR)
+    {
+        return None;
+    }
+    if relative.as_os_str().is_empty() {
+        Some(path.to_path_buf())
+    } else {
+        Some(path.join(relative))
+    }
+}
+
+/// Whether a finding touches a changed line of its file
+fn in_diff(changes: Option<&git::FileChanges>, finding: &Finding) -> bool {
+    let range = &finding.diagnostic.range;
+    // A range ending at the start of a line doesn't include that line
+    let last = if range.end.line > range.start.line && range.end.character == 0 {
+        range.end.line - 1
+    } else {
+        range.end.line.max(range.start.line)
+    };
+    changes.is_some_and(|changes| changes.touches(range.start.line + 1, last + 1))
+}
+
+/// The findings on changed lines, and how many others were dropped
+fn filter_to_diff(
+    file_results: Vec<FileResult>,
+    changed: &git::ChangedLines,
+) -> (Vec<FileResult>, usize) {
+    let mut dropped = 0;
+    let kept = file_results
+        .into_iter()
+        .filter_map(|mut result| {
+            let changes = changed.file(&result.path);
+            let before = result.findings.len();
+            result.findings.retain(|finding| in_diff(changes, finding));
+            dropped += before - result.findings.len();
+            (!result.findings.is_empty()).then_some(result)
+        })
+        .collect();
+    (kept, dropped)
+}
+
+/// A file to lint as read, or why it wasn't
+enum Source {
+    Text(String),
+    Binary,
+    /// Larger than the maximum file size, in bytes
+    TooLarge(u64),
+}
+
+/// Content of a file to lint; files and git blobs over the maximum size are
+/// sized up without being read
+fn read_source(workspace: &Workspace, file_path: &Path) -> Result<Source> {
+    let too_large = |size: u64| workspace.max_file_size.is_some_and(|max| size > max);
+    match &workspace.content {
+        Some(Content::Git(git_content)) => {
+            let blob = git_content
+                .blobs
+                .get(file_path)
+                .ok_or_else(|| anyhow::anyhow!("{} has no git blob", file_path.display()))?;
+            let read_error = || format!("Failed to read file from git: {}", file_path.display());
+            // Spares a git process per file when there is no limit
+            if workspace.max_file_size.is_some() {
+                let size = git::blob_size(&git_content.toplevel, blob).with_context(read_error)?;
+                if too_large(size) {
+                    return Ok(Source::TooLarge(size));
+                }
+            }
+            let content = git::read_blob(&git_content.toplevel, blob).with_context(read_error)?;
+            return Ok(text_content(content));
+        }
+        Some(Content::Stdin { path, text, size }) if path == file_path => {
+            return Ok(match text {
+                Some(text) => Source::Text(text.clone()),
+                None => Source::TooLarge(*size),
+            });
+        }
+        _ => {}
+    }
+    let size = fs::metadata(file_path)
+        .with_context(|| format!("Failed to read file: {}", file_path.display()))?
+        .len();
+    if too_large(size) {
+        return Ok(Source::TooLarge(size));
+    }
+    let content = fs::read(file_path)
+        .with_context(|| format!("Failed to read file: {}", file_path.display()))?;
+    Ok(text_content(content))
+}
+
+/// `bytes` as text, unless they look binary: not UTF-8, or containing a
+/// NUL byte, which text files don't but images, archives and UTF-16 do
+fn text_content(bytes: Vec<u8>) -> Source {
+    if bytes.contains(&0) {
+        return Source::Binary;
+    }
+    String::from_utf8(bytes).map_or(Source::Binary, Source::Text)
+}
+
+/// The `--stdin` buffer, to lint as `file`; past `max_file_size` bytes the
+/// rest is only counted, so a huge buffer is never held in memory
+fn read_stdin(file: PathBuf, max_file_size: Option<u64>) -> Result<Content> {
+    let mut stdin = std::io::stdin().lock();
+    let mut bytes = Vec::new();
+    let limit = max_file_size.map_or(u64::MAX, |max| max.saturating_add(1));
+    (&mut stdin)
+        .take(limit)
+        .read_to_end(&mut bytes)
+        .context("Failed to read stdin")?;
+    if max_file_size.is_some_and(|max| bytes.len() as u64 > max) {
+        let rest =
+            std::io::copy(&mut stdin, &mut std::io::sink()).context("Failed to read stdin")?;
+        return Ok(Content::Stdin {
+            path: file,
+            text: None,
+            size: bytes.len() as u64 + rest,
+        });
+    }
+    let size = bytes.len() as u64;
+    let text = String::from_utf8(bytes).map_err(|_| {
+        anyhow::anyhow!(
+            "stdin is not UTF-8 text; can't lint it as {}",
+            file.display()
+        )
+    })?;
+    Ok(Content::Stdin {
+        path: file,
+        text: Some(text),
+        size,
+    })
+}
+
+/// Files under `path` staged for the next commit, with their staged blobs
+fn collect_staged_files(path: &Path) -> Result<GitContent> {
+    let (dir, scope) = git_scope(path);
+    let toplevel = git::toplevel(dir)?;
+    let blobs: HashMap<PathBuf, String> = git::staged_files(&toplevel)?
+        .into_iter()
+        .filter_map(|file| Some((scoped_path(path, &scope, &file.path)?, file.blob)))
+        .collect();
+    eprintln!("Linting {} staged file(s)", blobs.len());
+    Ok(GitContent { toplevel, blobs })
+}
+
+/// Files under `path` in the git tree `rev` names, with their blobs
+fn collect_tree_files(path: &Path, rev: &str) -> Result<GitContent> {
+    let (dir, scope) = git_scope(path);
+    let toplevel = git::toplevel(dir)?;
+    let blobs: HashMap<PathBuf, String> = git::tree_files(&toplevel, rev)?
+        .into_iter()
+        .filter_map(|file| Some((scoped_path(path, &scope, &file.path)?, file.blob)))
+        .collect();
+    eprintln!("Linting {} file(s) of {}", blobs.len(), rev);
+    Ok(GitContent { toplevel, blobs })
+}
+
+/// Files under `path` git reports as changed since `base`, or since the
+/// default base when `base` is empty
+fn collect_changed_files(path: &Path, base: &str) -> Result<Vec<PathBuf>> {
+    let (toplevel, base, commit) = git_base(path, base, "HEAD")?;
+    let (_, scope) = git_scope(path);
+    let files: Vec<PathBuf> = git::changed_files(&toplevel, &commit)?
+        .into_iter()
+        .filter(|file| file.is_file())
+        .filter_map(|file| scoped_path(path, &scope, &file))
+        .collect();
+    eprintln!(
+        "Linting {} file(s) changed since {} ({})",
+        files.len(),
+        base,
+        &commit[..commit.len().min(12)]
+    );
+    Ok(files)
+}
+
+/// Files to lint at `path`: the file itself, or the files in the directory,
+/// its subdirectories too when `recursive`
+///
+/// With `respect_ignore`, files .gitignore, .ignore and git's excludes leave
+/// out are skipped, like in other tools that walk a repository. A file given
+/// directly is linted either way.
+pub(crate) fn collect_files(
+    path: &PathBuf,
+    recursive: bool,
+    respect_ignore: bool,
+) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+
+    if path.is_file() {
+        files.push(path.clone());
+    } else if path.is_dir() {
+        // Dotfiles are linted like any other file, but git's database and
+        // forseti's own state, cached results included, never are
+        let walk = ignore::WalkBuilder::new(path)
+            .standard_filters(respect_ignore)
+            .hidden(false)
+            .max_depth((!recursive).then_some(1))
+            .filter_entry(|e| {
+                let is_dir = e.file_type().is_some_and(|t| t.is_dir());
+                !(is_dir && (e.file_name() == state::STATE_DIR || e.file_name() == ".git"))
+            })
+            .build();
+        for entry in walk.filter_map(|e| e.ok()) {
+            if entry.file_type().is_some_and(|t| t.is_file()) {
+                files.push(entry.into_path());
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct RulesetInfo {
+    id: String,
+    launcher: Launcher,
+    /// Version from the installed manifest, when the ruleset shipped one
+    version: Option<String>,
+    /// Language ids the installed manifest declares; empty for any language
+    languages: Vec<String>,
+}
+
+impl RulesetInfo {
+    /// Whether files of `language` it receives count as covered; a ruleset
+    /// whose manifest names languages only covers those
+    fn claims(&self, language: Option<&Language>) -> bool {
+        self.languages.is_empty()
+            || language.is_some_and(|language| self.languages.iter().any(|id| id == language.id))
+    }
+
+    /// A ruleset binary outside any cache, e.g. one being developed
+    pub(crate) fn binary(id: &str, path: &Path) -> Self {
+        RulesetInfo {
+            id: id.to_string(),
+            launcher: Launcher::Binary(path.to_path_buf()),
+            version: None,
+            languages: Vec::new(),
+        }
+    }
+
+    /// What identifies the installed ruleset to the result cache: the
+    /// binary's path, size and modification time along with the packages
+    /// behind an npm or pip launcher, or the image's digest
+    pub(crate) fn identity(&self) -> String {
+        let installed = match &self.launcher {
+            Launcher::Binary(path) => {
+                let metadata = fs::metadata(path).ok();
+                let stamp = metadata.map(|m| (m.len(), m.modified().ok()));
+                // Upgrading a package leaves its launcher untouched
+                let packages = install::installed_packages(path);
+                format!("{}:{:?}:{:?}", path.display(), stamp, packages)
+            }
+            Launcher::Container { image, .. } => {
+                // A pull can move the tag to new content
+                format!("{}@{:?}", image, container::image_digest(image))
+            }
+        };
+        format!("{}:{:?}", installed, self.version)
+    }
+}
+
+/// How a ruleset process is started
+#[derive(Debug, Clone)]
+enum Launcher {
+    /// A native executable speaking the stdio protocol
+    Binary(PathBuf),
+    /// A container image run with the workspace mounted read-only
+    Container { image: String, workspace: PathBuf },
+}
+
+impl Launcher {
+    /// The command starting the ruleset, with `env` set in its environment
+    fn command(&self, env: &[(&str, String)]) -> Command {
+        match self {
+            Launcher::Binary(path) => {
+                let mut command = Command::new(path);
+                command.envs(env.iter().map(|(name, value)| (name, value)));
+                command
+            }
+            Launcher::Container { image, workspace } => {
+                container::run_command(image, workspace, env)
+            }
+        }
+    }
+}
+
+fn discover_rulesets(
+    cache_dir: &PathBuf,
+    system_dirs: &[PathBuf],
+    config: &Config,
+    settings: &Settings,
+    workspace_root: &Path,
+) -> Result<Vec<RulesetInfo>> {
+    let mut rulesets = Vec::new();
+
+    // Container-based rulesets take precedence over any binary
+    for ruleset_id in config.ruleset.keys() {
+        if let Some(image) = settings.ruleset(ruleset_id).image {
+            rulesets.push(RulesetInfo {
+                id: ruleset_id.clone(),
+                launcher: Launcher::Container {
+                    image,
+                    workspace: workspace_root.to_path_buf(),
+                },
+                version: None,
+                languages: Vec::new(),
+            });
+        }
+    }
+
+    // First, check for rulesets configured with local paths
+    for (ruleset_id, ruleset_cfg) in &config.ruleset {
+        if rulesets.iter().any(|r| &r.id == ruleset_id) {
+            continue;
+        }
+        if let Some(local_path) = &ruleset_cfg.path {
+            let path = PathBuf::from(local_path);
+            if path.exists() && path.is_file() {
+                rulesets.push(RulesetInfo {
+                    id: ruleset_id.clone(),
+                    launcher: Launcher::Binary(path),
+                    version: None,
+                    languages: Vec::new(),
+                });
+            }
+        }
+    }
+
+    // Then the project's pinned rulesets, fetched by the gen-installer scripts
+    discover_in_dir(
+        &state::ProjectState::new(workspace_root).vendor_dir(),
+        &mut rulesets,
+    );
+
+    // Then, look for rulesets in cache directory
+    if cache_dir.exists() {
+        let entries = fs::read_dir(cache_dir)?;
+        for entry in entries {
+            let entry = entry?;
+            let path = entry.path();
+
+            if path.is_dir() {
+                let bin_dir = path.join("bin");
+                if bin_dir.exists() {
+                    let bin_entries = fs::read_dir(bin_dir)?;
+                    for bin_entry in bin_entries {
+                        let bin_entry = bin_entry?;
+                        let bin_path = bin_entry.path();
+
+                        if bin_path.is_file() {
+                            let file_name = bin_path.file_name().unwrap().to_string_lossy();
+                            if file_name.starts_with("forseti_ruleset_") {
+                                let ruleset_id =
+                                    file_name.strip_prefix("forseti_ruleset_").unwrap();
+                                // Launcher scripts for npm/pip rulesets carry an extension on Windows
+                                let ruleset_id = strip_executable_suffix(ruleset_id).to_string();
+
+                                // Only add if not already found via local path
+                                if !rulesets.iter().any(|r| r.id == ruleset_id) {
+                                    let manifest = Manifest::load(&path.join(MANIFEST_FILE)).ok();
+                                    rulesets.push(RulesetInfo {
+                                        id: ruleset_id,
+                                        launcher: Launcher::Binary(bin_path),
+                                        version: manifest.as_ref().map(|m| m.version.clone()),
+                                        languages: manifest
+                                            .map(|m| m.languages)
+                                            .unwrap_or_default(),
+                                    });
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Last, binaries shipped by OS package managers; user installs win
+    for dir in system_dirs {
+        discover_in_dir(dir, &mut rulesets);
+    }
+
+    // Finally `forseti-ruleset-<id>` executables on PATH, like cargo
+    // subcommands, so a globally `cargo install`ed ruleset needs no setup
+    if let Some(path_var) = std::env::var_os("PATH") {
+        for dir in std::env::split_paths(&path_var) {
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let file_name = entry.file_name().to_string_lossy().into_owned();
+                let Some(name) = file_name.strip_prefix("forseti-ruleset-") else {
+                    continue;
+                };
+                let name = strip_executable_suffix(name);
+                // Crate names use `-` where config keys often use `_`
+                let ruleset_id = if config.ruleset.contains_key(name) {
+                    name.to_string()
+                } else {
+                    let underscored = name.replace('-', "_");
+                    if config.ruleset.contains_key(&underscored) {
+                        underscored
+                    } else {
+                        name.to_string()
+                    }
+                };
+                let bin_path = entry.path();
+                if is_executable(&bin_path) && !rulesets.iter().any(|r| r.id == ruleset_id) {
+                    rulesets.push(RulesetInfo {
+                        id: ruleset_id,
+                        launcher: Launcher::Binary(bin_path),
+                        version: None,
+                        languages: Vec::new(),
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(rulesets)
+}
+
+/// Add the `forseti_ruleset_<id>` binaries in a flat directory, unless a
+/// ruleset was already found elsewhere
+fn discover_in_dir(dir: &Path, rulesets: &mut Vec<RulesetInfo>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let bin_path = entry.path();
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        let Some(ruleset_id) = file_name.strip_prefix("forseti_ruleset_") else {
+            continue;
+        };
+        let ruleset_id = strip_executable_suffix(ruleset_id).to_string();
+        if bin_path.is_file() && !rulesets.iter().any(|r| r.id == ruleset_id) {
+            // Packages may ship `<id>.forseti-ruleset.toml` next to the binary
+            let manifest = dir.join(format!("{}.{}", ruleset_id, MANIFEST_FILE));
+            let manifest = Manifest::load(&manifest).ok();
+            rulesets.push(RulesetInfo {
+                id: ruleset_id,
+                launcher: Launcher::Binary(bin_path),
+                version: manifest.as_ref().map(|m| m.version.clone()),
+                languages: manifest.map(|m| m.languages).unwrap_or_default(),
+            });
+        }
+    }
+}
+
+fn is_executable(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+    }
+    #[cfg(not(unix))]
+    {
+        path.is_file()
+    }
+}
+
+/// Ruleset id part of a binary name, without a Windows `.exe`/`.cmd` extension
+fn strip_executable_suffix(name: &str) -> &str {
+    name.strip_suffix(".exe")
+        .or_else(|| name.strip_suffix(".cmd"))
+        .unwrap_or(name)
+}
+
+/// Ruleset processes kept alive between files, so each is initialized once
+/// and shut down at the end of the run rather than once per file
+#[derive(Default)]
+pub(crate) struct SessionPool {
+    idle: Mutex<HashMap<String, Vec<RulesetSession>>>,
+    /// `filePatterns` each ruleset reported, once a session has asked
+    patterns: Mutex<HashMap<String, Option<Arc<GlobSet>>>>,
+    /// Processes that exited unsuccessfully while idle between files
+    exits: Mutex<Vec<(String, std::process::ExitStatus)>>,
+}
+
+impl SessionPool {
+    /// An idle session for the ruleset, else its preloaded one, else a new one
+    fn take(
+        &self,
+        workspace: &Workspace,
+        ruleset: &RulesetInfo,
+        config: &toml::value::Table,
+    ) -> Result<RulesetSession> {
+        let mut idle = self.idle.lock().unwrap();
+        while let Some(mut session) = idle.get_mut(&ruleset.id).and_then(Vec::pop) {
+            // Skip processes that exited since their last file
+            match session.exit_status() {
+                None => return Ok(session),
+                Some(status) if !status.success() => {
+                    self.exits
+                        .lock()
+                        .unwrap()
+                        .push((ruleset.id.clone(), status));
+                }
+                Some(_) => {}
+            }
+        }
+        drop(idle);
+        workspace
+            .take_preloaded(&ruleset.id)
+            .unwrap_or_else(|| RulesetSession::start(ruleset, config, workspace.run))
+    }
+
+    /// The `filePatterns` a ruleset reported during initialize, compiled,
+    /// starting a session to find out the first time; `None` when it
+    /// reported none and takes every file
+    fn reported_patterns(
+        &self,
+        workspace: &Workspace,
+        ruleset: &RulesetInfo,
+        config: &toml::value::Table,
+    ) -> Result<Option<Arc<GlobSet>>> {
+        if let Some(patterns) = self.patterns.lock().unwrap().get(&ruleset.id) {
+            return Ok(patterns.clone());
+        }
+        let session = self.take(workspace, ruleset, config)?;
+        let patterns = session
+            .file_patterns
+            .as_deref()
+            .map(filters::glob_set)
+            .transpose()
+            .with_context(|| format!("Ruleset {} reported an invalid file pattern", ruleset.id))?
+            .map(Arc::new);
+        self.put(session);
+        self.patterns
+            .lock()
+            .unwrap()
+            .insert(ruleset.id.clone(), patterns.clone());
+        Ok(patterns)
+    }
+
+    /// Send `request` to a session from the pool, keeping the session for
+    /// the next file when it answered; a reused process that turns out to
+    /// have exited since its last file gets the request retried once on
+    /// another session
+    fn run<T>(
+        &self,
+        workspace: &Workspace,
+        ruleset: &RulesetInfo,
+        config: &toml::value::Table,
+        request: impl Fn(&mut RulesetSession) -> Result<T>,
+    ) -> Result<T> {
+        let mut session = self.take(workspace, ruleset, config)?;
+        let reused = session.requests > 0;
+        let mut result = request(&mut session);
+        if result.is_err() && reused && session.gone() {
+            if let Some(status) = session.exit_status()
+                && !status.success()
+            {
+                self.exits
+                    .lock()
+                    .unwrap()
+                    .push((ruleset.id.clone(), status));
+            }
+            session.kill();
+            session = self.take(workspace, ruleset, config)?;
+            result = request(&mut session);
+        }
+        // A process that failed may be stuck mid-response, so it isn't reused
+        match result {
+            Ok(_) => self.put(session),
+            Err(_) => session.kill(),
+        }
+        result
+    }
+
+    /// Keep a session that answered for the next file, unless its process
+    /// already exited
+    fn put(&self, session: RulesetSession) {
+        if session.exited {
+            return;
+        }
+        self.idle
+            .lock()
+            .unwrap()
+            .entry(session.ruleset_id.clone())
+            .or_default()
+            .push(session);
+    }
+
+    /// Rulesets whose processes exited unsuccessfully between files since
+    /// last asked, with how they exited
+    pub(crate) fn take_exits(&self) -> Vec<(String, std::process::ExitStatus)> {
+        std::mem::take(&mut *self.exits.lock().unwrap())
+    }
+
+    /// Shut every session down, returning those that failed to exit cleanly
+    pub(crate) fn shutdown(self) -> Vec<(String, anyhow::Error)> {
+        let mut failures = Vec::new();
+        let idle = self.idle.into_inner().unwrap();
+        for (ruleset_id, sessions) in idle {
+            for session in sessions {
+                if let Err(e) = session.shutdown() {
+                    failures.push((ruleset_id.clone(), e));
+                }
+            }
+        }
+        failures.sort_by(|(a, _), (b, _)| a.cmp(b));
+        failures
+    }
+}
+
+/// A ruleset process that has completed the initialize handshake
+pub(crate) struct RulesetSession {
+    ruleset_id: String,
+    child: Child,
+    writer: ChildStdin,
+    messages: MessageReader,
+    version: Option<String>,
+    /// Rule ids listed in the initialize response, when the ruleset lists them
+    rules: Option<Vec<String>>,
+    /// Analysis requests sent so far, numbering their ids
+    requests: usize,
+    /// Whether the ruleset declared the `analyzeBatch` capability
+    batch: bool,
+    /// Globs naming the files the ruleset understands, when it reported any
+    file_patterns: Option<Vec<String>>,
+    /// Reject events carrying wall-clock times, see [`RunInputs`]
+    deterministic: bool,
+    /// Whether the process died mid-analysis, so it can't take another file
+    exited: bool,
+}
+
+impl RulesetSession {
+    /// Start the ruleset process and initialize it
+    pub(crate) fn start(
+        ruleset: &RulesetInfo,
+        config: &toml::value::Table,
+        run: RunInputs,
+    ) -> Result<Self> {
+        // Start the ruleset process
+        let mut child = ruleset
+            .launcher
+            .command(&run.environment())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Failed to start ruleset: {}", ruleset.id))?;
+
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+
+        // Bounded so a ruleset flooding events is paused instead of buffered
+        let (tx, rx) = std::sync::mpsc::sync_channel(MESSAGE_BUFFER);
+        std::thread::spawn(move || read_messages(stdout, tx));
+
+        // Send initialization request
+        let mut writer = stdin;
+        let init_request = initialize_request(&ruleset.id, config, run);
+
+        writeln!(writer, "{}", serde_json::to_string(&init_request)?)?;
+
+        // Wait for initialization response
+        let mut messages = MessageReader {
+            rx,
+            framing_errors: Vec::new(),
+            disconnected: false,
+        };
+        let init_response = messages.next(std::time::Duration::from_secs(5), "initialization")?;
+        let init_res: Value = serde_json::from_str(&init_response)
+            .context("Protocol violation: malformed initialization response")?;
+        let version = init_res
+            .pointer("/payload/version")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        // Either `["rule", ...]` or `[{ "id": "rule", ... }, ...]`
+        let rules = init_res
+            .pointer("/payload/rules")
+            .and_then(Value::as_array)
+            .map(|rules| {
+                rules
+                    .iter()
+                    .filter_map(|rule| rule.as_str().or_else(|| rule["id"].as_str()))
+                    .map(str::to_string)
+                    .collect()
+            });
+        // Rulesets opt into batches with `capabilities: { analyzeBatch: true }`
+        let batch = init_res
+            .pointer("/payload/capabilities/analyzeBatch")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        // e.g. `filePatterns: ["*.tf", "*.tfvars"]`
+        let file_patterns = init_res
+            .pointer("/payload/filePatterns")
+            .and_then(Value::as_array)
+            .map(|patterns| {
+                patterns
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(str::to_string)
+                    .collect()
+            });
+
+        Ok(Self {
+            ruleset_id: ruleset.id.clone(),
+            child,
+            writer,
+            messages,
+            version,
+            rules,
+            requests: 0,
+            batch,
+            file_patterns,
+            deterministic: run.deterministic,
+            exited: false,
+        })
+    }
+
+    /// Analyze one file, leaving the process running for the next
+    pub(crate) fn analyze(&mut self, file_uri: &str, content: &str) -> Result<Analysis> {
+        // Send analyze file request
+        self.requests += 1;
+        let request_id = format!("analyze-{}", self.requests);
+        let analyze_request = json!({
+            "v": PROTOCOL_VERSION,
+            "kind": "req",
+            "type": "analyzeFile",
+            "id": request_id,
+            "payload": file_payload(file_uri, content)
+        });
+
+        let requested_at = timestamp();
+        writeln!(self.writer, "{}", serde_json::to_string(&analyze_request)?)?;
+
+        let mut analyses = self.collect_analyses(&request_id, &requested_at, &[file_uri])?;
+        Ok(analyses.remove(0))
+    }
+
+    /// Analyze several files, given as uri and content, in one `analyzeBatch`
+    /// request; only for rulesets that declared the capability
+    ///
+    /// Returns one analysis per file, in order.
+    pub(crate) fn analyze_batch(&mut self, files: &[(&str, &str)]) -> Result<Vec<Analysis>> {
+        self.requests += 1;
+        let request_id = format!("batch-{}", self.requests);
+        let payload: Vec<Value> = files
+            .iter()
+            .map(|(uri, content)| file_payload(uri, content))
+            .collect();
+        let batch_request = json!({
+            "v": PROTOCOL_VERSION,
+            "kind": "req",
+            "type": "analyzeBatch",
+            "id": request_id,
+            "payload": {
+                "files": payload
+            }
+        });
+
+        let requested_at = timestamp();
+        writeln!(self.writer, "{}", serde_json::to_string(&batch_request)?)?;
+
+        let uris: Vec<&str> = files.iter().map(|(uri, _)| *uri).collect();
+        self.collect_analyses(&request_id, &requested_at, &uris)
+    }
+
+    /// Read events until the response to `request_id`, sorting them into one
+    /// analysis per file in `uris`
+    ///
+    /// Events in a batch name their file with a `uri` in the payload; those
+    /// that don't, or answer a single file, belong to the first file.
+    fn collect_analyses(
+        &mut self,
+        request_id: &str,
+        requested_at: &str,
+        uris: &[&str],
+    ) -> Result<Vec<Analysis>> {
+        let mut analyses: Vec<Analysis> = uris.iter().map(|_| Analysis::default()).collect();
+        let mut events = 0;
+        // Rulesets may take a while on each file before the first event
+        let timeout = std::time::Duration::from_secs(10) * uris.len() as u32;
+
+        loop {
+            let response = match self.messages.next(timeout, "analysis") {
+                Ok(response) => response,
+                // Diagnostics sent before dying are kept, with how the
+                // process exited reported as an anomaly instead
+                Err(_) if self.messages.disconnected && events > 0 => {
+                    let status = match self.child.wait() {
+                        Ok(status) => status.to_string(),
+                        Err(e) => e.to_string(),
+                    };
+                    analyses[0].exit =
+                        Some(format!("process exited with {} before answering", status));
+                    self.exited = true;
+                    break;
+                }
+                Err(e) => return Err(e),
+            };
+            let msg: Value = serde_json::from_str(&response)
+                .context("Protocol violation: malformed message from ruleset")?;
+
+            let kind = msg.get("kind").and_then(Value::as_str);
+            if kind == Some("res") && msg.get("id").and_then(Value::as_str) == Some(request_id) {
+                break;
+            }
+            if kind != Some("event") {
+                continue;
+            }
+            let payload = &msg["payload"];
+            if self.deterministic
+                && let Some(found) = find_wall_clock(payload)
+            {
+                self.messages.framing_errors.push(format!(
+                    "rejected a {} event carrying a wall-clock time ({}) in a \
+                     --deterministic run",
+                    msg.get("type").and_then(Value::as_str).unwrap_or("unknown"),
+                    found
+                ));
+                continue;
+            }
+            let file = match payload.get("uri").and_then(Value::as_str) {
+                Some(uri) if uris.len() > 1 => uris.iter().position(|known| *known == uri),
+                _ => Some(0),
+            };
+
+            match msg.get("type").and_then(Value::as_str) {
+                Some("diagnostics") => {
+                    let Some(diags) = payload.get("diagnostics").and_then(Value::as_array) else {
+                        continue;
+                    };
+                    let received_at = timestamp();
+                    // Diagnostics for a file outside the request can't be placed
+                    let Some(file) = file else {
+                        analyses[0].dropped += diags.len();
+                        continue;
+                    };
+                    let analysis = &mut analyses[file];
+                    for diag in diags {
+                        match serde_json::from_value::<Diagnostic>(diag.clone()) {
+                            Ok(diagnostic) => {
+                                analysis.diagnostics.push(diagnostic);
+                                analysis.provenance.push(Provenance {
+                                    ruleset_id: self.ruleset_id.clone(),
+                                    pid: self.child.id(),
+                                    request_id: request_id.to_string(),
+                                    event: events,
+                                    requested_at: requested_at.to_string(),
+                                    received_at: received_at.clone(),
+                                });
+                            }
+                            Err(_) => analysis.dropped += 1,
+                        }
+                    }
+                    events += 1;
+                }
+                Some("metrics") => {
+                    // `{ "metrics": { "todoCount": 3, ... } }`, non-numbers ignored
+                    let metrics = &mut analyses[file.unwrap_or(0)].metrics;
+                    let values = payload.get("metrics").and_then(Value::as_object);
+                    for (name, value) in values.into_iter().flatten() {
+                        if let Some(value) = value.as_f64() {
+                            *metrics.entry(name.clone()).or_default() += value;
+                        }
+                    }
+                }
+                Some("artifact") => {
+                    // A malformed artifact can't be written, and its file
+                    // result isn't cached so the problem shows up again
+                    match serde_json::from_value::<Artifact>(payload.clone()) {
+                        Ok(artifact) => analyses[file.unwrap_or(0)].artifacts.push(artifact),
+                        Err(e) => self
+                            .messages
+                            .framing_errors
+                            .push(format!("malformed artifact event: {}", e)),
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for analysis in &mut analyses {
+            analysis.version = self.version.clone();
+        }
+        analyses[0].framing_errors = std::mem::take(&mut self.messages.framing_errors);
+        Ok(analyses)
+    }
+
+    /// Version the ruleset reported during initialize
+    pub(crate) fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    /// Write a raw line to the process, well-formed or not
+    pub(crate) fn send_line(&mut self, line: &str) -> Result<()> {
+        writeln!(self.writer, "{}", line)?;
+        Ok(self.writer.flush()?)
+    }
+
+    /// Wait for the response to request `id`, skipping events and other
+    /// responses
+    pub(crate) fn response(&mut self, id: &str, timeout: std::time::Duration) -> Result<Value> {
+        let deadline = std::time::Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+            let message: Value = serde_json::from_str(&self.messages.next(remaining, id)?)
+                .context("Protocol violation: malformed message from ruleset")?;
+            if message.get("kind").and_then(Value::as_str) == Some("res")
+                && message.get("id").and_then(Value::as_str) == Some(id)
+            {
+                return Ok(message);
+            }
+        }
+    }
+
+    /// How the process exited, or `None` while it is still running
+    pub(crate) fn exit_status(&mut self) -> Option<std::process::ExitStatus> {
+        self.child.try_wait().ok().flatten()
+    }
+
+    /// Whether the process closed its stdout or exited, so it can't answer
+    fn gone(&mut self) -> bool {
+        self.messages.disconnected || self.exit_status().is_some()
+    }
+
+    /// Stop the process without asking, e.g. after it stopped responding
+    pub(crate) fn kill(mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+
+    /// Close the process's stdin without a shutdown request and report
+    /// whether it exits within `timeout`, killing it otherwise
+    pub(crate) fn exits_on_eof(self, timeout: std::time::Duration) -> Result<bool> {
+        let Self {
+            mut child, writer, ..
+        } = self;
+        drop(writer);
+        let deadline = std::time::Instant::now() + timeout;
+        while std::time::Instant::now() < deadline {
+            if child.try_wait()?.is_some() {
+                return Ok(true);
+            }
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
+        let _ = child.kill();
+        let _ = child.wait();
+        Ok(false)
+    }
+
+    /// Ask the process to exit and wait for it
+    pub(crate) fn shutdown(mut self) -> Result<()> {
+        // Send shutdown request
+        let shutdown_request = json!({
+            "v": PROTOCOL_VERSION,
+            "kind": "req",
+            "type": "shutdown",
+            "id": "shutdown"
+        });
+
+        let _ = writeln!(self.writer, "{}", serde_json::to_string(&shutdown_request)?);
+
+        // Wait for process to finish
+        let status = self.child.wait()?;
+        if !status.success() {
+            return Err(anyhow::anyhow!("Ruleset process exited with {}", status));
+        }
+        Ok(())
+    }
+}
+
+/// A file as sent for analysis, with a `languageId` hint when its name or
+/// shebang line gives the language away
+fn file_payload(uri: &str, content: &str) -> Value {
+    let mut payload = json!({ "uri": uri, "content": content });
+    let name = uri.rsplit('/').next().unwrap_or(uri);
+    if let Some(language) = languages::detect_content(Path::new(name), content) {
+        payload["languageId"] = json!(language.id);
+    }
+    payload
+}
+
+/// The first request sent to every ruleset process
+pub(crate) fn initialize_request(
+    ruleset_id: &str,
+    config: &toml::value::Table,
+    run: RunInputs,
+) -> Value {
+    let variables: serde_json::Map<String, Value> = run
+        .environment()
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), json!(value)))
+        .collect();
+    json!({
+        "v": PROTOCOL_VERSION,
+        "kind": "req",
+        "type": "initialize",
+        "id": "init",
+        "payload": {
+            "rulesetId": ruleset_id,
+            "workspaceRoot": ".",
+            "rulesetConfig": config,
+            "run": {
+                "seed": run.seed,
+                "deterministic": run.deterministic
+            },
+            "environment": {
+                "os": std::env::consts::OS,
+                "arch": std::env::consts::ARCH,
+                "variables": variables
+            }
+        }
+    })
+}
+
+/// What a ruleset is told about the run it is part of
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RunInputs {
+    /// For rulesets that sample or shuffle, so they can do it reproducibly
+    pub seed: u64,
+    /// Rulesets run in a normalized environment and events carrying
+    /// wall-clock times are rejected, so identical input gives identical
+    /// output
+    pub deterministic: bool,
+}
+
+impl RunInputs {
+    /// `seed` when given, else 0 for deterministic runs and a random one
+    /// for the others
+    pub(crate) fn new(seed: Option<u64>, deterministic: bool) -> Self {
+        let seed = seed.unwrap_or_else(|| {
+            if deterministic {
+                return 0;
+            }
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default();
+            now.as_nanos() as u64 ^ u64::from(std::process::id()).rotate_left(32)
+        });
+        Self {
+            seed,
+            deterministic,
+        }
+    }
+
+    /// Variables set for deterministic ruleset processes, pinning time
+    /// zone, locale, build time and Python's hash seed; none otherwise
+    pub(crate) fn environment(&self) -> Vec<(&'static str, String)> {
+        if !self.deterministic {
+            return Vec::new();
+        }
+        vec![
+            ("TZ", "UTC".to_string()),
+            ("LC_ALL", "C".to_string()),
+            ("LANG", "C".to_string()),
+            ("SOURCE_DATE_EPOCH", source_date_epoch().to_string()),
+            ("PYTHONHASHSEED", (self.seed % (1 << 32)).to_string()),
+        ]
+    }
+}
+
+/// Build time deterministic runs report: `SOURCE_DATE_EPOCH` when the build
+/// system sets it, else the Unix epoch
+pub(crate) fn source_date_epoch() -> i64 {
+    std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+/// Keys that only ever hold wall-clock times, compared ignoring case and `_`
+const WALL_CLOCK_KEYS: &[&str] = &[
+    "timestamp",
+    "time",
+    "datetime",
+    "generatedat",
+    "createdat",
+    "updatedat",
+    "startedat",
+    "finishedat",
+    "elapsed",
+    "elapsedms",
+    "durationms",
+];
+
+/// Where an event payload carries a wall-clock time: a key like `timestamp`
+/// or `generatedAt`, or a string containing a date and time such as
+/// `2025-03-01T12:00`
+fn find_wall_clock(value: &Value) -> Option<String> {
+    match value {
+        Value::Object(map) => map.iter().find_map(|(key, value)| {
+            let normalized = key.replace('_', "").to_ascii_lowercase();
+            if WALL_CLOCK_KEYS.contains(&normalized.as_str()) {
+                Some(format!("key '{}'", key))
+            } else {
+                find_wall_clock(value)
+            }
+        }),
+        Value::Array(items) => items.iter().find_map(find_wall_clock),
+        Value::String(text) if contains_date_time(text) => Some(format!("'{}'", preview(text))),
+        _ => None,
+    }
+}
+
+/// Whether `text` contains `YYYY-MM-DD` followed by `T` or a space and `HH:MM`
+fn contains_date_time(text: &str) -> bool {
+    const SHAPE: &[u8] = b"0000-00-00T00:00";
+    text.as_bytes().windows(SHAPE.len()).any(|window| {
+        window.iter().zip(SHAPE).all(|(byte, shape)| match shape {
+            b'0' => byte.is_ascii_digit(),
+            b'T' => matches!(byte, b'T' | b't' | b' '),
+            _ => byte == shape,
+        })
+    })
+}
+
+/// Current time in RFC 3339 with millisecond precision
+fn timestamp() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+/// One line read from a ruleset's stdout
+enum Frame {
+    /// A well-formed protocol message
+    Message(String),
+    /// Output that isn't a protocol message, with a description of the problem
+    Garbage(String),
+}
+
+/// Forward protocol lines from a ruleset's stdout until it closes
+///
+/// Sending blocks while the channel is full, which in turn stops draining the
+/// pipe and stalls the ruleset until the consumer catches up. Oversized lines,
+/// invalid UTF-8 and stray prints are reported as garbage and reading resumes
+/// at the next line.
+fn read_messages(stdout: ChildStdout, tx: SyncSender<Frame>) {
+    let mut reader = BufReader::new(stdout);
+    loop {
+        let mut line = Vec::new();
+        let frames = match (&mut reader)
+            .take(MAX_MESSAGE_BYTES as u64 + 1)
+            .read_until(b'\n', &mut line)
+        {
+            Ok(0) | Err(_) => break,
+            Ok(_) if line.len() > MAX_MESSAGE_BYTES && !line.ends_with(b"\n") => {
+                let skipped = line.len() + skip_line(&mut reader);
+                vec![Frame::Garbage(format!(
+                    "skipped a {} byte line, over the {} byte limit",
+                    skipped, MAX_MESSAGE_BYTES
+                ))]
+            }
+            Ok(_) => match String::from_utf8(line) {
+                Ok(line) => split_frames(line.trim_end_matches(['\r', '\n'])),
+                Err(_) => vec![Frame::Garbage(
+                    "skipped a line that is not valid UTF-8".to_string(),
+                )],
+            },
+        };
+        for frame in frames {
+            if tx.send(frame).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Discard the rest of the current line, returning how many bytes were skipped
+fn skip_line(reader: &mut BufReader<ChildStdout>) -> usize {
+    let mut skipped = 0;
+    loop {
+        let mut chunk = Vec::new();
+        match reader.take(64 * 1024).read_until(b'\n', &mut chunk) {
+            Ok(0) | Err(_) => return skipped,
+            Ok(n) => {
+                skipped += n;
+                if chunk.ends_with(b"\n") {
+                    return skipped;
+                }
+            }
+        }
+    }
+}
+
+/// Classify a line, recovering a message glued to the end of stray output
+fn split_frames(line: &str) -> Vec<Frame> {
+    if line.trim().is_empty() {
+        return Vec::new();
+    }
+    if is_message(line) {
+        return vec![Frame::Message(line.to_string())];
+    }
+    // e.g. `debug: starting{"v":1,...}` from a print without a newline
+    if let Some(start) = line.find('{')
+        && start > 0
+        && is_message(&line[start..])
+    {
+        return vec![
+            Frame::Garbage(format!("stray output: {}", preview(&line[..start]))),
+            Frame::Message(line[start..].to_string()),
+        ];
+    }
+    vec![Frame::Garbage(format!("stray output: {}", preview(line)))]
+}
+
+fn is_message(text: &str) -> bool {
+    serde_json::from_str::<Value>(text).is_ok_and(|value| value.is_object())
+}
+
+fn preview(text: &str) -> String {
+    const MAX_CHARS: usize = 60;
+    let text = text.trim();
+    if text.chars().count() > MAX_CHARS {
+        format!("{}...", text.chars().take(MAX_CHARS).collect::<String>())
+    } else {
+        text.to_string()
+    }
+}
+
+/// Receiving end of a ruleset's stdout, collecting framing errors as it goes
+struct MessageReader {
+    rx: Receiver<Frame>,
+    framing_errors: Vec<String>,
+    /// Whether the process closed its stdout, usually by exiting
+    disconnected: bool,
+}
+
+impl MessageReader {
+    /// Wait for the next protocol message, telling a timeout apart from a
+    /// crashed process; garbage in between is recorded and skipped
+    fn next(&mut self, timeout: std::time::Duration, stage: &str) -> Result<String> {
+        let deadline = std::time::Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+            match self.rx.recv_timeout(remaining) {
+                Ok(Frame::Message(message)) => return Ok(message),
+                Ok(Frame::Garbage(error)) => self.framing_errors.push(error),
+                Err(RecvTimeoutError::Timeout) => {
+                    return Err(anyhow::anyhow!("Timeout waiting for {} response", stage));
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.disconnected = true;
+                    return Err(anyhow::anyhow!(
+                        "Ruleset process exited before sending {} response",
+                        stage
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// Add the run's diagnostics, those below `--severity` included, to the
+/// `--store` database
+#[cfg(feature = "store")]
+fn record_run(
+    store: &mut ResultStore,
+    metadata: &RunMetadata,
+    file_results: &[FileResult],
+    files: usize,
+    workspace_root: &Path,
+) -> Result<()> {
+    let rows: Vec<(String, String, &FileResult, &Finding)> = file_results
+        .iter()
+        .flat_map(|result| {
+            let path = relative_path(&result.path, workspace_root);
+            result.findings.iter().map(move |finding| {
+                let rule = rules::qualify(&result.ruleset_id, &finding.diagnostic.rule_id);
+                (path.clone(), rule, result, finding)
+            })
+        })
+        .collect();
+    let diagnostics: Vec<StoredDiagnostic> = rows
+        .iter()
+        .map(|(path, rule, result, finding)| {
+            let range = &finding.diagnostic.range;
+            StoredDiagnostic {
+                path,
+                ruleset: &result.ruleset_id,
+                rule,
+                severity: &finding.diagnostic.severity,
+                start: (range.start.line + 1, range.start.character + 1),
+                end: (range.end.line + 1, range.end.character + 1),
+                message: &finding.diagnostic.message,
+                fingerprint: &finding.fingerprint,
+            }
+        })
+        .collect();
+    store.record(metadata, files, &diagnostics)
+}
+
+/// Write the artifacts rulesets sent under `dir`, or else under
+/// `.forseti/artifacts/` after clearing out the previous run's
+///
+/// Artifacts that can't be written are reported as plugin anomalies; of
+/// several with the same name from one ruleset, the last one wins.
+fn write_artifacts(
+    ctx: &GlobalContext,
+    workspace_root: &Path,
+    dir: Option<&Path>,
+    artifacts: Vec<(String, Artifact)>,
+    anomalies: &mut Vec<PluginAnomaly>,
+) -> Result<Vec<WrittenArtifact>> {
+    let project_state = state::ProjectState::new(workspace_root);
+    let default_dir = project_state.artifacts_dir();
+    if artifacts.is_empty() && (dir.is_some() || !default_dir.exists()) {
+        return Ok(Vec::new());
+    }
+    let (dir, _lock) = match dir {
+        Some(dir) => (dir.to_path_buf(), None),
+        None => {
+            let lock = project_state.lock()?;
+            if default_dir.exists() {
+                fs::remove_dir_all(&default_dir)
+                    .with_context(|| format!("Failed to clear {}", default_dir.display()))?;
+            }
+            (default_dir, Some(lock))
+        }
+    };
+
+    let mut written: Vec<WrittenArtifact> = Vec::new();
+    for (ruleset_id, artifact) in artifacts {
+        match artifact.write(&dir, &ruleset_id) {
+            Ok(artifact) => {
+                ctx.log_verbose(&format!(
+                    "Ruleset {} wrote artifact {} ({} bytes)",
+                    ruleset_id,
+                    artifact.path.display(),
+                    artifact.bytes
+                ));
+                written.retain(|w| !(w.ruleset == artifact.ruleset && w.name == artifact.name));
+                written.push(artifact);
+            }
+            Err(e) => {
+                let message = format!("artifact '{}' not written: {:#}", artifact.name, e);
+                eprintln!("Warning: ruleset {} {}", ruleset_id, message);
+                anomalies.push(PluginAnomaly {
+                    ruleset_id,
+                    file_path: None,
+                    message,
+                });
+            }
+        }
+    }
+    if !written.is_empty() {
+        eprintln!("Wrote {} artifact(s) to {}", written.len(), dir.display());
+    }
+    Ok(written)
+}
+
+/// Dump the provenance of every reported diagnostic, to `file` or stderr
+fn write_provenance(
+    file_results: &[FileResult],
+    workspace_root: &Path,
+    file: Option<PathBuf>,
+) -> Result<()> {
+    let entries: Vec<Value> = file_results
+        .iter()
+        .flat_map(|result| {
+            let path = relative_path(&result.path, workspace_root);
+            result.findings.iter().map(move |finding| {
+                json!({
+                    "path": path,
+                    "line": finding.diagnostic.range.start.line + 1,
+                    "ruleId": rules::qualify(&result.ruleset_id, &finding.diagnostic.rule_id),
+                    "fingerprint": finding.fingerprint,
+                    "provenance": finding.provenance,
+                })
+            })
+        })
+        .collect();
+    let dump = serde_json::to_string_pretty(&entries)?;
+
+    match file {
+        Some(file) => {
+            paths::write_atomic(&file, dump.as_bytes())
+                .with_context(|| format!("Failed to write provenance: {}", file.display()))?;
+            eprintln!("Provenance written to {}", file.display());
+        }
+        None => eprintln!("{}", dump),
+    }
+    Ok(())
+}
+
+/// Severity for low-confidence suggestions meant for editors: never fails a
+/// run and stays out of human and CI reports unless `[output] hints = true`
+pub(crate) const HINT: &str = "hint";
+
+/// The results without hint findings, or `None` when there are none to drop
+pub(crate) fn strip_hints(file_results: &[FileResult]) -> Option<Vec<FileResult>> {
+    retain_findings(file_results, |finding| finding.diagnostic.severity != HINT)
+}
+
+/// The results with only the findings `keep` accepts, or `None` when it
+/// accepts them all
+fn retain_findings(
+    file_results: &[FileResult],
+    keep: impl Fn(&Finding) -> bool,
+) -> Option<Vec<FileResult>> {
+    let drops_any = file_results
+        .iter()
+        .flat_map(|result| &result.findings)
+        .any(|finding| !keep(finding));
+    if !drops_any {
+        return None;
+    }
+    Some(
+        file_results
+            .iter()
+            .filter_map(|result| {
+                let mut result = result.clone();
+                result.findings.retain(&keep);
+                (!result.findings.is_empty()).then_some(result)
+            })
+            .collect(),
+    )
+}
+
+/// Path relative to the workspace root with `/` separators, falling back to
+/// the path as given when it lives outside the workspace
+pub(crate) fn relative_path(path: &Path, workspace_root: &Path) -> String {
+    let absolute = paths::canonicalize_missing(path);
+    match absolute.strip_prefix(workspace_root) {
+        Ok(relative) => relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/"),
+        Err(_) => paths::display(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use forseti_sdk::core::{Position, Range};
+
+    fn diagnostic(rule_id: &str, message: &str, start: (u32, u32), end: (u32, u32)) -> Diagnostic {
+        let position = |(line, character)| Position { line, character };
+        Diagnostic {
+            rule_id: rule_id.to_string(),
+            message: message.to_string(),
+            severity: "warn".to_string(),
+            range: Range {
+                start: position(start),
+                end: position(end),
+            },
+            code: None,
+            suggest: None,
+            docs_url: None,
+        }
+    }
+
+    #[test]
+    fn redacts_the_flagged_text_from_messages() {
+        let content = "let password = \"hunter2\";\n";
+        let mut diagnostics = [diagnostic(
+            "secrets",
+            "Hardcoded password \"hunter2\"",
+            (0, 16),
+            (0, 23),
+        )];
+        redact_snippets(&mut diagnostics, content);
+
+        assert_eq!(diagnostics[0].message, "Hardcoded password \"[REDACTED]\"");
+    }
+
+    #[test]
+    fn redacts_each_line_of_a_multiline_range() {
+        let content = "key = \"\"\"\r\n  abc123\r\n  def456\r\n\"\"\"\r\n";
+        let mut diagnostics = [diagnostic(
+            "secrets",
+            "Keys abc123 and def456 are checked in",
+            (1, 0),
+            (3, 0),
+        )];
+        redact_snippets(&mut diagnostics, content);
+
+        assert_eq!(
+            diagnostics[0].message,
+            "Keys [REDACTED] and [REDACTED] are checked in"
+        );
+    }
+
+    #[test]
+    fn empty_and_out_of_range_diagnostics_keep_their_message() {
+        let content = "x = 1\n";
+        let mut diagnostics = [
+            diagnostic("style", "x = 1 is fine", (0, 2), (0, 2)),
+            diagnostic("style", "x = 1 is gone", (5, 0), (6, 0)),
+        ];
+        redact_snippets(&mut diagnostics, content);
+
+        assert_eq!(diagnostics[0].message, "x = 1 is fine");
+        assert_eq!(diagnostics[1].message, "x = 1 is gone");
+    }
+
+    fn frames(frames: impl IntoIterator<Item = Frame>) -> Vec<String> {
+        frames
+            .into_iter()
+            .map(|frame| match frame {
+                Frame::Message(text) => format!("message {}", text),
+                Frame::Garbage(problem) => format!("garbage {}", problem),
+            })
+            .collect()
+    }
+
+    /// Frames `read_messages` forwards from a process running `script`
+    #[cfg(unix)]
+    fn read_script(script: &str) -> Vec<String> {
+        let mut child = Command::new("sh")
+            .args(["-c", script])
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let (tx, rx) = std::sync::mpsc::sync_channel(1);
+        let stdout = child.stdout.take().unwrap();
+        let reader = std::thread::spawn(move || read_messages(stdout, tx));
+        let read = frames(rx);
+        reader.join().unwrap();
+        child.wait().unwrap();
+        read
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn oversized_and_invalid_lines_are_skipped() {
+        let script = format!(
+            "head -c {} /dev/zero | tr '\\000' x; echo; printf '\\377\\n'; echo '{{\"id\":1}}'",
+            MAX_MESSAGE_BYTES + 10
+        );
+
+        assert_eq!(
+            read_script(&script),
+            [
+                format!(
+                    "garbage skipped a {} byte line, over the {} byte limit",
+                    MAX_MESSAGE_BYTES + 11,
+                    MAX_MESSAGE_BYTES
+                ),
+                "garbage skipped a line that is not valid UTF-8".to_string(),
+                "message {\"id\":1}".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn a_line_at_the_limit_is_read() {
+        let padding = MAX_MESSAGE_BYTES - "{\"p\":\"\"}".len();
+        let script = format!(
+            "printf '{{\"p\":\"'; head -c {} /dev/zero | tr '\\000' x; printf '\"}}\\n'",
+            padding
+        );
+
+        let read = read_script(&script);
+        assert_eq!(read.len(), 1);
+        assert!(read[0].starts_with("message {\"p\":\"xxx"));
+    }
+
+    #[test]
+    fn recovers_a_message_after_stray_output() {
+        assert_eq!(
+            frames(split_frames("debug: starting{\"id\":1}")),
+            [
+                "garbage stray output: debug: starting",
+                "message {\"id\":1}"
+            ]
+        );
+        assert_eq!(frames(split_frames("{\"id\":2}")), ["message {\"id\":2}"]);
+    }
+
+    #[test]
+    fn stray_output_without_a_message_is_garbage() {
+        assert!(split_frames("   ").is_empty());
+        assert_eq!(
+            frames(split_frames("loaded 3 rules")),
+            ["garbage stray output: loaded 3 rules"]
+        );
+        // Valid JSON, but not a protocol message
+        assert_eq!(frames(split_frames("42")), ["garbage stray output: 42"]);
+        assert_eq!(
+            frames(split_frames("progress {50%}")),
+            ["garbage stray output: progress {50%}"]
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn reading_resumes_after_stray_output() {
+        assert_eq!(
+            read_script("echo 'warming up'; printf 'ready{\"id\":1}\\n'; echo '{\"id\":2}'"),
+            [
+                "garbage stray output: warming up",
+                "garbage stray output: ready",
+                "message {\"id\":1}",
+                "message {\"id\":2}",
+            ]
+        );
+    }
+
+    #[test]
+    fn tabs_advance_to_the_next_stop() {
+        assert_eq!(display_column("\tx", 1, 4), 4);
+        assert_eq!(display_column("ab\tx", 3, 4), 4);
+        assert_eq!(display_column("abcd\tx", 5, 4), 8);
+        assert_eq!(display_column("\t\tx", 2, 8), 16);
+        // Tabs after the column don't move it
+        assert_eq!(display_column("ab\tx", 2, 4), 2);
+    }
+
+    #[test]
+    fn tab_width_one_counts_characters() {
+        assert_eq!(display_column("\t\tx", 2, 1), 2);
+        assert_eq!(display_column("é\tx", 2, 1), 2);
+        // A width of 0 would never advance, so it counts as 1
+        assert_eq!(display_column("\t\tx", 2, 0), 2);
+    }
+
+    #[test]
+    fn columns_past_the_end_of_the_line_keep_counting() {
+        assert_eq!(display_column("\tx", 4, 4), 7);
+        assert_eq!(display_column("", 3, 4), 3);
+    }
+
+    fn workspace_files(count: usize) -> Vec<PathBuf> {
+        (0..count)
+            .map(|index| PathBuf::from(format!("/work/src/file{}.rs", index)))
+            .collect()
+    }
+
+    #[test]
+    fn samples_round_up_and_keep_discovery_order() {
+        let root = Path::new("/work");
+        let sample = sample_files(workspace_files(10), 25.0, 7, root);
+
+        assert_eq!(sample.len(), 3);
+        let files = workspace_files(10);
+        let positions: Vec<usize> = sample
+            .iter()
+            .map(|file| files.iter().position(|known| known == file).unwrap())
+            .collect();
+        assert!(positions.is_sorted(), "{:?}", positions);
+        assert_eq!(sample_files(workspace_files(3), 1.0, 7, root).len(), 1);
+        assert_eq!(sample_files(workspace_files(10), 100.0, 7, root), files);
+        assert!(sample_files(workspace_files(10), 0.0, 7, root).is_empty());
+    }
+
+    #[test]
+    fn samples_depend_only_on_seed_and_relative_paths() {
+        let sample = |seed, root: &str| {
+            let files = workspace_files(20)
+                .into_iter()
+                .map(|file| Path::new(root).join(file.strip_prefix("/work").unwrap()))
+                .collect();
+            sample_files(files, 50.0, seed, Path::new(root))
+                .into_iter()
+                .map(|file| relative_path(&file, Path::new(root)))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(sample(7, "/work"), sample(7, "/elsewhere/checkout"));
+        assert_ne!(sample(7, "/work"), sample(8, "/work"));
+    }
+
+    fn finding(rule_id: &str, severity: &str, line: u32) -> Finding {
+        let mut diagnostic = diagnostic(rule_id, &format!("line {}", line), (line, 0), (line, 1));
+        diagnostic.severity = severity.to_string();
+        Finding {
+            diagnostic,
+            fingerprint: String::new(),
+            column: 1,
+            provenance: Provenance {
+                ruleset_id: "base".to_string(),
+                pid: 0,
+                request_id: String::new(),
+                event: 0,
+                requested_at: String::new(),
+                received_at: String::new(),
+            },
+        }
+    }
+
+    /// A result of the `base` ruleset for `path`, with one finding per
+    /// `(rule, severity)` on consecutive lines
+    fn result(path: &str, findings: &[(&str, &str)]) -> FileResult {
+        FileResult {
+            path: PathBuf::from(path),
+            ruleset_id: "base".to_string(),
+            findings: findings
+                .iter()
+                .enumerate()
+                .map(|(line, (rule, severity))| finding(rule, severity, line as u32))
+                .collect(),
+            source: Arc::from(""),
+        }
+    }
+
+    /// `path:line rule` for every finding, in report order
+    fn listed(file_results: &[FileResult]) -> Vec<String> {
+        file_results
+            .iter()
+            .flat_map(|result| {
+                result.findings.iter().map(|finding| {
+                    format!(
+                        "{}:{} {}",
+                        result.path.display(),
+                        finding.diagnostic.range.start.line,
+                        finding.diagnostic.rule_id
+                    )
+                })
+            })
+            .collect()
+    }
+
+    fn notes(notes: &[LimitNote]) -> Vec<String> {
+        notes
+            .iter()
+            .map(|note| format!("{} +{}", note.origin, note.more))
+            .collect()
+    }
+
+    #[test]
+    fn nothing_is_limited_within_the_limits() {
+        let results = [result("a.rs", &[("todo", "warn"), ("fixme", "warn")])];
+
+        assert!(limit_findings(&results, None, None).is_none());
+        assert!(limit_findings(&results, Some(1), Some(2)).is_none());
+    }
+
+    #[test]
+    fn per_rule_limit_spans_files() {
+        let results = [
+            result("a.rs", &[("todo", "warn"), ("todo", "warn")]),
+            result("b.rs", &[("todo", "warn"), ("fixme", "warn")]),
+        ];
+        let (limited, limit_notes) = limit_findings(&results, Some(2), None).unwrap();
+
+        assert_eq!(
+            listed(&limited),
+            ["a.rs:0 todo", "a.rs:1 todo", "b.rs:1 fixme"]
+        );
+        assert_eq!(notes(&limit_notes), ["rule base/todo +1"]);
+    }
+
+    #[test]
+    fn findings_over_a_rule_limit_leave_room_in_the_file() {
+        let results = [
+            result(
+                "a.rs",
+                &[
+                    ("todo", "warn"),
+                    ("todo", "warn"),
+                    ("fixme", "warn"),
+                    ("long", "warn"),
+                ],
+            ),
+            result("b.rs", &[("todo", "warn"), ("long", "warn")]),
+        ];
+        let (limited, limit_notes) = limit_findings(&results, Some(1), Some(2)).unwrap();
+
+        // The second todo is over its rule's limit, so fixme still fits in a.rs
+        assert_eq!(
+            listed(&limited),
+            ["a.rs:0 todo", "a.rs:2 fixme", "b.rs:1 long"]
+        );
+        assert_eq!(notes(&limit_notes), ["rule base/todo +2", "file a.rs +1"]);
+    }
+
+    #[test]
+    fn streamed_findings_count_against_the_limits() {
+        let mut counts = LimitCounts::default();
+        let mut admit =
+            |rule: &str, path: &str| counts.admit(rule, Path::new(path), Some(2), Some(2));
+
+        assert_eq!(admit("base/todo", "a.rs"), None);
+        assert_eq!(admit("base/todo", "b.rs"), None);
+        assert_eq!(admit("base/todo", "c.rs"), Some(Over::Rule));
+        assert_eq!(admit("base/long", "a.rs"), None);
+        assert_eq!(admit("base/long", "a.rs"), Some(Over::File));
+        // The todo left out of c.rs doesn't use up its room
+        assert_eq!(admit("base/long", "c.rs"), None);
+        assert_eq!(admit("base/fixme", "c.rs"), None);
+        assert_eq!(admit("base/fixme", "c.rs"), Some(Over::File));
+    }
+
+    #[test]
+    fn nothing_is_capped_within_the_cap() {
+        let results = [result("a.rs", &[("todo", "warn"), ("fixme", "info")])];
+
+        assert!(cap_findings(&results, 2).is_none());
+    }
+
+    #[test]
+    fn most_severe_findings_are_kept_in_report_order() {
+        let results = [
+            result("a.rs", &[("todo", "info"), ("unsafe", "error")]),
+            result(
+                "b.rs",
+                &[("hint", HINT), ("long", "warn"), ("unused", "error")],
+            ),
+        ];
+        let capped = cap_findings(&results, 3).unwrap();
+
+        assert_eq!(
+            listed(&capped),
+            ["a.rs:1 unsafe", "b.rs:1 long", "b.rs:2 unused"]
+        );
+    }
+
+    #[test]
+    fn a_noisy_rule_leaves_room_for_the_others() {
+        let results = [
+            result("a.rs", &[("long", "warn"); 4]),
+            result(
+                "b.rs",
+                &[("long", "warn"), ("todo", "warn"), ("todo", "warn")],
+            ),
+        ];
+        let capped = cap_findings(&results, 3).unwrap();
+
+        // Each rule keeps its first finding, then ties go to report order
+        assert_eq!(
+            listed(&capped),
+            ["a.rs:0 long", "a.rs:1 long", "b.rs:1 todo"]
+        );
+        assert!(capped.iter().all(|result| !result.findings.is_empty()));
+    }
+
+    #[test]
+    fn every_rule_keeps_one_before_any_keeps_two() {
+        let results = [result(
+            "a.rs",
+            &[("todo", "info"), ("unsafe", "error"), ("unsafe", "error")],
+        )];
+        let capped = cap_findings(&results, 2).unwrap();
+
+        assert_eq!(listed(&capped), ["a.rs:0 todo", "a.rs:1 unsafe"]);
+    }
+
+    #[test]
+    fn finds_wall_clock_keys_at_any_depth() {
+        assert_eq!(
+            find_wall_clock(&json!({"rules": 3, "stats": [{"generated_at": 1_700_000_000}]})),
+            Some("key 'generated_at'".to_string())
+        );
+        assert_eq!(
+            find_wall_clock(&json!({"TimeStamp": "later"})),
+            Some("key 'TimeStamp'".to_string())
+        );
+        assert_eq!(find_wall_clock(&json!({"timeout": 30, "times": 2})), None);
+    }
+
+    #[test]
+    fn finds_date_times_in_strings() {
+        assert_eq!(
+            find_wall_clock(&json!({"note": "built 2025-03-01T12:00:00Z"})),
+            Some("'built 2025-03-01T12:00:00Z'".to_string())
+        );
+        assert_eq!(
+            find_wall_clock(&json!(["2025-03-01 12:00"])),
+            Some("'2025-03-01 12:00'".to_string())
+        );
+        // A date alone is stable across runs
+        assert_eq!(find_wall_clock(&json!({"since": "2025-03-01"})), None);
+        assert_eq!(find_wall_clock(&json!("12:00 on 2025-03-01")), None);
+        assert_eq!(find_wall_clock(&json!(null)), None);
+    }
+
+    #[test]
+    fn manifest_languages_limit_what_a_ruleset_covers() {
+        let mut ruleset = RulesetInfo::binary("security", Path::new("forseti_ruleset_security"));
+        let rust = languages::by_id("rust");
+        let terraform = languages::by_id("terraform");
+
+        assert!(ruleset.claims(terraform));
+        assert!(ruleset.claims(None));
+        ruleset.languages = vec!["rust".to_string(), "go".to_string()];
+        assert!(ruleset.claims(rust));
+        assert!(!ruleset.claims(terraform));
+        assert!(!ruleset.claims(None));
+    }
+
+    #[test]
+    fn suggests_rulesets_for_unclaimed_files_by_kind() {
+        let unclaimed = [
+            "infra/a.tf",
+            "infra/b.tf",
+            "src/main.rs",
+            "README",
+            "Dockerfile",
+        ]
+        .map(PathBuf::from);
+        let suggestions = suggest_rulesets(&unclaimed, Path::new(".forseti.toml"));
+
+        let listed: Vec<_> = suggestions
+            .iter()
+            .map(|s| format!("{} {} {}", s.count, s.kind, s.language.id))
+            .collect();
+        assert_eq!(
+            listed,
+            ["2 .tf terraform", "1 .rs rust", "1 Dockerfile dockerfile"]
+        );
+    }
 }
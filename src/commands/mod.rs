@@ -1,16 +1,83 @@
 use clap::{Subcommand, ValueEnum};
 use std::path::PathBuf;
 
+pub mod annotate;
+pub mod coverage;
+pub mod daemon;
+pub mod enable_language;
+pub mod gen_installer;
 pub mod init;
 pub mod install;
+pub mod langs;
 pub mod lint;
+pub mod output;
+pub mod plugin;
+pub mod rerun;
+pub mod verify;
+pub mod worker;
 
-#[derive(ValueEnum, Clone, Debug)]
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
 pub enum OutputFormat {
     Text,
     Json,
+    /// Deterministic JSON (sorted keys, relative paths) suitable for golden files
+    JsonCanonical,
+    /// One JSON diagnostic per line, written as each file finishes
+    #[value(alias = "ndjson")]
+    Jsonl,
     Junit,
     Sarif,
+    /// GitHub Actions workflow commands, shown inline on pull requests
+    Github,
+    /// GitLab Code Quality report for merge request widgets
+    Gitlab,
+    /// Code Climate engine issues (NUL-separated on stdout, an array in a file)
+    Codeclimate,
+    /// TAP 13 with one test point per analyzed file
+    Tap,
+    /// TeamCity inspection service messages
+    Teamcity,
+    /// `path:line:col: severity message [rule]`, one line per finding and
+    /// nothing else, for grep and other shell tools
+    #[value(alias = "unix")]
+    Compact,
+    /// One row per finding with a header, for spreadsheets
+    Csv,
+}
+
+/// What `--output` asked for: a built-in format or an external formatter
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OutputSpec {
+    Builtin(OutputFormat),
+    /// `exec:<command>`, run with the canonical JSON report on stdin
+    Exec(String),
+}
+
+impl std::str::FromStr for OutputSpec {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if let Some(command) = value.strip_prefix("exec:") {
+            if command.trim().is_empty() {
+                return Err("exec: needs a formatter command, e.g. exec:./my-formatter".into());
+            }
+            return Ok(OutputSpec::Exec(command.to_string()));
+        }
+        OutputFormat::from_str(value, true)
+            .map(OutputSpec::Builtin)
+            .map_err(|_| {
+                let names: Vec<String> = OutputFormat::value_variants()
+                    .iter()
+                    .filter_map(|format| format.to_possible_value())
+                    .map(|value| value.get_name().to_string())
+                    .collect();
+                format!(
+                    "unknown output format '{}' (expected one of: {}, or exec:<command>)",
+                    value,
+                    names.join(", ")
+                )
+            })
+    }
 }
 
 #[derive(Subcommand)]
@@ -26,9 +93,10 @@ pub enum Commands {
     },
     /// Download and install engines and rulesets from configuration
     Install {
-        /// Cache directory for downloaded binaries
-        #[arg(long, default_value = "~/.forseti/cache")]
-        cache_path: PathBuf,
+        /// Cache directory for downloaded binaries (defaults to `cache` in the
+        /// install root)
+        #[arg(long)]
+        cache_path: Option<PathBuf>,
 
         /// Enable caching of downloaded binaries
         #[arg(long)]
@@ -41,27 +109,117 @@ pub enum Commands {
         /// Force reinstall even if already exists
         #[arg(long)]
         force: bool,
+
+        /// Only check that the cache matches the config, exiting non-zero on
+        /// missing, outdated or modified rulesets; installs nothing
+        #[arg(long, conflicts_with = "force")]
+        check: bool,
     },
-    /// Lint files in a directory or file path
-    Lint {
-        /// Path to lint (file or directory). Defaults to current directory.
+    /// Write install-rulesets.sh and install-rulesets.ps1, which download the
+    /// rulesets pinned in [ruleset.<id>.download] for contributors without cargo
+    GenInstaller {
+        /// Project directory containing .forseti.toml (defaults to current directory)
         #[arg(default_value = ".")]
         path: PathBuf,
+    },
+    /// Add the recommended rulesets for a language to the config and install them
+    #[command(visible_alias = "add")]
+    EnableLanguage {
+        /// Language id or name, e.g. rust or TypeScript (see `forseti langs`)
+        language: String,
 
-        /// Apply automatic fixes where possible (experimental)
+        /// Project directory containing .forseti.toml (defaults to current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// Only update the config; run `forseti install` later
+        #[arg(long)]
+        no_install: bool,
+    },
+    /// Lint files in a directory or file path
+    Lint(Box<lint::LintArgs>),
+    /// Insert suppression comments above every current violation of a rule
+    Annotate {
+        /// Rule to suppress (`rule` or `ruleset/rule`)
         #[arg(long)]
-        fix: bool,
+        rule: String,
 
-        /// Recursively scan all subdirectories
-        #[arg(short, long)]
-        recursive: bool,
+        /// Justification written after each inserted comment
+        #[arg(long, default_value = "TODO: justify this suppression")]
+        justification: String,
+
+        /// Path to annotate (file or directory). Defaults to current directory.
+        #[arg(default_value = ".")]
+        path: PathBuf,
+    },
+    /// Check config, installed rulesets and their handshake without linting
+    Verify {
+        /// Project directory containing .forseti.toml (defaults to current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+    },
+    /// Tools for ruleset authors
+    Plugin {
+        #[command(subcommand)]
+        command: PluginCommands,
+    },
+    /// Re-run lint with the arguments and config recorded in a SARIF or JUnit report
+    Rerun {
+        /// Report produced by an earlier `forseti lint`
+        report: PathBuf,
 
-        /// Output format for results
-        #[arg(short, long, default_value = "text")]
-        output: OutputFormat,
+        /// Arguments added to the recorded `forseti lint` ones, e.g. `-- -o sarif
+        /// --output-file new.sarif`; the recorded output, store and artifact
+        /// flags are dropped, so without these the results go to stdout
+        #[arg(last = true)]
+        args: Vec<String>,
+    },
+    /// Report file counts and sizes per detected language
+    Langs {
+        /// Directory to scan (defaults to current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+    },
+    /// Lint and report which configured rules fired, which never did, and
+    /// which available rules the config leaves out
+    Coverage {
+        /// Path to lint (defaults to current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+    },
+    /// Keep a project's rulesets initialized in the background, so lint runs
+    /// and editor integrations hand it their files instead of starting them
+    Daemon {
+        /// Project directory containing .forseti.toml (defaults to current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+        /// Stop the daemon running for the project
+        #[arg(long)]
+        stop: bool,
+    },
+}
 
-        /// Write results to file (defaults to stdout)
+#[derive(Subcommand)]
+pub enum PluginCommands {
+    /// Run the protocol conformance suite against a ruleset binary before
+    /// releasing it
+    Verify {
+        /// Ruleset executable, e.g. target/release/forseti_ruleset_mine
+        binary: PathBuf,
+    },
+    /// Feed a ruleset binary mutated protocol messages and random files,
+    /// recording the input of every crash or hang
+    Fuzz {
+        /// Ruleset executable, e.g. target/release/forseti_ruleset_mine
+        binary: PathBuf,
+        /// How long to keep generating cases
+        #[arg(long, default_value_t = 60)]
+        seconds: u64,
+        /// Seed printed by an earlier run, to replay its cases
         #[arg(long)]
-        output_file: Option<PathBuf>,
+        seed: Option<u64>,
+        /// Directory reproducer inputs are written to
+        #[arg(long, default_value = "forseti-fuzz")]
+        out: PathBuf,
     },
 }
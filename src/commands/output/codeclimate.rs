@@ -0,0 +1,100 @@
+use super::{Formatter, Report};
+use crate::commands::lint::{HINT, relative_path};
+use crate::context::GlobalContext;
+use crate::rules;
+use anyhow::Result;
+use serde_json::{Value, json};
+use std::io::Write;
+
+/// Code Climate issues: an array in a file, NUL-separated on stdout
+pub(crate) struct CodeclimateFormatter;
+
+impl Formatter for CodeclimateFormatter {
+    fn render(&self, _ctx: &GlobalContext, report: &Report) -> Result<String> {
+        let issues = generate_codeclimate(report)?;
+        Ok(serde_json::to_string_pretty(&issues)?)
+    }
+
+    fn print(&self, _ctx: &GlobalContext, report: &Report) -> Result<()> {
+        // Engine spec: each issue followed by a NUL byte
+        let mut stdout = std::io::stdout().lock();
+        for issue in &generate_codeclimate(report)? {
+            write!(stdout, "{}\0", serde_json::to_string(issue)?)?;
+        }
+        stdout.flush()?;
+        Ok(())
+    }
+}
+
+/// Code Climate engine issues
+fn generate_codeclimate(report: &Report) -> Result<Vec<Value>> {
+    let mut issues = Vec::new();
+    for result in report.file_results {
+        let path = relative_path(&result.path, report.workspace_root);
+        for finding in &result.findings {
+            let diagnostic = &finding.diagnostic;
+            let check_name = rules::qualify(&result.ruleset_id, &diagnostic.rule_id);
+            let mut issue = json!({
+                "type": "issue",
+                "check_name": check_name,
+                "description": diagnostic.message,
+                "categories": [codeclimate_category(&check_name, &diagnostic.severity)],
+                "location": {
+                    "path": path,
+                    "positions": {
+                        "begin": {
+                            "line": diagnostic.range.start.line + 1,
+                            "column": finding.column,
+                        },
+                        "end": {
+                            "line": diagnostic.range.end.line + 1,
+                        },
+                    },
+                },
+                "severity": quality_severity(&diagnostic.severity),
+                "remediation_points": match diagnostic.severity.as_str() {
+                    "error" => 150_000,
+                    "info" | HINT => 10_000,
+                    _ => 50_000,
+                },
+                "fingerprint": finding.fingerprint,
+            });
+            if let Some(docs_url) = &diagnostic.docs_url {
+                issue["content"] = json!({ "body": format!("See {}", docs_url) });
+            }
+            issues.push(issue);
+        }
+    }
+    Ok(issues)
+}
+
+/// Code Climate / GitLab severity for a diagnostic severity
+pub(crate) fn quality_severity(severity: &str) -> &'static str {
+    match severity {
+        "error" => "major",
+        "info" | HINT => "info",
+        _ => "minor",
+    }
+}
+
+/// Code Climate category guessed from the rule name, falling back on severity
+fn codeclimate_category(check_name: &str, severity: &str) -> &'static str {
+    const KEYWORDS: [(&str, &str); 6] = [
+        ("secur", "Security"),
+        ("secret", "Security"),
+        ("perf", "Performance"),
+        ("complex", "Complexity"),
+        ("dup", "Duplication"),
+        ("compat", "Compatibility"),
+    ];
+    let check_name = check_name.to_lowercase();
+    KEYWORDS
+        .iter()
+        .find(|(keyword, _)| check_name.contains(keyword))
+        .map(|(_, category)| *category)
+        .unwrap_or(if severity == "error" {
+            "Bug Risk"
+        } else {
+            "Style"
+        })
+}
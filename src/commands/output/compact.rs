@@ -0,0 +1,26 @@
+use super::Report;
+use crate::commands::lint::relative_path;
+use crate::rules;
+
+/// `path:line:col: severity message [rule]` per finding, with line breaks in
+/// messages flattened so every finding stays on one line
+pub(crate) fn generate_compact(report: &Report) -> String {
+    let mut lines = String::new();
+    for result in report.file_results {
+        let path = relative_path(&result.path, report.workspace_root);
+        for finding in &result.findings {
+            let diagnostic = &finding.diagnostic;
+            let message: Vec<&str> = diagnostic.message.split_whitespace().collect();
+            lines.push_str(&format!(
+                "{}:{}:{}: {} {} [{}]\n",
+                path,
+                diagnostic.range.start.line + 1,
+                finding.column,
+                diagnostic.severity,
+                message.join(" "),
+                rules::qualify(&result.ruleset_id, &diagnostic.rule_id)
+            ));
+        }
+    }
+    lines
+}
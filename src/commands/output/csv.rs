@@ -0,0 +1,37 @@
+use super::Report;
+use crate::commands::lint::relative_path;
+use crate::rules;
+
+/// RFC 4180 CSV with a header row and one row per finding
+pub(crate) fn generate_csv(report: &Report) -> String {
+    let mut csv = String::from("file,line,column,severity,rule,ruleset,message,docs_url\r\n");
+    for result in report.file_results {
+        let path = relative_path(&result.path, report.workspace_root);
+        for finding in &result.findings {
+            let diagnostic = &finding.diagnostic;
+            let fields = [
+                path.clone(),
+                (diagnostic.range.start.line + 1).to_string(),
+                finding.column.to_string(),
+                diagnostic.severity.clone(),
+                rules::qualify(&result.ruleset_id, &diagnostic.rule_id),
+                result.ruleset_id.clone(),
+                diagnostic.message.clone(),
+                diagnostic.docs_url.clone().unwrap_or_default(),
+            ];
+            let row: Vec<String> = fields.iter().map(|field| csv_escape(field)).collect();
+            csv.push_str(&row.join(","));
+            csv.push_str("\r\n");
+        }
+    }
+    csv
+}
+
+/// Quote a CSV field when it contains a delimiter, quote or line break
+fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
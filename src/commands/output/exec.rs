@@ -0,0 +1,53 @@
+use super::json::generate_canonical_json;
+use super::{Formatter, Report};
+use crate::context::GlobalContext;
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// An external program given the canonical JSON report on stdin; whatever
+/// it prints becomes the report
+pub(crate) struct ExecFormatter {
+    pub command: String,
+}
+
+impl Formatter for ExecFormatter {
+    fn render(&self, ctx: &GlobalContext, report: &Report) -> Result<String> {
+        let mut parts = self.command.split_whitespace();
+        let program = parts
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("exec: output needs a formatter command"))?;
+        ctx.log_verbose(&format!("Running external formatter: {}", self.command));
+
+        let mut child = Command::new(program)
+            .args(parts)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .with_context(|| format!("Failed to start formatter: {}", program))?;
+
+        let json = generate_canonical_json(report)?;
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        // Write from a thread so a formatter streaming output before reading
+        // all of its input can't deadlock against us
+        let writer = std::thread::spawn(move || stdin.write_all(json.as_bytes()));
+        let output = child
+            .wait_with_output()
+            .with_context(|| format!("Failed to run formatter: {}", program))?;
+        if let Ok(Err(e)) = writer.join()
+            && e.kind() != std::io::ErrorKind::BrokenPipe
+        {
+            return Err(e).with_context(|| format!("Failed to write to formatter: {}", program));
+        }
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "Formatter {} exited with {}",
+                self.command,
+                output.status
+            ));
+        }
+        String::from_utf8(output.stdout)
+            .with_context(|| format!("Formatter {} printed invalid UTF-8", self.command))
+    }
+}
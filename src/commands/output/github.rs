@@ -0,0 +1,49 @@
+use super::Report;
+use crate::commands::lint::{HINT, relative_path, truncation_note};
+use crate::rules;
+
+/// GitHub Actions workflow commands, one per finding, which the runner turns
+/// into inline pull request annotations
+pub(crate) fn generate_github_annotations(report: &Report) -> String {
+    let mut commands = String::new();
+    for result in report.file_results {
+        let path = relative_path(&result.path, report.workspace_root);
+        for finding in &result.findings {
+            let diagnostic = &finding.diagnostic;
+            let level = match diagnostic.severity.as_str() {
+                "error" => "error",
+                "info" | HINT => "notice",
+                _ => "warning",
+            };
+            commands.push_str(&format!(
+                "::{} file={},line={},col={},endLine={},title={}::{}\n",
+                level,
+                github_escape_property(&path),
+                diagnostic.range.start.line + 1,
+                finding.column,
+                diagnostic.range.end.line + 1,
+                github_escape_property(&rules::qualify(&result.ruleset_id, &diagnostic.rule_id)),
+                github_escape_data(&diagnostic.message)
+            ));
+        }
+    }
+    if report.omitted > 0 {
+        commands.push_str(&format!(
+            "::warning title=forseti::{}\n",
+            github_escape_data(&truncation_note(report.omitted))
+        ));
+    }
+    commands
+}
+
+fn github_escape_data(s: &str) -> String {
+    s.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn github_escape_property(s: &str) -> String {
+    github_escape_data(s)
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
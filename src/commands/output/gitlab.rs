@@ -0,0 +1,36 @@
+use super::Report;
+use super::codeclimate::quality_severity;
+use crate::commands::lint::relative_path;
+use crate::rules;
+use anyhow::Result;
+use serde_json::json;
+
+/// GitLab Code Quality report, shown in merge request widgets
+pub(crate) fn generate_gitlab(report: &Report) -> Result<String> {
+    let mut issues = Vec::new();
+    for result in report.file_results {
+        let path = relative_path(&result.path, report.workspace_root);
+        for finding in &result.findings {
+            let diagnostic = &finding.diagnostic;
+            issues.push(json!({
+                "description": diagnostic.message,
+                "check_name": rules::qualify(&result.ruleset_id, &diagnostic.rule_id),
+                "fingerprint": finding.fingerprint,
+                "severity": quality_severity(&diagnostic.severity),
+                "location": {
+                    "path": path,
+                    "positions": {
+                        "begin": {
+                            "line": diagnostic.range.start.line + 1,
+                            "column": finding.column,
+                        },
+                        "end": {
+                            "line": diagnostic.range.end.line + 1,
+                        },
+                    },
+                },
+            }));
+        }
+    }
+    Ok(serde_json::to_string_pretty(&issues)?)
+}
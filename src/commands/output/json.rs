@@ -0,0 +1,207 @@
+use super::{Formatter, Report};
+use crate::commands::lint::{FileResult, Finding, HINT, relative_path};
+use crate::context::GlobalContext;
+use crate::rules;
+use anyhow::Result;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Version of the `json` report envelope, bumped only for changes that can
+/// break consumers; new fields may appear without a bump
+const JSON_REPORT_VERSION: u32 = 1;
+
+/// Diagnostics grouped by file path, in a versioned envelope:
+///
+/// ```json
+/// {
+///   "version": 1,
+///   "tool": { "name": "forseti", "version": "0.1.0" },
+///   "files": [{ "path": "src/main.rs", "diagnostics": [...] }],
+///   "summary": {
+///     "files": { "discovered": 10, "analyzed": 10, "skipped": 0, "withIssues": 1 },
+///     "diagnostics": { "total": 2, "error": 1, "warn": 1, "info": 0, "hint": 0, "omitted": 0 },
+///     "truncated": false,
+///     "score": 98.7
+///   },
+///   "metrics": { "rust/complexity": 412.0, "rust/todoCount": 17.0 },
+///   "artifacts": [{
+///     "ruleset": "deps", "name": "graph.svg", "path": ".forseti/artifacts/deps/graph.svg",
+///     "mediaType": "image/svg+xml", "bytes": 5120
+///   }]
+/// }
+/// ```
+pub(crate) struct JsonFormatter;
+
+impl Formatter for JsonFormatter {
+    fn render(&self, _ctx: &GlobalContext, report: &Report) -> Result<String> {
+        // Files in the order they were linted, each listed once
+        let mut files: Vec<(String, Vec<Value>)> = Vec::new();
+        let mut positions: HashMap<String, usize> = HashMap::new();
+        let (mut error, mut warn, mut info, mut hint) = (0, 0, 0, 0);
+        for result in report.file_results {
+            let path = report.display_path(&result.path);
+            let position = *positions.entry(path.clone()).or_insert_with(|| {
+                files.push((path, Vec::new()));
+                files.len() - 1
+            });
+            for finding in &result.findings {
+                match finding.diagnostic.severity.as_str() {
+                    "error" => error += 1,
+                    "info" => info += 1,
+                    HINT => hint += 1,
+                    _ => warn += 1,
+                }
+                files[position]
+                    .1
+                    .push(finding_to_json(finding, &result.ruleset_id)?);
+            }
+        }
+
+        let files: Vec<Value> = files
+            .into_iter()
+            .map(|(path, diagnostics)| json!({ "path": path, "diagnostics": diagnostics }))
+            .collect();
+        let artifacts: Vec<Value> = report
+            .artifacts
+            .iter()
+            .map(|artifact| {
+                json!({
+                    "ruleset": artifact.ruleset,
+                    "name": artifact.name,
+                    "path": relative_path(&artifact.path, report.workspace_root),
+                    "mediaType": artifact.media_type,
+                    "bytes": artifact.bytes,
+                })
+            })
+            .collect();
+        Ok(serde_json::to_string_pretty(&json!({
+            "version": JSON_REPORT_VERSION,
+            "tool": {
+                "name": "forseti",
+                "version": env!("CARGO_PKG_VERSION"),
+            },
+            "files": files,
+            "summary": {
+                "files": report.file_counts,
+                "diagnostics": {
+                    "total": report.total_diagnostics,
+                    "error": error,
+                    "warn": warn,
+                    "info": info,
+                    "hint": hint,
+                    "omitted": report.omitted,
+                },
+                "truncated": report.omitted > 0,
+                "score": (report.score * 10.0).round() / 10.0,
+            },
+            "metrics": report.metrics,
+            "artifacts": artifacts,
+        }))?)
+    }
+}
+
+/// Serialize a finding's diagnostic with forseti's own fields merged in
+fn finding_to_json(finding: &Finding, ruleset_id: &str) -> Result<Value> {
+    let mut value = serde_json::to_value(&finding.diagnostic)?;
+    if let Value::Object(map) = &mut value {
+        map.insert(
+            "ruleId".to_string(),
+            Value::String(rules::qualify(ruleset_id, &finding.diagnostic.rule_id)),
+        );
+        map.insert("ruleset".to_string(), Value::String(ruleset_id.to_string()));
+        map.insert(
+            "fingerprint".to_string(),
+            Value::String(finding.fingerprint.clone()),
+        );
+    }
+    Ok(value)
+}
+
+/// One finding as a JSON Lines record: the JSON diagnostic plus its
+/// workspace-relative `path`
+pub(crate) fn jsonl_line(
+    result: &FileResult,
+    finding: &Finding,
+    workspace_root: &Path,
+) -> Result<String> {
+    let mut value = finding_to_json(finding, &result.ruleset_id)?;
+    value["path"] = json!(relative_path(&result.path, workspace_root));
+    Ok(serde_json::to_string(&value)?)
+}
+
+/// Every finding as JSON Lines, for reports rendered after the run
+pub(crate) fn generate_jsonl(report: &Report) -> Result<String> {
+    let mut lines = String::new();
+    for result in report.file_results {
+        for finding in &result.findings {
+            lines.push_str(&jsonl_line(result, finding, report.workspace_root)?);
+            lines.push('\n');
+        }
+    }
+    Ok(lines)
+}
+
+/// JSON that only changes when findings change: paths relative to the
+/// workspace root, files and diagnostics sorted, object keys sorted
+pub(crate) fn generate_canonical_json(report: &Report) -> Result<String> {
+    let Report {
+        file_results,
+        workspace_root,
+        file_counts,
+        ..
+    } = *report;
+    let mut files: std::collections::BTreeMap<String, Vec<(&Finding, &str)>> =
+        std::collections::BTreeMap::new();
+
+    for result in file_results {
+        files
+            .entry(relative_path(&result.path, workspace_root))
+            .or_default()
+            .extend(
+                result
+                    .findings
+                    .iter()
+                    .map(|f| (f, result.ruleset_id.as_str())),
+            );
+    }
+
+    let mut output = serde_json::Map::new();
+    for (path, mut findings) in files {
+        findings.sort_by(|(a, ra), (b, rb)| {
+            let (a, b) = (&a.diagnostic, &b.diagnostic);
+            (
+                a.range.start.line,
+                a.range.start.character,
+                ra,
+                &a.rule_id,
+                &a.message,
+            )
+                .cmp(&(
+                    b.range.start.line,
+                    b.range.start.character,
+                    rb,
+                    &b.rule_id,
+                    &b.message,
+                ))
+        });
+
+        let values = findings
+            .into_iter()
+            .map(|(finding, ruleset_id)| finding_to_json(finding, ruleset_id))
+            .collect::<Result<Vec<_>>>()?;
+        output.insert(path, Value::Array(values));
+    }
+
+    // serde_json maps are ordered by key, so every object comes out sorted
+    let mut canonical = json!({
+        "files": output,
+        "summary": file_counts,
+    });
+    // Only when truncated, so complete reports stay byte-for-byte the same
+    if report.omitted > 0 {
+        canonical["omitted"] = json!(report.omitted);
+    }
+    let json = serde_json::to_string_pretty(&canonical)?;
+    Ok(format!("{}\n", json))
+}
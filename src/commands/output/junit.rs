@@ -0,0 +1,140 @@
+use super::Report;
+use crate::commands::lint::Finding;
+use crate::metadata::METADATA_KEY;
+use crate::rules;
+use anyhow::Result;
+
+pub(crate) fn generate_junit_xml(report: &Report) -> Result<String> {
+    let Report {
+        file_results,
+        metadata,
+        file_counts,
+        output_settings,
+        ..
+    } = *report;
+    use std::fmt::Write;
+
+    let junit_settings = &output_settings.junit;
+    let mut xml = String::new();
+
+    // XML header
+    writeln!(xml, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+
+    // Elements per kind, as `[output.junit] elements` maps severities
+    let (mut failures, mut errors, mut skipped) = (0, 0, file_counts.skipped);
+    for finding in file_results.iter().flat_map(|result| &result.findings) {
+        match junit_settings.element(&finding.diagnostic.severity) {
+            Some("error") => errors += 1,
+            Some("skipped") => skipped += 1,
+            Some(_) => failures += 1,
+            None => {}
+        }
+    }
+
+    // Testsuite opening tag
+    writeln!(
+        xml,
+        r#"<testsuite name="Forseti Linter" tests="{}" failures="{}" errors="{}" skipped="{}">"#,
+        file_counts.analyzed, failures, errors, skipped
+    )?;
+
+    // Run metadata, so `forseti rerun` can reproduce this report
+    writeln!(xml, r#"  <properties>"#)?;
+    writeln!(
+        xml,
+        r#"    <property name="{}" value="{}"/>"#,
+        METADATA_KEY,
+        html_escape(&serde_json::to_string(metadata)?)
+    )?;
+    for (name, count) in [
+        ("files.discovered", file_counts.discovered),
+        ("files.analyzed", file_counts.analyzed),
+        ("files.skipped", file_counts.skipped),
+        ("files.withIssues", file_counts.with_issues),
+        ("diagnostics.omitted", report.omitted),
+    ] {
+        writeln!(
+            xml,
+            r#"    <property name="forseti.{}" value="{}"/>"#,
+            name, count
+        )?;
+    }
+    writeln!(
+        xml,
+        r#"    <property name="forseti.score" value="{:.1}"/>"#,
+        report.score
+    )?;
+    writeln!(xml, r#"  </properties>"#)?;
+
+    // Generate test cases for each file
+    for result in file_results {
+        let file_name = report.display_path(&result.path);
+        let ruleset_id = &result.ruleset_id;
+        let has_issues = result.findings.iter().any(|finding| {
+            junit_settings
+                .element(&finding.diagnostic.severity)
+                .is_some()
+        });
+
+        if has_issues {
+            // File with issues - create failure test case
+            writeln!(
+                xml,
+                r#"  <testcase classname="forseti.{}" name="{}" time="0">"#,
+                ruleset_id,
+                html_escape(&file_name)
+            )?;
+
+            // Add failures for each diagnostic
+            for Finding {
+                diagnostic, column, ..
+            } in &result.findings
+            {
+                let Some(element) = junit_settings.element(&diagnostic.severity) else {
+                    continue;
+                };
+                let rule_id = rules::qualify(ruleset_id, &diagnostic.rule_id);
+                let failure_message = format!(
+                    "{}:{}: {} [{}]",
+                    diagnostic.range.start.line + 1,
+                    column,
+                    diagnostic.message,
+                    rule_id
+                );
+
+                writeln!(
+                    xml,
+                    r#"    <{} message="{}" type="{}">{}</{}>"#,
+                    element,
+                    html_escape(&failure_message),
+                    html_escape(&rule_id),
+                    html_escape(&diagnostic.message),
+                    element
+                )?;
+            }
+
+            writeln!(xml, r#"  </testcase>"#)?;
+        } else {
+            // File with no issues - create passing test case
+            writeln!(
+                xml,
+                r#"  <testcase classname="forseti.{}" name="{}" time="0"/>"#,
+                ruleset_id,
+                html_escape(&file_name)
+            )?;
+        }
+    }
+
+    // Close testsuite
+    writeln!(xml, r#"</testsuite>"#)?;
+
+    Ok(xml)
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
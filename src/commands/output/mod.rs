@@ -0,0 +1,351 @@
+use crate::artifacts::WrittenArtifact;
+use crate::commands::lint::{
+    FileCounts, FileResult, GroupBy, HINT, LimitNote, OutputMode, PathStyle, RulesetStats,
+    relative_path, strip_hints,
+};
+use crate::commands::{OutputFormat, OutputSpec};
+use crate::context::GlobalContext;
+use crate::metadata::RunMetadata;
+use crate::paths;
+use crate::settings::OutputSettings;
+use anyhow::{Context, Result};
+use serde_json::{Value, json};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+mod codeclimate;
+mod compact;
+mod csv;
+mod exec;
+mod github;
+mod gitlab;
+mod json;
+mod junit;
+mod sarif;
+mod step_summary;
+mod tap;
+mod teamcity;
+mod text;
+
+use codeclimate::CodeclimateFormatter;
+use compact::generate_compact;
+use csv::generate_csv;
+use exec::ExecFormatter;
+use github::generate_github_annotations;
+use gitlab::generate_gitlab;
+use json::{JsonFormatter, generate_canonical_json, generate_jsonl};
+use junit::generate_junit_xml;
+use sarif::generate_sarif;
+use tap::generate_tap;
+use teamcity::generate_teamcity;
+use text::TextFormatter;
+
+pub(crate) use json::jsonl_line;
+pub(crate) use sarif::SarifBaseline;
+pub(crate) use step_summary::write_step_summary;
+
+/// Everything the output formats render
+#[derive(Clone, Copy)]
+pub(crate) struct Report<'a> {
+    pub file_results: &'a [FileResult],
+    pub total_diagnostics: usize,
+    pub workspace_root: &'a Path,
+    pub metadata: &'a RunMetadata,
+    pub ruleset_stats: &'a std::collections::BTreeMap<String, RulesetStats>,
+    pub file_counts: &'a FileCounts,
+    pub analyzed_files: &'a [PathBuf],
+    pub output_settings: &'a OutputSettings,
+    /// Print source excerpts under text diagnostics
+    pub code_frame: bool,
+    pub group_by: Option<GroupBy>,
+    /// Directory depth of the per-directory table in the text summary
+    pub group_summary_by: Option<usize>,
+    pub path_style: Option<PathStyle>,
+    /// Lint score of the whole run, see [`crate::commands::lint::lint_score`]
+    pub score: f64,
+    pub sarif_baseline: Option<&'a SarifBaseline>,
+    /// Project-wide metric totals, see [`crate::commands::lint::LintRun::metrics`]
+    pub metrics: &'a std::collections::BTreeMap<String, f64>,
+    /// Artifacts rulesets produced, as written
+    pub artifacts: &'a [WrittenArtifact],
+    /// Findings left out by the per-rule and per-file limits
+    pub limit_notes: &'a [LimitNote],
+    /// Findings left out of this report by those limits and
+    /// `--max-diagnostics`; a report with any is truncated
+    pub omitted: usize,
+}
+
+/// Renders a finished lint run in one output format
+pub(crate) trait Formatter {
+    /// The report as written to `--output-file`
+    fn render(&self, ctx: &GlobalContext, report: &Report) -> Result<String>;
+
+    /// Print the report to stdout
+    fn print(&self, ctx: &GlobalContext, report: &Report) -> Result<()> {
+        let rendered = self.render(ctx, report)?;
+        if rendered.is_empty() || rendered.ends_with('\n') {
+            print!("{}", rendered);
+        } else {
+            println!("{}", rendered);
+        }
+        Ok(())
+    }
+}
+
+/// The formatter registered for an `--output` value
+fn formatter(output: &OutputSpec) -> Box<dyn Formatter> {
+    match output {
+        OutputSpec::Builtin(format) => match format {
+            OutputFormat::Text => Box::new(TextFormatter),
+            OutputFormat::Json => Box::new(JsonFormatter),
+            OutputFormat::JsonCanonical => Box::new(RenderFn(generate_canonical_json)),
+            OutputFormat::Junit => Box::new(RenderFn(generate_junit_xml)),
+            OutputFormat::Sarif => Box::new(RenderFn(generate_sarif)),
+            OutputFormat::Gitlab => Box::new(RenderFn(generate_gitlab)),
+            OutputFormat::Codeclimate => Box::new(CodeclimateFormatter),
+            OutputFormat::Jsonl => Box::new(RenderFn(generate_jsonl)),
+            OutputFormat::Tap => Box::new(RenderFn(|report| Ok(generate_tap(report)))),
+            OutputFormat::Teamcity => Box::new(RenderFn(|report| Ok(generate_teamcity(report)))),
+            OutputFormat::Compact => Box::new(RenderFn(|report| Ok(generate_compact(report)))),
+            OutputFormat::Csv => Box::new(RenderFn(|report| Ok(generate_csv(report)))),
+            OutputFormat::Github => {
+                Box::new(RenderFn(|report| Ok(generate_github_annotations(report))))
+            }
+        },
+        OutputSpec::Exec(command) => Box::new(ExecFormatter {
+            command: command.clone(),
+        }),
+    }
+}
+
+/// Match `--output-file`s to the last `--output`s, leaving earlier outputs
+/// on stdout
+pub(crate) fn pair_outputs(
+    outputs: Vec<OutputSpec>,
+    files: Vec<PathBuf>,
+) -> Result<Vec<(OutputSpec, Option<PathBuf>)>> {
+    if files.len() > outputs.len() {
+        return Err(anyhow::anyhow!(
+            "{} --output-file(s) given for {} --output(s)",
+            files.len(),
+            outputs.len()
+        ));
+    }
+    let on_stdout = outputs.len() - files.len();
+    if on_stdout > 1 {
+        return Err(anyhow::anyhow!(
+            "Only one --output can go to stdout; add an --output-file for the others"
+        ));
+    }
+    let files = std::iter::repeat_n(None, on_stdout).chain(files.into_iter().map(Some));
+    Ok(outputs.into_iter().zip(files).collect())
+}
+
+impl<'a> Report<'a> {
+    /// A file path as text, JSON and JUnit reports write it
+    pub(crate) fn display_path(&self, path: &Path) -> String {
+        match self.path_style {
+            None => path.display().to_string(),
+            Some(PathStyle::FromRoot) => relative_path(path, self.workspace_root),
+            Some(PathStyle::Absolute) => paths::display(&paths::canonicalize(path)),
+            Some(PathStyle::Relative) => {
+                let cwd = std::env::current_dir()
+                    .map(|cwd| paths::canonicalize(&cwd))
+                    .unwrap_or_default();
+                paths::display(&paths::relative_to(&paths::canonicalize(path), &cwd))
+            }
+        }
+    }
+
+    /// The same report narrowed to some of its findings
+    pub(crate) fn with_results<'b>(&self, file_results: &'b [FileResult]) -> Report<'b>
+    where
+        'a: 'b,
+    {
+        Report {
+            file_results,
+            total_diagnostics: file_results
+                .iter()
+                .map(|result| result.findings.len())
+                .sum(),
+            ..*self
+        }
+    }
+}
+
+pub(crate) fn output_results(
+    ctx: &GlobalContext,
+    report: &Report,
+    output: &OutputSpec,
+    output_file: Option<PathBuf>,
+    mode: OutputMode,
+) -> Result<()> {
+    // Editor integrations read the JSON formats; everything else is for
+    // people and CI, which hints would only distract
+    let shows_hints = report.output_settings.hints
+        || matches!(
+            output,
+            OutputSpec::Exec(_)
+                | OutputSpec::Builtin(
+                    OutputFormat::Json | OutputFormat::JsonCanonical | OutputFormat::Jsonl
+                )
+        );
+    let without_hints;
+    let report = if shows_hints {
+        *report
+    } else {
+        without_hints = strip_hints(report.file_results);
+        report.with_results(without_hints.as_deref().unwrap_or(report.file_results))
+    };
+    let report = &report;
+    let formatter = formatter(output);
+    match output_file {
+        Some(output_file) => {
+            let rendered = formatter.render(ctx, report)?;
+            let existing = match mode {
+                OutputMode::Overwrite => None,
+                OutputMode::Append | OutputMode::Merge => fs::read_to_string(&output_file)
+                    .ok()
+                    .filter(|existing| !existing.trim().is_empty()),
+            };
+            let contents = match existing {
+                None => rendered,
+                Some(existing) if mode == OutputMode::Merge => {
+                    merge_reports(output, &existing, &rendered).with_context(|| {
+                        format!("Failed to merge into {}", output_file.display())
+                    })?
+                }
+                Some(mut existing) => {
+                    if !existing.ends_with('\n') {
+                        existing.push('\n');
+                    }
+                    // One header per CSV file
+                    let rendered = match output {
+                        OutputSpec::Builtin(OutputFormat::Csv) => {
+                            rendered.split_once('\n').map_or("", |(_, rows)| rows)
+                        }
+                        _ => &rendered,
+                    };
+                    existing + rendered
+                }
+            };
+            paths::write_atomic(&output_file, contents.as_bytes())
+                .with_context(|| format!("Failed to write report: {}", output_file.display()))
+        }
+        None => formatter.print(ctx, report),
+    }
+}
+
+/// Combine an existing json or sarif report with this run's: SARIF gains
+/// this run's runs, JSON files and counts are combined and the lower score kept
+fn merge_reports(output: &OutputSpec, existing: &str, rendered: &str) -> Result<String> {
+    let mut merged: Value =
+        serde_json::from_str(existing).context("Existing report is not JSON")?;
+    let new: Value = serde_json::from_str(rendered)?;
+    match output {
+        OutputSpec::Builtin(OutputFormat::Sarif) => {
+            let runs = merged["runs"]
+                .as_array_mut()
+                .ok_or_else(|| anyhow::anyhow!("Existing report is not SARIF"))?;
+            runs.extend(new["runs"].as_array().into_iter().flatten().cloned());
+        }
+        _ => {
+            if merged["version"] != new["version"] {
+                return Err(anyhow::anyhow!(
+                    "Existing report has JSON report version {}, not {}",
+                    merged["version"],
+                    new["version"]
+                ));
+            }
+            let files = merged["files"]
+                .as_array_mut()
+                .ok_or_else(|| anyhow::anyhow!("Existing report has no files"))?;
+            for file in new["files"].as_array().into_iter().flatten() {
+                let diagnostics = file["diagnostics"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .cloned();
+                match files
+                    .iter_mut()
+                    .find(|existing| existing["path"] == file["path"])
+                {
+                    Some(existing) => {
+                        if let Some(existing) = existing["diagnostics"].as_array_mut() {
+                            existing.extend(diagnostics);
+                        }
+                    }
+                    None => files.push(file.clone()),
+                }
+            }
+            for group in ["files", "diagnostics"] {
+                let Some(counts) = merged["summary"][group].as_object_mut() else {
+                    continue;
+                };
+                for (name, count) in counts.iter_mut() {
+                    let added = new["summary"][group][name].as_u64();
+                    if let (Some(a), Some(b)) = (count.as_u64(), added) {
+                        *count = json!(a + b);
+                    }
+                }
+            }
+            if let (Some(a), Some(b)) = (
+                merged["summary"]["score"].as_f64(),
+                new["summary"]["score"].as_f64(),
+            ) {
+                merged["summary"]["score"] = json!(a.min(b));
+            }
+            if new["summary"]["truncated"] == json!(true) {
+                merged["summary"]["truncated"] = json!(true);
+            }
+            // Both runs' totals, for metrics either run reported
+            if let Some(added) = new["metrics"].as_object() {
+                if !merged["metrics"].is_object() {
+                    merged["metrics"] = json!({});
+                }
+                let totals = merged["metrics"].as_object_mut().unwrap();
+                for (name, value) in added {
+                    let total = totals.get(name).and_then(Value::as_f64).unwrap_or(0.0);
+                    totals.insert(name.clone(), json!(total + value.as_f64().unwrap_or(0.0)));
+                }
+            }
+            // Each run's artifacts went to their own files, so list them all
+            if let Some(added) = new["artifacts"].as_array() {
+                match merged["artifacts"].as_array_mut() {
+                    Some(listed) => listed.extend(added.iter().cloned()),
+                    None => merged["artifacts"] = json!(added),
+                }
+            }
+            merged["tool"] = new["tool"].clone();
+        }
+    }
+    Ok(serde_json::to_string_pretty(&merged)?)
+}
+
+/// A format that is a plain function of the report
+struct RenderFn(fn(&Report) -> Result<String>);
+
+impl Formatter for RenderFn {
+    fn render(&self, _ctx: &GlobalContext, report: &Report) -> Result<String> {
+        (self.0)(report)
+    }
+}
+
+/// (errors, warnings, info) per ruleset
+pub(crate) fn severity_counts(
+    file_results: &[FileResult],
+) -> std::collections::BTreeMap<&str, (usize, usize, usize)> {
+    let mut counts: std::collections::BTreeMap<&str, (usize, usize, usize)> =
+        std::collections::BTreeMap::new();
+    for result in file_results {
+        let entry = counts.entry(result.ruleset_id.as_str()).or_default();
+        for finding in &result.findings {
+            match finding.diagnostic.severity.as_str() {
+                "error" => entry.0 += 1,
+                "info" | HINT => entry.2 += 1,
+                _ => entry.1 += 1,
+            }
+        }
+    }
+    counts
+}
@@ -0,0 +1,156 @@
+use super::Report;
+use crate::commands::lint::{relative_path, truncation_note};
+use crate::fingerprint::FINGERPRINT_VERSION;
+use crate::metadata::METADATA_KEY;
+use crate::rules;
+use anyhow::{Context, Result};
+use serde_json::{Value, json};
+use std::fs;
+use std::path::Path;
+
+/// SARIF 2.1.0 log with one run covering every ruleset
+pub(crate) fn generate_sarif(report: &Report) -> Result<String> {
+    let Report {
+        file_results,
+        workspace_root,
+        metadata,
+        file_counts,
+        output_settings,
+        ..
+    } = *report;
+    let sarif_settings = &output_settings.sarif;
+    let mut sarif_rules: std::collections::BTreeMap<String, Value> =
+        std::collections::BTreeMap::new();
+    // Highest security-severity among each rule's results
+    let mut scores: std::collections::BTreeMap<String, f64> = std::collections::BTreeMap::new();
+    let mut results = Vec::new();
+
+    for result in file_results {
+        let uri = relative_path(&result.path, workspace_root);
+        for finding in &result.findings {
+            let diagnostic = &finding.diagnostic;
+            let rule_id = rules::qualify(&result.ruleset_id, &diagnostic.rule_id);
+
+            sarif_rules.entry(rule_id.clone()).or_insert_with(|| {
+                let mut rule = json!({
+                    "id": rule_id,
+                    "properties": { "ruleset": result.ruleset_id },
+                });
+                if let Some(docs_url) = &diagnostic.docs_url {
+                    rule["helpUri"] = json!(docs_url);
+                }
+                rule
+            });
+            if let Some(&score) = sarif_settings.security_severity.get(&diagnostic.severity) {
+                let max = scores.entry(rule_id.clone()).or_insert(score);
+                *max = max.max(score);
+            }
+
+            let mut sarif_result = json!({
+                "ruleId": rule_id,
+                "level": sarif_settings.level(&diagnostic.severity),
+                "message": { "text": diagnostic.message },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": uri },
+                        "region": {
+                            "startLine": diagnostic.range.start.line + 1,
+                            "startColumn": diagnostic.range.start.character + 1,
+                            "endLine": diagnostic.range.end.line + 1,
+                            "endColumn": diagnostic.range.end.character + 1,
+                        }
+                    }
+                }],
+                "fingerprints": { FINGERPRINT_VERSION: finding.fingerprint },
+                "partialFingerprints": { FINGERPRINT_VERSION: finding.fingerprint },
+            });
+            if let Some(baseline) = report.sarif_baseline {
+                if baseline.fingerprints.contains(&finding.fingerprint) {
+                    sarif_result["baselineState"] = json!("unchanged");
+                    sarif_result["suppressions"] = json!([{
+                        "kind": "external",
+                        "justification": format!("Present in baseline {}", baseline.origin),
+                    }]);
+                } else {
+                    sarif_result["baselineState"] = json!("new");
+                }
+            }
+            results.push(sarif_result);
+        }
+    }
+
+    for (rule_id, score) in scores {
+        if let Some(rule) = sarif_rules.get_mut(&rule_id) {
+            rule["properties"]["security-severity"] = json!(format!("{:.1}", score));
+        }
+    }
+
+    let sarif = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "forseti",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": env!("CARGO_PKG_REPOSITORY"),
+                    "rules": sarif_rules.into_values().collect::<Vec<_>>(),
+                }
+            },
+            "results": results,
+            "invocations": [{
+                "executionSuccessful": true,
+                "startTimeUtc": metadata.timestamp,
+                "arguments": metadata.args,
+                "toolExecutionNotifications": (report.omitted > 0).then(|| json!([{
+                    "level": "warning",
+                    "message": { "text": truncation_note(report.omitted) },
+                }])),
+            }],
+            "versionControlProvenance": metadata.git_commit.as_ref().map(|commit| json!([{
+                "revisionId": commit,
+            }])),
+            "properties": {
+                METADATA_KEY: metadata,
+                "forseti.files": file_counts,
+                "forseti.omitted": report.omitted,
+            },
+        }]
+    });
+
+    Ok(serde_json::to_string_pretty(&sarif)?)
+}
+
+/// Findings of an earlier SARIF report, for `--sarif-baseline`
+pub(crate) struct SarifBaseline {
+    /// The report as given on the command line
+    origin: String,
+    fingerprints: std::collections::HashSet<String>,
+}
+
+impl SarifBaseline {
+    /// Read the forseti fingerprints of every result in a SARIF report
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read SARIF baseline: {}", path.display()))?;
+        let sarif: Value = serde_json::from_str(&content)
+            .with_context(|| format!("SARIF baseline is not JSON: {}", path.display()))?;
+        let fingerprints = sarif["runs"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|run| run["results"].as_array())
+            .flatten()
+            .filter_map(|result| {
+                result["partialFingerprints"][FINGERPRINT_VERSION]
+                    .as_str()
+                    .or_else(|| result["fingerprints"][FINGERPRINT_VERSION].as_str())
+            })
+            .map(str::to_string)
+            .collect();
+        Ok(Self {
+            origin: path.display().to_string(),
+            fingerprints,
+        })
+    }
+}
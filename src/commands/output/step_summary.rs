@@ -0,0 +1,107 @@
+use super::{Report, severity_counts};
+use crate::commands::lint::{in_github_actions, relative_path, truncation_note};
+use crate::rules;
+use anyhow::{Context, Result};
+use std::fs;
+use std::io::Write;
+
+/// Findings listed individually in the GitHub job summary
+const STEP_SUMMARY_FINDINGS: usize = 50;
+
+/// Markdown job summary for GitHub Actions
+fn generate_step_summary(report: &Report) -> String {
+    use std::fmt::Write;
+
+    let counts = severity_counts(report.file_results);
+    let (errors, warnings, info) = counts.values().fold((0, 0, 0), |total, count| {
+        (total.0 + count.0, total.1 + count.1, total.2 + count.2)
+    });
+    let cell = |text: &str| text.replace('|', "\\|").replace('\n', " ");
+
+    let mut md = String::new();
+    let _ = writeln!(md, "## Forseti\n");
+    let _ = writeln!(
+        md,
+        "| Files analyzed | Files with issues | Errors | Warnings | Info | Score |"
+    );
+    let _ = writeln!(md, "|---:|---:|---:|---:|---:|---:|");
+    let _ = writeln!(
+        md,
+        "| {} | {} | {} | {} | {} | {:.1} |\n",
+        report.file_counts.analyzed,
+        report.file_counts.with_issues,
+        errors,
+        warnings,
+        info,
+        report.score
+    );
+
+    if !report.ruleset_stats.is_empty() {
+        let _ = writeln!(md, "| Ruleset | Files | Errors | Warnings | Info | Time |");
+        let _ = writeln!(md, "|---|---:|---:|---:|---:|---:|");
+        for (ruleset_id, stats) in report.ruleset_stats {
+            let (errors, warnings, info) =
+                counts.get(ruleset_id.as_str()).copied().unwrap_or_default();
+            let _ = writeln!(
+                md,
+                "| {} | {} | {} | {} | {} | {:.2}s |",
+                cell(ruleset_id),
+                stats.files,
+                errors,
+                warnings,
+                info,
+                stats.elapsed.as_secs_f64()
+            );
+        }
+        let _ = writeln!(md);
+    }
+
+    if report.total_diagnostics > 0 {
+        let _ = writeln!(
+            md,
+            "<details><summary>Findings (first {})</summary>\n",
+            STEP_SUMMARY_FINDINGS.min(report.total_diagnostics)
+        );
+        let _ = writeln!(md, "| Location | Severity | Rule | Message |");
+        let _ = writeln!(md, "|---|---|---|---|");
+        let findings = report
+            .file_results
+            .iter()
+            .flat_map(|result| result.findings.iter().map(move |finding| (result, finding)));
+        for (result, finding) in findings.take(STEP_SUMMARY_FINDINGS) {
+            let diagnostic = &finding.diagnostic;
+            let _ = writeln!(
+                md,
+                "| `{}:{}` | {} | {} | {} |",
+                relative_path(&result.path, report.workspace_root),
+                diagnostic.range.start.line + 1,
+                diagnostic.severity,
+                cell(&rules::qualify(&result.ruleset_id, &diagnostic.rule_id)),
+                cell(&diagnostic.message)
+            );
+        }
+        let _ = writeln!(md, "\n</details>");
+    }
+    if report.omitted > 0 {
+        let _ = writeln!(md, "\n_{}_", truncation_note(report.omitted));
+    }
+    md
+}
+
+/// Append the job summary to `$GITHUB_STEP_SUMMARY` when running in GitHub Actions
+pub(crate) fn write_step_summary(report: &Report) -> Result<()> {
+    if !in_github_actions() {
+        return Ok(());
+    }
+    let Some(path) = std::env::var_os("GITHUB_STEP_SUMMARY") else {
+        return Ok(());
+    };
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .context("Failed to open $GITHUB_STEP_SUMMARY")?;
+    file.write_all(generate_step_summary(report).as_bytes())
+        .context("Failed to write $GITHUB_STEP_SUMMARY")?;
+    Ok(())
+}
@@ -0,0 +1,51 @@
+use super::Report;
+use crate::commands::lint::{Finding, relative_path, truncation_note};
+use crate::rules;
+use serde_json::json;
+
+/// TAP 13 stream with one test point per analyzed file, failing when the
+/// file has findings, which are listed in the YAML diagnostic block
+pub(crate) fn generate_tap(report: &Report) -> String {
+    use std::fmt::Write;
+
+    let mut tap = String::new();
+    let _ = writeln!(tap, "TAP version 13");
+    let _ = writeln!(tap, "1..{}", report.analyzed_files.len());
+    for (index, file) in report.analyzed_files.iter().enumerate() {
+        let path = relative_path(file, report.workspace_root);
+        let findings: Vec<(&str, &Finding)> = report
+            .file_results
+            .iter()
+            .filter(|result| &result.path == file)
+            .flat_map(|result| {
+                result
+                    .findings
+                    .iter()
+                    .map(move |finding| (result.ruleset_id.as_str(), finding))
+            })
+            .collect();
+
+        if findings.is_empty() {
+            let _ = writeln!(tap, "ok {} - {}", index + 1, path);
+            continue;
+        }
+        let _ = writeln!(tap, "not ok {} - {}", index + 1, path);
+        let _ = writeln!(tap, "  ---");
+        let _ = writeln!(tap, "  findings:");
+        for (ruleset_id, finding) in findings {
+            let diagnostic = &finding.diagnostic;
+            // JSON strings are valid YAML scalars, which saves escaping rules
+            let rule_id = rules::qualify(ruleset_id, &diagnostic.rule_id);
+            let _ = writeln!(tap, "    - rule: {}", rule_id);
+            let _ = writeln!(tap, "      severity: {}", diagnostic.severity);
+            let _ = writeln!(tap, "      line: {}", diagnostic.range.start.line + 1);
+            let _ = writeln!(tap, "      column: {}", finding.column);
+            let _ = writeln!(tap, "      message: {}", json!(diagnostic.message));
+        }
+        let _ = writeln!(tap, "  ...");
+    }
+    if report.omitted > 0 {
+        let _ = writeln!(tap, "# {}", truncation_note(report.omitted));
+    }
+    tap
+}
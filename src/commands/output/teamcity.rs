@@ -0,0 +1,56 @@
+use super::Report;
+use crate::commands::lint::{HINT, relative_path, truncation_note};
+use crate::rules;
+
+/// TeamCity service messages: an `inspectionType` per rule, then an
+/// `inspection` per finding
+pub(crate) fn generate_teamcity(report: &Report) -> String {
+    let mut messages = String::new();
+    let mut declared = std::collections::HashSet::new();
+    for result in report.file_results {
+        let path = relative_path(&result.path, report.workspace_root);
+        for finding in &result.findings {
+            let diagnostic = &finding.diagnostic;
+            let type_id = rules::qualify(&result.ruleset_id, &diagnostic.rule_id);
+            if declared.insert(type_id.clone()) {
+                messages.push_str(&format!(
+                    "##teamcity[inspectionType id='{}' name='{}' description='{}' category='{}']\n",
+                    teamcity_escape(&type_id),
+                    teamcity_escape(&diagnostic.rule_id),
+                    teamcity_escape(diagnostic.docs_url.as_deref().unwrap_or(&type_id)),
+                    teamcity_escape(&result.ruleset_id)
+                ));
+            }
+            let severity = match diagnostic.severity.as_str() {
+                "error" => "ERROR",
+                "info" | HINT => "INFO",
+                _ => "WARNING",
+            };
+            messages.push_str(&format!(
+                "##teamcity[inspection typeId='{}' message='{}' file='{}' line='{}' \
+                 SEVERITY='{}']\n",
+                teamcity_escape(&type_id),
+                teamcity_escape(&diagnostic.message),
+                teamcity_escape(&path),
+                diagnostic.range.start.line + 1,
+                severity
+            ));
+        }
+    }
+    if report.omitted > 0 {
+        messages.push_str(&format!(
+            "##teamcity[message text='{}' status='WARNING']\n",
+            teamcity_escape(&truncation_note(report.omitted))
+        ));
+    }
+    messages
+}
+
+fn teamcity_escape(s: &str) -> String {
+    s.replace('|', "||")
+        .replace('\'', "|'")
+        .replace('\n', "|n")
+        .replace('\r', "|r")
+        .replace('[', "|[")
+        .replace(']', "|]")
+}
@@ -0,0 +1,504 @@
+use super::{Formatter, Report, severity_counts};
+use crate::commands::lint::{
+    FileResult, Finding, GroupBy, HINT, RulesetStats, display_column, relative_path, thousands,
+};
+use crate::context::GlobalContext;
+use crate::i18n::Msg;
+use crate::line_index::LineIndex;
+use crate::rules;
+use anyhow::Result;
+use forseti_sdk::core::Diagnostic;
+use std::path::Path;
+
+/// `file:line:col: message [ruleset/rule]` lines and a summary, colored on a terminal
+pub(crate) struct TextFormatter;
+
+impl TextFormatter {
+    fn generate(ctx: &GlobalContext, report: &Report, color: bool) -> String {
+        use std::fmt::Write;
+
+        let Report {
+            file_results,
+            total_diagnostics,
+            file_counts,
+            ..
+        } = *report;
+        let paint = |text: &str, code: u8| {
+            if color {
+                ctx.paint(text, code)
+            } else {
+                text.to_string()
+            }
+        };
+        let mut out = String::new();
+        let mut error_count = 0;
+        let mut warn_count = 0;
+        let mut info_count = 0;
+        let findings: Vec<(&FileResult, &Finding)> = file_results
+            .iter()
+            .flat_map(|result| result.findings.iter().map(move |finding| (result, finding)))
+            .collect();
+        for (_, finding) in &findings {
+            // Count diagnostics by severity
+            match finding.diagnostic.severity.as_str() {
+                "error" => error_count += 1,
+                "warn" => warn_count += 1,
+                "info" | HINT => info_count += 1,
+                _ => warn_count += 1, // Default to warn for unknown severities
+            }
+        }
+
+        // Flagged text may be a secret, so redaction disables frames
+        let frames = report.code_frame && !report.output_settings.redact_snippets;
+        let write_finding = |out: &mut String,
+                             result: &FileResult,
+                             finding: &Finding,
+                             location: String,
+                             rule: bool| {
+            let diagnostic = &finding.diagnostic;
+            let color = severity_color(&diagnostic.severity);
+            let rule_part = if rule {
+                format!(
+                    " [{}]",
+                    rules::qualify(&result.ruleset_id, &diagnostic.rule_id)
+                )
+            } else {
+                String::new()
+            };
+            let docs_part = if let Some(ref docs_url) = diagnostic.docs_url {
+                format!(" ({})", docs_url)
+            } else {
+                String::new()
+            };
+            // Severities otherwise only show as colors
+            let severity_part = if ctx.ascii {
+                match diagnostic.severity.as_str() {
+                    "warn" => "warning: ".to_string(),
+                    severity => format!("{}: ", severity),
+                }
+            } else {
+                String::new()
+            };
+            let _ = writeln!(
+                out,
+                "{} {}{}{}{}",
+                paint(&location, DIM),
+                severity_part,
+                paint(&diagnostic.message, color),
+                rule_part,
+                docs_part
+            );
+            if !frames {
+                return;
+            }
+            let tab_width = report.output_settings.tab_width();
+            for (line, marker) in code_frame(&result.source, diagnostic, tab_width) {
+                let _ = writeln!(out, "{}", line);
+                if let Some(marker) = marker {
+                    let _ = writeln!(out, "{}", paint(&marker, color));
+                }
+            }
+            let _ = writeln!(out);
+        };
+        let position = |finding: &Finding| {
+            format!(
+                "{}:{}:",
+                finding.diagnostic.range.start.line + 1,
+                finding.column
+            )
+        };
+
+        match report.group_by {
+            None => {
+                for (result, finding) in findings {
+                    let path = report.display_path(&result.path);
+                    let location = format!("{}:{}", path, position(finding));
+                    write_finding(&mut out, result, finding, location, true);
+                }
+            }
+            Some(GroupBy::File) => {
+                let mut groups: std::collections::BTreeMap<&Path, Vec<(&FileResult, &Finding)>> =
+                    std::collections::BTreeMap::new();
+                for (result, finding) in findings {
+                    groups
+                        .entry(&result.path)
+                        .or_default()
+                        .push((result, finding));
+                }
+                for (path, mut group) in groups {
+                    group.sort_by_key(|(_, finding)| {
+                        let start = &finding.diagnostic.range.start;
+                        (start.line, start.character)
+                    });
+                    let header = format!("{} ({})", report.display_path(path), group.len());
+                    let _ = writeln!(out, "{}", paint(&header, BOLD));
+                    for (result, finding) in group {
+                        write_finding(
+                            &mut out,
+                            result,
+                            finding,
+                            format!("  {}", position(finding)),
+                            true,
+                        );
+                    }
+                    let _ = writeln!(out);
+                }
+            }
+            Some(GroupBy::Rule) => {
+                let mut groups: std::collections::BTreeMap<String, Vec<(&FileResult, &Finding)>> =
+                    std::collections::BTreeMap::new();
+                for (result, finding) in findings {
+                    let id = rules::qualify(&result.ruleset_id, &finding.diagnostic.rule_id);
+                    groups.entry(id).or_default().push((result, finding));
+                }
+                // Noisiest rules first, the order triage usually wants
+                let mut groups: Vec<_> = groups.into_iter().collect();
+                groups.sort_by(|(a, a_group), (b, b_group)| {
+                    b_group.len().cmp(&a_group.len()).then_with(|| a.cmp(b))
+                });
+                for (id, group) in groups {
+                    let header = format!("{} ({})", id, group.len());
+                    let _ = writeln!(out, "{}", paint(&header, BOLD));
+                    for (result, finding) in group {
+                        let path = report.display_path(&result.path);
+                        let location = format!("  {}:{}", path, position(finding));
+                        write_finding(&mut out, result, finding, location, false);
+                    }
+                    let _ = writeln!(out);
+                }
+            }
+        }
+
+        // Summary
+        let lang = ctx.lang;
+        if total_diagnostics > 0 || report.omitted > 0 {
+            let _ = writeln!(out);
+            let _ = writeln!(out, "{}", lang.text(Msg::Summary));
+            let count = |msg: Msg, count: usize| format!("{}: {}", lang.text(msg), count);
+            let _ = writeln!(
+                out,
+                "  {}",
+                count(Msg::FilesDiscovered, file_counts.discovered)
+            );
+            let _ = writeln!(out, "  {}", count(Msg::FilesAnalyzed, file_counts.analyzed));
+            if file_counts.skipped > 0 {
+                let _ = writeln!(out, "  {}", count(Msg::FilesSkipped, file_counts.skipped));
+            }
+            let _ = writeln!(
+                out,
+                "  {}",
+                count(Msg::FilesWithIssues, file_counts.with_issues)
+            );
+            let _ = writeln!(out, "  {}", count(Msg::TotalIssues, total_diagnostics));
+            if error_count > 0 {
+                let _ = writeln!(out, "    {}", paint(&count(Msg::Errors, error_count), RED));
+            }
+            if warn_count > 0 {
+                let _ = writeln!(
+                    out,
+                    "    {}",
+                    paint(&count(Msg::Warnings, warn_count), YELLOW)
+                );
+            }
+            if info_count > 0 {
+                let _ = writeln!(out, "    {}", paint(&count(Msg::Info, info_count), BLUE));
+            }
+            let _ = writeln!(
+                out,
+                "  {}: {:.1}/100",
+                lang.text(Msg::LintScore),
+                report.score
+            );
+            if report.omitted > 0 {
+                let note = lang.format(Msg::Truncated, &[&thousands(report.omitted)]);
+                let _ = writeln!(out, "  {}", paint(&note, BOLD));
+            }
+            for note in report.limit_notes {
+                let _ = writeln!(
+                    out,
+                    "  {}",
+                    lang.format(Msg::MoreFrom, &[&thousands(note.more), &note.origin])
+                );
+            }
+            if let Some(depth) = report.group_summary_by {
+                let rows = directory_summary(file_results, report.workspace_root, depth);
+                let headers = [
+                    Msg::Directory,
+                    Msg::Files,
+                    Msg::Issues,
+                    Msg::Errors,
+                    Msg::Warnings,
+                    Msg::Info,
+                ]
+                .map(|msg| lang.text(msg));
+                // Columns fit the header in any language
+                let widths = headers.map(|header| header.chars().count());
+                let width = rows
+                    .iter()
+                    .map(|row| row.directory.chars().count())
+                    .chain([widths[0]])
+                    .max()
+                    .unwrap_or_default();
+                let [_, files, issues, errors, warnings, info] = widths;
+                let _ = writeln!(out);
+                let _ = writeln!(
+                    out,
+                    "  {:<width$}  {:>files$}  {:>issues$}  {:>errors$}  {:>warnings$}  {:>info$}",
+                    headers[0], headers[1], headers[2], headers[3], headers[4], headers[5]
+                );
+                for row in rows {
+                    let _ = writeln!(
+                        out,
+                        "  {:<width$}  {:>files$}  {:>issues$}  {:>errors$}  {:>warnings$}  {:>info$}",
+                        row.directory, row.files, row.issues, row.errors, row.warnings, row.info
+                    );
+                }
+            }
+        } else {
+            let _ = writeln!(out);
+            let _ = writeln!(
+                out,
+                "{}{}",
+                ctx.glyph("✓ ", ""),
+                lang.format(Msg::NoIssues, &[&file_counts.analyzed])
+            );
+            if file_counts.skipped > 0 {
+                let _ = writeln!(
+                    out,
+                    "  {}: {}",
+                    lang.text(Msg::FilesSkipped),
+                    file_counts.skipped
+                );
+            }
+        }
+
+        out
+    }
+}
+
+impl Formatter for TextFormatter {
+    fn render(&self, ctx: &GlobalContext, report: &Report) -> Result<String> {
+        Ok(Self::generate(ctx, report, false))
+    }
+
+    fn print(&self, ctx: &GlobalContext, report: &Report) -> Result<()> {
+        print!("{}", Self::generate(ctx, report, true));
+        print_ruleset_table(ctx, report.file_results, report.ruleset_stats);
+        Ok(())
+    }
+}
+
+/// Per-ruleset breakdown of files analyzed, issues by severity and time spent
+fn print_ruleset_table(
+    ctx: &GlobalContext,
+    file_results: &[FileResult],
+    ruleset_stats: &std::collections::BTreeMap<String, RulesetStats>,
+) {
+    // CI logs stay compact
+    if ruleset_stats.is_empty() || ctx.ci {
+        return;
+    }
+
+    let counts = severity_counts(file_results);
+
+    // Columns widen to fit headers in other languages
+    let headers = [
+        (Msg::Ruleset, 20),
+        (Msg::Files, 6),
+        (Msg::Errors, 8),
+        (Msg::Warnings, 8),
+        (Msg::Info, 8),
+        (Msg::Time, 9),
+    ]
+    .map(|(msg, width)| {
+        let header = ctx.lang.text(msg);
+        (header, width.max(header.chars().count()))
+    });
+    let [
+        ruleset_width,
+        files_width,
+        errors_width,
+        warnings_width,
+        info_width,
+        time_width,
+    ] = headers.map(|(_, width)| width);
+
+    // Pad before painting so escape codes don't break alignment
+    let cell = |count: usize, width: usize, color: u8| {
+        let text = format!("{:>width$}", count);
+        if count > 0 {
+            ctx.paint(&text, color)
+        } else {
+            text
+        }
+    };
+
+    println!();
+    println!(
+        "  {:<ruleset_width$} {:>files_width$} {:>errors_width$} {:>warnings_width$} {:>info_width$} {:>time_width$}",
+        headers[0].0, headers[1].0, headers[2].0, headers[3].0, headers[4].0, headers[5].0
+    );
+    for (ruleset_id, stats) in ruleset_stats {
+        let (errors, warnings, info) = counts.get(ruleset_id.as_str()).copied().unwrap_or_default();
+        println!(
+            "  {:<ruleset_width$} {:>files_width$} {} {} {} {:>seconds$.2}s",
+            ruleset_id,
+            stats.files,
+            cell(errors, errors_width, RED),
+            cell(warnings, warnings_width, YELLOW),
+            cell(info, info_width, BLUE),
+            stats.elapsed.as_secs_f64(),
+            seconds = time_width - 1
+        );
+    }
+}
+
+/// ANSI colors used for severities in the text summary
+const RED: u8 = 31;
+const YELLOW: u8 = 33;
+const BLUE: u8 = 34;
+/// ANSI faint style, for locations that shouldn't compete with messages
+const DIM: u8 = 2;
+/// ANSI bold style, for group headers
+const BOLD: u8 = 1;
+
+fn severity_color(severity: &str) -> u8 {
+    match severity {
+        "error" => RED,
+        "info" | HINT => BLUE,
+        _ => YELLOW,
+    }
+}
+
+/// Source lines shown above and below a diagnostic's range in a code frame
+const CODE_FRAME_CONTEXT: usize = 2;
+
+/// rustc-style excerpt of the lines around a diagnostic: each source line
+/// with a line-number gutter, and under every line the range covers, a
+/// marker line underlining it with `^`
+///
+/// Tabs are expanded to `tab_width` so markers line up with the text.
+fn code_frame(
+    content: &str,
+    diagnostic: &Diagnostic,
+    tab_width: usize,
+) -> Vec<(String, Option<String>)> {
+    let index = LineIndex::new(content);
+    let start = &diagnostic.range.start;
+    let end = &diagnostic.range.end;
+    let start_line = start.line as usize;
+    let mut end_line = (end.line as usize).max(start_line);
+    // A range running to the start of the next line ends with this one
+    if end_line > start_line && end.character == 0 {
+        end_line -= 1;
+    }
+    if start_line >= index.line_count() {
+        return Vec::new();
+    }
+    let first = start_line.saturating_sub(CODE_FRAME_CONTEXT);
+    let last = (end_line + CODE_FRAME_CONTEXT).min(index.line_count() - 1);
+    let gutter = (last + 1).to_string().len();
+
+    let mut frame = vec![(format!("{:gutter$} |", ""), None)];
+    for line in first..=last {
+        let text = index.line(line).unwrap_or_default();
+        let expanded = expand_tabs(text, tab_width);
+        let marker = (start_line..=end_line).contains(&line).then(|| {
+            let from = if line == start_line {
+                display_column(text, start.character as usize, tab_width)
+            } else {
+                0
+            };
+            let to = if line == end.line as usize {
+                display_column(text, end.character as usize, tab_width)
+            } else {
+                expanded.chars().count()
+            };
+            format!(
+                "{:gutter$} | {}{}",
+                "",
+                " ".repeat(from),
+                "^".repeat(to.saturating_sub(from).max(1))
+            )
+        });
+        frame.push((
+            format!("{:>gutter$} | {}", line + 1, expanded)
+                .trim_end()
+                .to_string(),
+            marker,
+        ));
+    }
+    frame
+}
+
+/// A line with each tab replaced by spaces up to the next tab stop
+fn expand_tabs(line: &str, tab_width: usize) -> String {
+    let tab_width = tab_width.max(1);
+    let mut expanded = String::with_capacity(line.len());
+    for c in line.chars() {
+        if c == '\t' {
+            let column = expanded.chars().count();
+            expanded.push_str(&" ".repeat(tab_width - column % tab_width));
+        } else {
+            expanded.push(c);
+        }
+    }
+    expanded
+}
+
+/// Issue counts for one row of the `--group-summary-by` table
+#[derive(Default)]
+struct DirectorySummary {
+    directory: String,
+    /// Files with issues
+    files: usize,
+    issues: usize,
+    errors: usize,
+    warnings: usize,
+    info: usize,
+}
+
+/// Findings rolled up by the first `depth` directories of each file's path
+/// under the workspace root (`.` for files at the root), most issues first
+fn directory_summary(
+    file_results: &[FileResult],
+    workspace_root: &Path,
+    depth: usize,
+) -> Vec<DirectorySummary> {
+    let mut rows: std::collections::BTreeMap<String, DirectorySummary> =
+        std::collections::BTreeMap::new();
+    let mut files = std::collections::HashSet::new();
+    for result in file_results {
+        let relative = relative_path(&result.path, workspace_root);
+        let parents: Vec<&str> = relative.split('/').collect();
+        let parents = &parents[..parents.len() - 1];
+        let directory = match parents {
+            [] => ".".to_string(),
+            _ => parents[..depth.min(parents.len())].join("/"),
+        };
+        let row = rows
+            .entry(directory.clone())
+            .or_insert_with(|| DirectorySummary {
+                directory,
+                ..DirectorySummary::default()
+            });
+        if !result.findings.is_empty() && files.insert(&result.path) {
+            row.files += 1;
+        }
+        for finding in &result.findings {
+            row.issues += 1;
+            match finding.diagnostic.severity.as_str() {
+                "error" => row.errors += 1,
+                "info" | HINT => row.info += 1,
+                _ => row.warnings += 1,
+            }
+        }
+    }
+    let mut rows: Vec<DirectorySummary> = rows.into_values().collect();
+    rows.sort_by(|a, b| {
+        b.issues
+            .cmp(&a.issues)
+            .then_with(|| a.directory.cmp(&b.directory))
+    });
+    rows
+}
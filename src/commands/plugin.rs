@@ -0,0 +1,473 @@
+use crate::commands::lint::{RulesetInfo, RulesetSession, RunInputs, initialize_request};
+use crate::context::GlobalContext;
+use crate::manifest::PROTOCOL_VERSION;
+use crate::paths;
+use anyhow::{Context, Result, anyhow};
+use serde_json::{Value, json};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// ANSI colors for passing and failing checks
+const GREEN: u8 = 32;
+const RED: u8 = 31;
+
+/// How long a ruleset may take to respond to a probe or exit after EOF
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Content analyzed by the happy path: mixed line endings, tabs, trailing
+/// whitespace and non-ASCII text, all of which rulesets meet in the wild
+const SAMPLE: &str = "first line\n\tindented with a tab  \r\nnon-ASCII: naïve café ✓\n\nlast line";
+
+/// How long a fuzz case may take before the ruleset counts as hung, the same
+/// limit a lint run gives an analysis
+const FUZZ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Most mutated messages sent ahead of the analyze request in one fuzz case
+const MAX_MUTATIONS: usize = 8;
+
+/// Request id of the well-formed analyze request closing each fuzz case
+const FUZZ_ANALYZE_ID: &str = "fuzz-analyze";
+
+/// One row of the conformance matrix
+struct Check {
+    name: &'static str,
+    elapsed: Duration,
+    /// What passed, or why it failed
+    outcome: Result<String>,
+}
+
+/// Run the protocol conformance suite against a ruleset binary and print a
+/// pass/fail matrix
+pub fn verify(ctx: &GlobalContext, binary: &Path) -> Result<()> {
+    if !binary.is_file() {
+        return Err(anyhow!("Ruleset binary not found: {}", binary.display()));
+    }
+    let ruleset = RulesetInfo::binary(&ruleset_id(binary), binary);
+    let config = toml::value::Table::new();
+    let start = || RulesetSession::start(&ruleset, &config, RunInputs::new(Some(0), false));
+    let sample_uri = paths::file_uri(&std::env::temp_dir().join("forseti-conformance.txt"));
+    let analyze = |mut session: RulesetSession, content: &str| -> Result<String> {
+        let analysis = session.analyze(&sample_uri, content)?;
+        session.shutdown()?;
+        if analysis.dropped > 0 {
+            return Err(anyhow!("{} malformed diagnostic(s)", analysis.dropped));
+        }
+        if let Some(error) = analysis.framing_errors.first() {
+            return Err(anyhow!("non-protocol output on stdout: {}", error));
+        }
+        Ok(format!("{} diagnostic(s)", analysis.diagnostics.len()))
+    };
+
+    println!("Checking protocol conformance of {}", binary.display());
+    let checks = [
+        run_check("initialize", || {
+            let session = start()?;
+            let version = match session.version() {
+                Some(version) => format!("version {}", version),
+                None => "no version reported".to_string(),
+            };
+            session.shutdown()?;
+            Ok(version)
+        }),
+        run_check("analyze", || analyze(start()?, SAMPLE)),
+        run_check("shutdown", || {
+            start()?.shutdown()?;
+            Ok("exited cleanly".to_string())
+        }),
+        run_check("unknown request", || {
+            let mut session = start()?;
+            let probe = json!({
+                "v": PROTOCOL_VERSION,
+                "kind": "req",
+                "type": "forsetiConformanceProbe",
+                "id": "probe",
+            });
+            session.send_line(&probe.to_string())?;
+            let answered = session.response("probe", PROBE_TIMEOUT).is_ok();
+            analyze(session, SAMPLE)?;
+            Ok(if answered {
+                "answered, still analyzes".to_string()
+            } else {
+                "ignored, still analyzes".to_string()
+            })
+        }),
+        run_check("malformed input", || {
+            let mut session = start()?;
+            session.send_line("this is not a protocol message")?;
+            session.send_line(r#"{"v":1,"kind":"req"}"#)?;
+            analyze(session, SAMPLE)?;
+            Ok("skipped, still analyzes".to_string())
+        }),
+        run_check("large file", || {
+            // Analysis has the same time limit as during a lint run
+            let content = "x".repeat(79) + "\n";
+            analyze(start()?, &content.repeat(16 * 1024))
+                .map(|detail| format!("1 MiB in time, {}", detail))
+        }),
+        run_check("stdin closed", || {
+            if start()?.exits_on_eof(PROBE_TIMEOUT)? {
+                Ok("exited without a shutdown request".to_string())
+            } else {
+                Err(anyhow!(
+                    "still running {}s after stdin closed",
+                    PROBE_TIMEOUT.as_secs()
+                ))
+            }
+        }),
+    ];
+
+    for check in &checks {
+        match &check.outcome {
+            Ok(detail) => println!(
+                "  {} {:<16} {} ({:.2}s)",
+                ctx.paint(ctx.glyph("✓", "ok"), GREEN),
+                check.name,
+                detail,
+                check.elapsed.as_secs_f64()
+            ),
+            Err(e) => println!(
+                "  {} {:<16} {:#}",
+                ctx.paint(ctx.glyph("✗", "FAIL"), RED),
+                check.name,
+                e
+            ),
+        }
+    }
+
+    let failed = checks.iter().filter(|c| c.outcome.is_err()).count();
+    if failed > 0 {
+        return Err(anyhow!(
+            "{} of {} conformance check(s) failed",
+            failed,
+            checks.len()
+        ));
+    }
+    println!("All {} conformance checks passed", checks.len());
+    Ok(())
+}
+
+fn run_check(name: &'static str, check: impl FnOnce() -> Result<String>) -> Check {
+    let started = Instant::now();
+    let outcome = check();
+    Check {
+        name,
+        elapsed: started.elapsed(),
+        outcome,
+    }
+}
+
+/// Ruleset id a binary is installed under, from `forseti_ruleset_<id>` or
+/// `forseti-ruleset-<id>`, else the file name
+fn ruleset_id(binary: &Path) -> String {
+    let name = binary
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    name.strip_prefix("forseti_ruleset_")
+        .or_else(|| name.strip_prefix("forseti-ruleset-"))
+        .unwrap_or(&name)
+        .to_string()
+}
+
+/// Feed a ruleset binary mutated protocol messages and random file contents
+/// for `seconds`, writing the input of every crash or hang to `out`
+pub fn fuzz(
+    ctx: &GlobalContext,
+    binary: &Path,
+    seconds: u64,
+    seed: Option<u64>,
+    out: &Path,
+) -> Result<()> {
+    if !binary.is_file() {
+        return Err(anyhow!("Ruleset binary not found: {}", binary.display()));
+    }
+    let id = ruleset_id(binary);
+    let ruleset = RulesetInfo::binary(&id, binary);
+    let config = toml::value::Table::new();
+    let uri = paths::file_uri(&std::env::temp_dir().join("forseti-fuzz.txt"));
+    let seed = seed.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos() as u64)
+            .unwrap_or_default()
+    });
+    // The ruleset sees the fuzz seed too, so a rerun repeats its side
+    let run = RunInputs::new(Some(seed), false);
+    let init = initialize_request(&id, &config, run).to_string();
+
+    println!(
+        "Fuzzing {} for {}s (seed {}, rerun with --seed to repeat)",
+        binary.display(),
+        seconds,
+        seed
+    );
+    let mut rng = Rng::new(seed);
+    let deadline = Instant::now() + Duration::from_secs(seconds);
+    let mut cases = 0;
+    let mut reproducers: Vec<PathBuf> = Vec::new();
+    while Instant::now() < deadline {
+        cases += 1;
+        let lines = fuzz_case(&mut rng, &uri);
+        ctx.log_verbose(&format!("Case {}: {} message(s)", cases, lines.len()));
+        let Some((kind, detail)) = run_case(&ruleset, &config, run, &lines)? else {
+            continue;
+        };
+
+        fs::create_dir_all(out).with_context(|| format!("Failed to create {}", out.display()))?;
+        let reproducer = out.join(format!("{}-{}.jsonl", kind, cases));
+        let mut input = init.clone();
+        for line in &lines {
+            input.push('\n');
+            input.push_str(line);
+        }
+        input.push('\n');
+        fs::write(&reproducer, input)
+            .with_context(|| format!("Failed to write {}", reproducer.display()))?;
+        println!(
+            "  {} case {}: {} ({})",
+            ctx.paint(ctx.glyph("✗", "FAIL"), RED),
+            cases,
+            detail,
+            reproducer.display()
+        );
+        reproducers.push(reproducer);
+    }
+
+    if !reproducers.is_empty() {
+        return Err(anyhow!(
+            "{} of {} fuzz case(s) crashed or hung the ruleset; replay one with `{} < {}`",
+            reproducers.len(),
+            cases,
+            binary.display(),
+            reproducers[0].display()
+        ));
+    }
+    println!("{} fuzz case(s) ran without a crash or hang", cases);
+    Ok(())
+}
+
+/// Send one case and classify how the ruleset handled it: `None` when it
+/// answered the closing analyze request, else `("crash" | "hang", detail)`
+fn run_case(
+    ruleset: &RulesetInfo,
+    config: &toml::value::Table,
+    run: RunInputs,
+    lines: &[String],
+) -> Result<Option<(&'static str, String)>> {
+    // Initialize isn't fuzzed, so failing it means the binary is unusable
+    let mut session = RulesetSession::start(ruleset, config, run).context(
+        "Ruleset failed to initialize; run `forseti plugin verify` on it before fuzzing",
+    )?;
+    for line in lines {
+        // A broken pipe means the process died; the exit status tells below
+        if session.send_line(line).is_err() {
+            break;
+        }
+    }
+    let answered = session.response(FUZZ_ANALYZE_ID, FUZZ_TIMEOUT).is_ok();
+    if answered {
+        session.kill();
+        return Ok(None);
+    }
+
+    // Give a process whose stdout just closed a moment to be reaped
+    let deadline = Instant::now() + Duration::from_secs(1);
+    while Instant::now() < deadline {
+        if let Some(status) = session.exit_status() {
+            return Ok(Some(("crash", format!("exited with {}", status))));
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    session.kill();
+    Ok(Some((
+        "hang",
+        format!("no response within {}s", FUZZ_TIMEOUT.as_secs()),
+    )))
+}
+
+/// Protocol lines for one case: mutated messages, then a well-formed
+/// analyze request for random content the ruleset must answer
+fn fuzz_case(rng: &mut Rng, uri: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    for n in 0..rng.below(MAX_MUTATIONS + 1) {
+        let id = format!("fuzz-{}", n);
+        let mut message = match rng.below(4) {
+            0 => json!({
+                "v": PROTOCOL_VERSION,
+                "kind": "req",
+                "type": "analyzeFile",
+                "id": id,
+                "payload": { "uri": uri, "content": random_content(rng) },
+            }),
+            1 => json!({
+                "v": PROTOCOL_VERSION,
+                "kind": "req",
+                "type": random_string(rng, 16),
+                "id": id,
+            }),
+            2 => json!({
+                "v": PROTOCOL_VERSION,
+                "kind": "event",
+                "type": "diagnostics",
+                "payload": { "diagnostics": [random_value(rng, 2)] },
+            }),
+            _ => json!({ "v": PROTOCOL_VERSION, "kind": "res", "id": id }),
+        };
+        for _ in 0..rng.below(3) {
+            mutate_value(rng, &mut message);
+        }
+        let mut line = message.to_string();
+        if rng.chance(3) {
+            line = mutate_text(rng, &line);
+        }
+        lines.push(line);
+    }
+    lines.push(
+        json!({
+            "v": PROTOCOL_VERSION,
+            "kind": "req",
+            "type": "analyzeFile",
+            "id": FUZZ_ANALYZE_ID,
+            "payload": { "uri": uri, "content": random_content(rng) },
+        })
+        .to_string(),
+    );
+    lines
+}
+
+/// Drop, replace or add one field of a message object
+fn mutate_value(rng: &mut Rng, message: &mut Value) {
+    const KEYS: &[&str] = &["v", "kind", "type", "id", "payload", "uri", "content"];
+    let Some(object) = message.as_object_mut() else {
+        return;
+    };
+    let key = rng.pick(KEYS).to_string();
+    match rng.below(3) {
+        0 => {
+            object.remove(&key);
+        }
+        1 => {
+            object.insert(key, random_value(rng, 3));
+        }
+        _ => {
+            if let Some(Value::Object(payload)) = object.get_mut("payload") {
+                payload.insert(key, random_value(rng, 3));
+            }
+        }
+    }
+}
+
+/// Break a serialized message: cut it short, splice in noise or nest it deep
+fn mutate_text(rng: &mut Rng, line: &str) -> String {
+    let boundaries: Vec<usize> = line.char_indices().map(|(i, _)| i).collect();
+    let at = boundaries
+        .get(rng.below(boundaries.len().max(1)))
+        .copied()
+        .unwrap_or(0);
+    match rng.below(4) {
+        0 => line[..at].to_string(),
+        1 => format!("{}{}{}", &line[..at], random_string(rng, 8), &line[at..]),
+        2 => format!("{}{}", line, &line[at..]),
+        _ => {
+            let depth = 1 + rng.below(10_000);
+            format!(
+                r#"{{"v":{},"kind":"req","type":"analyzeFile","id":"fuzz-deep","payload":{}{}}}"#,
+                PROTOCOL_VERSION,
+                "[".repeat(depth),
+                "]".repeat(depth)
+            )
+        }
+    }
+}
+
+/// Any JSON value, favoring the extremes parsers get wrong
+fn random_value(rng: &mut Rng, depth: usize) -> Value {
+    match rng.below(if depth == 0 { 7 } else { 9 }) {
+        0 => Value::Null,
+        1 => json!(rng.chance(2)),
+        2 => json!(*rng.pick(&[0, -1, i64::MIN, i64::MAX])),
+        3 => json!(*rng.pick(&[u64::MAX, u32::MAX as u64 + 1])),
+        4 => json!(*rng.pick(&[1e308, -0.0, 0.5])),
+        5 => json!(random_string(rng, 32)),
+        6 => json!("x".repeat(64 * 1024)),
+        7 => Value::Array(
+            (0..rng.below(4))
+                .map(|_| random_value(rng, depth - 1))
+                .collect(),
+        ),
+        _ => {
+            let mut object = serde_json::Map::new();
+            for n in 0..rng.below(4) {
+                object.insert(format!("k{}", n), random_value(rng, depth - 1));
+            }
+            Value::Object(object)
+        }
+    }
+}
+
+/// File content of the kinds rulesets mishandle: empty, huge, binary-ish,
+/// odd line endings and multi-byte text
+fn random_content(rng: &mut Rng) -> String {
+    match rng.below(6) {
+        0 => String::new(),
+        1 => random_string(rng, 4096),
+        2 => "x".repeat(1 + rng.below(1024 * 1024)),
+        3 => "\n".repeat(1 + rng.below(100_000)),
+        4 => (0..rng.below(1000))
+            .map(|_| *rng.pick(&["a", "\n", "\r\n", "\r", "\t", " "]))
+            .collect(),
+        _ => (0..rng.below(1000))
+            .map(|_| *rng.pick(&["é", "✓", "😀", "\u{301}", "\u{202e}", "\u{feff}", "\0"]))
+            .collect(),
+    }
+}
+
+/// Up to `max` characters drawn from ASCII, control characters and
+/// multi-byte code points
+fn random_string(rng: &mut Rng, max: usize) -> String {
+    (0..rng.below(max + 1))
+        .map(|_| match rng.below(4) {
+            0 => char::from(rng.below(0x20) as u8),
+            1 => char::from_u32(0x80 + rng.below(0x10000) as u32).unwrap_or('\u{fffd}'),
+            _ => char::from(b' ' + rng.below(95) as u8),
+        })
+        .collect()
+}
+
+/// Small deterministic generator (xorshift64), so a seed replays a session
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // Xorshift never leaves zero
+        Self(seed.max(1))
+    }
+
+    fn next(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+
+    /// A number in `0..n`, or 0 when `n` is 0
+    fn below(&mut self, n: usize) -> usize {
+        if n == 0 {
+            0
+        } else {
+            (self.next() % n as u64) as usize
+        }
+    }
+
+    /// True one time in `n`
+    fn chance(&mut self, n: usize) -> bool {
+        self.below(n) == 0
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+}
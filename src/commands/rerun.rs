@@ -0,0 +1,183 @@
+use crate::context::GlobalContext;
+use crate::fingerprint;
+use crate::metadata::{self, METADATA_KEY, RunMetadata};
+use anyhow::{Context, Result, anyhow};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Flags of the recorded command line that are dropped from the replay,
+/// with their short forms; each takes a value. The config is passed
+/// separately, and the replay shouldn't overwrite the reports, store or
+/// artifacts the recorded run wrote
+const DROPPED_FLAGS: &[(&str, Option<char>)] = &[
+    ("--config", Some('c')),
+    ("--output", Some('o')),
+    ("--output-file", None),
+    ("--output-mode", None),
+    ("--store", None),
+    ("--artifacts-dir", None),
+];
+
+/// Re-run the lint invocation recorded in a report, printing to stdout
+/// unless `extra` arguments ask for other output
+pub fn run(ctx: &GlobalContext, report: &Path, extra: &[String]) -> Result<()> {
+    let content = fs::read_to_string(report)
+        .with_context(|| format!("Failed to read report: {}", report.display()))?;
+    let metadata = read_metadata(&content)
+        .with_context(|| format!("No run metadata found in {}", report.display()))?;
+
+    println!(
+        "Re-running {} (forseti {}, started {})",
+        metadata.run_id, metadata.cli_version, metadata.timestamp
+    );
+    if metadata.cli_version != env!("CARGO_PKG_VERSION") {
+        eprintln!(
+            "Warning: report was produced by forseti {}, this is {}",
+            metadata.cli_version,
+            env!("CARGO_PKG_VERSION")
+        );
+    }
+    let current_commit = metadata::git_commit(&metadata.workspace_root);
+    if let Some(recorded) = &metadata.git_commit
+        && current_commit.as_ref() != Some(recorded)
+    {
+        eprintln!(
+            "Warning: report was produced at commit {}, workspace is at {}",
+            recorded,
+            current_commit.as_deref().unwrap_or("an unknown commit")
+        );
+    }
+    for (ruleset, version) in &metadata.plugin_versions {
+        ctx.log_verbose(&format!("Recorded ruleset {} {}", ruleset, version));
+    }
+
+    // Reports only carry the config's hash, so the replay uses the config on disk
+    let config_path = recorded_config_path(&metadata)?;
+    let current_hash = fs::read(&config_path)
+        .map(|bytes| fingerprint::hash_bytes(&bytes))
+        .ok();
+    if current_hash.as_deref() != Some(metadata.config_hash.as_str()) {
+        eprintln!(
+            "Warning: {} changed since the report, results may differ",
+            config_path.display()
+        );
+    }
+
+    let exe = std::env::current_exe().context("Failed to locate the forseti executable")?;
+    let mut command = Command::new(exe);
+    command
+        .arg("--config")
+        .arg(&config_path)
+        .args(strip_args(&metadata.args))
+        .args(extra)
+        .current_dir(&metadata.working_dir);
+    ctx.log_verbose(&format!("Running: {:?}", command));
+    let status = command.status().context("Failed to re-run forseti")?;
+    if !status.success() {
+        std::process::exit(status.code().unwrap_or(1));
+    }
+    Ok(())
+}
+
+/// Extract run metadata from a SARIF or JUnit report
+fn read_metadata(content: &str) -> Result<RunMetadata> {
+    if content.trim_start().starts_with('<') {
+        let marker = format!(r#"name="{}" value=""#, METADATA_KEY);
+        let start = content
+            .find(&marker)
+            .ok_or_else(|| anyhow!("missing {} property", METADATA_KEY))?
+            + marker.len();
+        let end = content[start..]
+            .find('"')
+            .ok_or_else(|| anyhow!("unterminated {} property", METADATA_KEY))?;
+        let json = xml_unescape(&content[start..start + end]);
+        return serde_json::from_str(&json).context("Malformed run metadata");
+    }
+
+    let report: Value = serde_json::from_str(content).context("Report is not JSON or XML")?;
+    let metadata = report
+        .pointer("/runs/0/properties")
+        .and_then(|properties| properties.get(METADATA_KEY))
+        .ok_or_else(|| anyhow!("missing {} property", METADATA_KEY))?;
+    serde_json::from_value(metadata.clone()).context("Malformed run metadata")
+}
+
+/// Config file the recorded run used, resolved against its working directory
+fn recorded_config_path(metadata: &RunMetadata) -> Result<PathBuf> {
+    let mut args = metadata.args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--config" || arg == "-c" {
+            if let Some(path) = args.next() {
+                return Ok(metadata.working_dir.join(path));
+            }
+        } else if let Some(path) = arg.strip_prefix("--config=") {
+            return Ok(metadata.working_dir.join(path));
+        }
+    }
+    Ok(metadata.workspace_root.join(".forseti.toml"))
+}
+
+/// The recorded arguments without [`DROPPED_FLAGS`] and their values, in
+/// any of the `--flag value`, `--flag=value`, `-f value` and `-fvalue` forms
+fn strip_args(args: &[String]) -> Vec<String> {
+    let mut stripped = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        // The flag and whether its value is part of the same argument
+        let (flag, attached) = match arg.split_once('=') {
+            Some((flag, _)) if flag.starts_with("--") => (flag, true),
+            _ if !arg.starts_with("--") && arg.starts_with('-') && arg.len() > 2 => {
+                (arg.get(..2).unwrap_or(arg), true)
+            }
+            _ => (arg.as_str(), false),
+        };
+        let dropped = DROPPED_FLAGS.iter().any(|(long, short)| {
+            flag == *long || short.is_some_and(|short| flag == format!("-{}", short))
+        });
+        if !dropped {
+            stripped.push(arg.clone());
+        } else if !attached {
+            args.next();
+        }
+    }
+    stripped
+}
+
+fn xml_unescape(s: &str) -> String {
+    s.replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn drops_output_store_and_artifact_flags() {
+        let recorded = args(
+            "-v lint src -r --config a.toml -o sarif --output-file=out.sarif --output-mode append \
+             --store runs.db --artifacts-dir=art -ojson -c=b.toml --severity warn",
+        );
+
+        assert_eq!(
+            strip_args(&recorded),
+            args("-v lint src -r --severity warn")
+        );
+    }
+
+    #[test]
+    fn keeps_flags_sharing_a_prefix() {
+        let recorded = args("lint --output-files x --storex y");
+
+        assert_eq!(strip_args(&recorded), recorded);
+    }
+}
@@ -0,0 +1,51 @@
+use crate::commands::lint::Workspace;
+use crate::context::GlobalContext;
+use anyhow::{Result, anyhow};
+use std::path::Path;
+
+/// ANSI colors for passing and failing checks
+const GREEN: u8 = 32;
+const RED: u8 = 31;
+
+/// Check that everything a lint run needs is in place, without linting
+pub fn run(ctx: &GlobalContext, path: &Path) -> Result<()> {
+    // Loading validates the config and discovers installed rulesets
+    let workspace = Workspace::load(ctx, path)?;
+    println!(
+        "{} config {}",
+        ctx.paint(ctx.glyph("✓", "ok"), GREEN),
+        workspace.config_path.display()
+    );
+
+    let checks = workspace.verify_rulesets();
+    for check in &checks {
+        let version = check.version.as_deref().unwrap_or("unknown version");
+        match &check.problem {
+            None => println!(
+                "{} {} {} ({}, {:.2}s)",
+                ctx.paint(ctx.glyph("✓", "ok"), GREEN),
+                check.ruleset_id,
+                version,
+                check.source.as_deref().unwrap_or_default(),
+                check.elapsed.as_secs_f64()
+            ),
+            Some(problem) => println!(
+                "{} {}: {}",
+                ctx.paint(ctx.glyph("✗", "FAIL"), RED),
+                check.ruleset_id,
+                problem
+            ),
+        }
+    }
+
+    let failed = checks.iter().filter(|c| c.problem.is_some()).count();
+    if failed > 0 {
+        return Err(anyhow!(
+            "{} of {} enabled ruleset(s) failed verification",
+            failed,
+            checks.len()
+        ));
+    }
+    println!("Ready to lint with {} ruleset(s)", checks.len());
+    Ok(())
+}
@@ -0,0 +1,143 @@
+use crate::context::GlobalContext;
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::ffi::OsString;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::thread;
+
+/// Flags Bazel and the docs spell the worker flag as; both are left out of
+/// the command line each request runs with
+const WORKER_FLAGS: &[&str] = &["--persistent-worker", "--persistent_worker"];
+
+/// A work request, one JSON line on stdin
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WorkRequest {
+    /// Command line after the startup arguments, e.g. `lint --output-file ...`
+    #[serde(default)]
+    arguments: Vec<String>,
+    /// Zero for singleplex workers; multiplex requests are answered by id
+    #[serde(default)]
+    request_id: i64,
+    /// Only sent to workers that declare `supports-worker-cancellation`
+    #[serde(default)]
+    cancel: bool,
+    /// Directory the request's paths are relative to when sandboxed
+    #[serde(default)]
+    sandbox_dir: Option<PathBuf>,
+}
+
+/// The answer to a work request, one JSON line on stdout
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct WorkResponse {
+    exit_code: i32,
+    /// What the run printed, stdout then stderr; Bazel shows it when the
+    /// action fails
+    output: String,
+    request_id: i64,
+}
+
+/// Serve Bazel's persistent worker protocol in its JSON form: read work
+/// requests from stdin until it closes and run each as a forseti command
+/// line, answering with its exit code and output on stdout
+///
+/// Every request runs in a child process, so its output can be captured
+/// and one failing can't take the worker down; rulesets stay warm across
+/// requests when a `forseti daemon` serves the workspace. Multiplex
+/// requests run concurrently.
+pub fn run(ctx: &GlobalContext) -> Result<()> {
+    let exe = env::current_exe().context("Failed to locate the forseti binary")?;
+    // Bazel starts the worker with the action's arguments up to its flag
+    // file, so those come before every request's own
+    let startup: Vec<OsString> = env::args_os()
+        .skip(1)
+        .filter(|arg| !WORKER_FLAGS.iter().any(|flag| arg == flag))
+        .collect();
+    let stdout = Mutex::new(io::stdout());
+    let mut stdin = io::stdin().lock();
+
+    thread::scope(|scope| -> Result<()> {
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            if stdin.read_until(b'\n', &mut line)? == 0 {
+                return Ok(());
+            }
+            if line.trim_ascii().is_empty() {
+                continue;
+            }
+            let request: WorkRequest = serde_json::from_slice(&line).map_err(|e| {
+                anyhow!(
+                    "Malformed work request ({}); forseti only speaks the JSON worker \
+                     protocol, so give the action requires-worker-protocol = \"json\"",
+                    e
+                )
+            })?;
+            if request.cancel {
+                continue;
+            }
+            let singleplex = request.request_id == 0;
+            let (exe, startup, stdout) = (&exe, &startup, &stdout);
+            let answer = move || {
+                ctx.log_verbose(&format!(
+                    "Work request {}: {}",
+                    request.request_id,
+                    request.arguments.join(" ")
+                ));
+                respond(stdout, &work(exe, startup, &request));
+            };
+            // Singleplex requests are answered in the order they came
+            if singleplex {
+                answer();
+            } else {
+                scope.spawn(answer);
+            }
+        }
+    })
+}
+
+/// Write a response as one line
+fn respond(stdout: &Mutex<io::Stdout>, response: &WorkResponse) {
+    let mut json = serde_json::to_string(response).unwrap_or_default();
+    json.push('\n');
+    let mut stdout = stdout.lock().unwrap_or_else(|e| e.into_inner());
+    // Bazel gone means nothing is left to answer
+    let _ = stdout
+        .write_all(json.as_bytes())
+        .and_then(|_| stdout.flush());
+}
+
+/// Run one request's command line and collect what it printed
+fn work(exe: &Path, startup: &[OsString], request: &WorkRequest) -> WorkResponse {
+    let mut command = Command::new(exe);
+    command
+        .args(startup)
+        .args(&request.arguments)
+        .stdin(Stdio::null());
+    if let Some(dir) = request
+        .sandbox_dir
+        .as_ref()
+        .filter(|dir| !dir.as_os_str().is_empty())
+    {
+        command.current_dir(dir);
+    }
+    let (exit_code, output) = match command.output() {
+        Ok(output) => {
+            let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+            text.push_str(&String::from_utf8_lossy(&output.stderr));
+            // Killed by a signal counts as a failure too
+            (output.status.code().unwrap_or(1), text)
+        }
+        Err(e) => (1, format!("Failed to run {}: {}\n", exe.display(), e)),
+    };
+    WorkResponse {
+        exit_code,
+        output,
+        request_id: request.request_id,
+    }
+}
@@ -0,0 +1,79 @@
+use anyhow::{Context, Result, anyhow};
+use std::env;
+use std::path::Path;
+use std::process::Command;
+
+/// Environment variable overriding the container runtime (e.g. `podman`)
+pub const RUNTIME_ENV: &str = "FORSETI_CONTAINER_RUNTIME";
+
+fn runtime() -> String {
+    env::var(RUNTIME_ENV).unwrap_or_else(|_| "docker".to_string())
+}
+
+/// Pull a ruleset image so lint runs don't pay for the download
+pub fn pull(image: &str) -> Result<()> {
+    let runtime = runtime();
+    let output = Command::new(&runtime)
+        .args(["pull", image])
+        .output()
+        .with_context(|| {
+            format!(
+                "Failed to run {} pull. Make sure {} is installed.",
+                runtime, runtime
+            )
+        })?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to pull image {}: {}",
+            image,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(())
+}
+
+/// Whether an image is already in the local image store
+pub fn is_present(image: &str) -> bool {
+    Command::new(runtime())
+        .args(["image", "inspect", image])
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+/// Content digest of a local image, which changes when a pull moves the tag
+/// to new content; `None` when the image isn't present
+pub fn image_digest(image: &str) -> Option<String> {
+    let output = Command::new(runtime())
+        .args(["image", "inspect", "--format", "{{.Id}}", image])
+        .output()
+        .ok()?;
+    let digest = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (output.status.success() && !digest.is_empty()).then_some(digest)
+}
+
+/// Build the command that runs a ruleset image speaking the stdio protocol
+///
+/// The workspace is mounted read-only at the same path inside the container so
+/// file URIs sent to the ruleset resolve identically on both sides.
+pub fn run_command(image: &str, workspace: &Path, env: &[(&str, String)]) -> Command {
+    let workspace = workspace.display().to_string();
+    let mut command = Command::new(runtime());
+    command.args([
+        "run",
+        "--rm",
+        "-i",
+        "--network",
+        "none",
+        "-v",
+        &format!("{}:{}:ro", workspace, workspace),
+        "-w",
+        &workspace,
+    ]);
+    for (name, value) in env {
+        command.arg("-e").arg(format!("{}={}", name, value));
+    }
+    command.arg(image);
+    command
+}
@@ -1,26 +1,153 @@
+use crate::i18n::Lang;
+use crate::paths;
+use anyhow::{Result, anyhow};
+use std::env;
+use std::io::IsTerminal;
 use std::path::{Path, PathBuf};
 
+/// Environment variable listing system ruleset directories (`PATH`-style)
+pub const SYSTEM_PLUGIN_DIR_ENV: &str = "FORSETI_SYSTEM_PLUGIN_DIR";
+
+/// Environment variable naming the directory rulesets are installed into
+/// instead of `~/.forseti`, for hermetic environments like Nix
+pub const HOME_ENV: &str = "FORSETI_HOME";
+
 /// Global context passed to all commands
 #[derive(Debug, Clone)]
 pub struct GlobalContext {
     /// Enable verbose output
     pub verbose: bool,
     /// Disable colorized output
-    #[allow(dead_code)]
     pub no_color: bool,
     /// Custom config path (overrides default resolution)
     pub config_path: Option<PathBuf>,
+    /// Ruleset directories given with `--system-plugin-dir`
+    pub system_plugin_dirs: Vec<PathBuf>,
+    /// Non-interactive CI mode: compact output, no writes outside the
+    /// workspace or an explicitly provided cache directory
+    pub ci: bool,
+    /// Language of summaries and messages meant for people
+    pub lang: Lang,
+    /// Accessible output: ASCII only, nothing told apart by color alone, and
+    /// plain progress lines
+    pub ascii: bool,
+    /// Directory replacing `~/.forseti`, from `--install-root` or
+    /// `FORSETI_HOME`; `None` when neither is given
+    pub install_root: Option<PathBuf>,
 }
 
 impl GlobalContext {
-    pub fn new(verbose: bool, no_color: bool, config_path: Option<PathBuf>) -> Self {
+    pub fn new(
+        verbose: bool,
+        no_color: bool,
+        config_path: Option<PathBuf>,
+        system_plugin_dirs: Vec<PathBuf>,
+        ci: bool,
+        lang: Lang,
+        ascii: bool,
+    ) -> Self {
         Self {
             verbose,
-            no_color,
+            no_color: no_color || ci,
             config_path,
+            system_plugin_dirs,
+            ci,
+            lang,
+            ascii: ascii || env::var_os("TERM").is_some_and(|term| term == "dumb"),
+            install_root: None,
         }
     }
 
+    /// Use `install_root` instead of `~/.forseti`, falling back to
+    /// `FORSETI_HOME` when it's `None`
+    pub fn with_install_root(mut self, install_root: Option<PathBuf>) -> Self {
+        self.install_root = install_root.or_else(|| {
+            env::var_os(HOME_ENV)
+                .filter(|value| !value.is_empty())
+                .map(PathBuf::from)
+        });
+        self
+    }
+
+    /// Directory `forseti install` puts rulesets in and lint looks for them:
+    /// `cache` in the install root, `~/.forseti` unless one is given
+    pub fn ruleset_cache_dir(&self) -> Result<PathBuf> {
+        let root = match &self.install_root {
+            Some(root) => root.clone(),
+            None => dirs::home_dir()
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Unable to determine home directory; pass --install-root or set {}",
+                        HOME_ENV
+                    )
+                })?
+                .join(".forseti"),
+        };
+        Ok(paths::extended(root.join("cache")))
+    }
+
+    /// In CI mode, refuse to write `path` unless it lies inside `workspace_root`
+    pub fn check_write(&self, path: &Path, workspace_root: &Path) -> Result<()> {
+        if !self.ci {
+            return Ok(());
+        }
+        // The file may not exist yet, so resolve its directory instead
+        let dir = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        let dir = paths::canonicalize(dir);
+        if dir.starts_with(workspace_root) {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "Refusing to write {} outside the workspace {} in --ci mode",
+                path.display(),
+                workspace_root.display()
+            ))
+        }
+    }
+
+    /// Directories searched for ruleset binaries after the user cache, so OS
+    /// package managers can ship rulesets alongside the CLI
+    ///
+    /// `--system-plugin-dir` wins over `FORSETI_SYSTEM_PLUGIN_DIR`, which wins
+    /// over the default locations next to the executable and under the usual
+    /// system prefixes.
+    pub fn system_plugin_dirs(&self) -> Vec<PathBuf> {
+        if !self.system_plugin_dirs.is_empty() {
+            return self.system_plugin_dirs.clone();
+        }
+        if let Some(value) = env::var_os(SYSTEM_PLUGIN_DIR_ENV) {
+            return env::split_paths(&value).collect();
+        }
+
+        let mut dirs = Vec::new();
+        // `<prefix>/bin/forseti` ships rulesets in `<prefix>/lib/forseti/rulesets`
+        // (Homebrew), or in `rulesets` next to the executable (Scoop)
+        if let Ok(exe) = env::current_exe()
+            && let Some(bin_dir) = exe.parent()
+        {
+            dirs.push(bin_dir.join("rulesets"));
+            if let Some(prefix) = bin_dir.parent() {
+                dirs.push(prefix.join("lib").join("forseti").join("rulesets"));
+            }
+        }
+        if cfg!(unix) {
+            for dir in [
+                "/usr/local/lib/forseti/rulesets",
+                "/opt/homebrew/lib/forseti/rulesets",
+                "/usr/lib/forseti/rulesets",
+            ] {
+                let dir = PathBuf::from(dir);
+                if !dirs.contains(&dir) {
+                    dirs.push(dir);
+                }
+            }
+        }
+        dirs
+    }
+
     /// Get the config path to use (either custom or default)
     pub fn resolve_config_path(&self, base_path: &Path) -> PathBuf {
         if let Some(config) = &self.config_path {
@@ -37,10 +164,31 @@ impl GlobalContext {
         }
     }
 
+    /// Wrap `text` in an ANSI color code when stdout is a color-capable terminal
+    ///
+    /// `--no-color`, a non-empty `NO_COLOR` (https://no-color.org) or
+    /// `TERM=dumb` turn colors off.
+    pub fn paint(&self, text: &str, color: u8) -> String {
+        let enabled = !self.no_color
+            && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+            && std::env::var_os("TERM").is_none_or(|term| term != "dumb")
+            && std::io::stdout().is_terminal();
+        if !enabled {
+            text.to_string()
+        } else {
+            format!("\x1b[{}m{}\x1b[0m", color, text)
+        }
+    }
+
+    /// `glyph`, or its ASCII stand-in in accessible mode
+    pub fn glyph<'a>(&self, glyph: &'a str, ascii: &'a str) -> &'a str {
+        if self.ascii { ascii } else { glyph }
+    }
+
     /// Log verbose message if verbose mode is enabled
     pub fn log_verbose(&self, message: &str) {
         if self.verbose {
             eprintln!("[VERBOSE] {}", message);
         }
     }
-}
\ No newline at end of file
+}
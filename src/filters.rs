@@ -0,0 +1,227 @@
+use crate::settings::{OutputFilter, RulesetFiles};
+use crate::suppressions;
+use anyhow::{Context, Result, anyhow};
+use globset::{Glob, GlobMatcher, GlobSet, GlobSetBuilder};
+use regex::Regex;
+
+/// Compiled `[[output.filters]]`, applied to findings before any report is
+/// written
+#[derive(Debug, Default)]
+pub struct ReportFilters {
+    filters: Vec<CompiledFilter>,
+}
+
+#[derive(Debug)]
+struct CompiledFilter {
+    rule: Option<String>,
+    severity: Option<String>,
+    path: Option<GlobMatcher>,
+    message: Option<Regex>,
+}
+
+impl ReportFilters {
+    pub fn new(filters: &[OutputFilter]) -> Result<Self> {
+        let filters = filters
+            .iter()
+            .enumerate()
+            .map(|(index, filter)| {
+                let origin = format!("filter #{}", index + 1);
+                if filter.rule.is_none()
+                    && filter.severity.is_none()
+                    && filter.path.is_none()
+                    && filter.message.is_none()
+                {
+                    return Err(anyhow!(
+                        "{} has no criteria and would mute everything",
+                        origin
+                    ));
+                }
+                let path = filter
+                    .path
+                    .as_deref()
+                    .map(|glob| {
+                        Glob::new(glob)
+                            .map(|g| g.compile_matcher())
+                            .with_context(|| format!("Invalid path glob in {}", origin))
+                    })
+                    .transpose()?;
+                let message = filter
+                    .message
+                    .as_deref()
+                    .map(|pattern| {
+                        Regex::new(pattern)
+                            .with_context(|| format!("Invalid message regex in {}", origin))
+                    })
+                    .transpose()?;
+                Ok(CompiledFilter {
+                    rule: filter.rule.clone(),
+                    severity: filter.severity.clone(),
+                    path,
+                    message,
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { filters })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    /// Whether any filter mutes a finding with this `ruleset/rule` id
+    pub fn mutes(&self, rule: &str, severity: &str, relative_path: &str, message: &str) -> bool {
+        self.filters.iter().any(|filter| {
+            filter
+                .rule
+                .as_deref()
+                .is_none_or(|selector| suppressions::rule_matches(selector, rule))
+                && filter.severity.as_deref().is_none_or(|s| s == severity)
+                && filter
+                    .path
+                    .as_ref()
+                    .is_none_or(|glob| glob.is_match(relative_path))
+                && filter
+                    .message
+                    .as_ref()
+                    .is_none_or(|regex| regex.is_match(message))
+        })
+    }
+}
+
+/// Compiled `[ruleset.<id>.files]` and `file_patterns`
+#[derive(Debug)]
+pub struct FileScope {
+    patterns: Option<GlobSet>,
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+}
+
+impl FileScope {
+    pub fn new(files: &RulesetFiles, patterns: Option<&[String]>) -> Result<Self> {
+        let patterns = patterns
+            .map(|patterns| glob_set(patterns).context("Invalid file pattern"))
+            .transpose()?;
+        let include = if files.include.is_empty() {
+            None
+        } else {
+            Some(glob_set(&files.include).context("Invalid include glob")?)
+        };
+        let exclude = glob_set(&files.exclude).context("Invalid exclude glob")?;
+        Ok(Self {
+            patterns,
+            include,
+            exclude,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_none() && self.include.is_none() && self.exclude.is_empty()
+    }
+
+    /// Whether the ruleset should see a file at this workspace-relative path
+    pub fn covers(&self, relative_path: &str) -> bool {
+        self.patterns
+            .as_ref()
+            .is_none_or(|patterns| patterns.is_match(relative_path))
+            && self
+                .include
+                .as_ref()
+                .is_none_or(|include| include.is_match(relative_path))
+            && !self.exclude.is_match(relative_path)
+    }
+}
+
+/// Globs matched against workspace-relative paths, where `*` also matches
+/// `/` so `*.py` finds Python files in every directory
+pub fn glob_set(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern).with_context(|| format!("'{}'", pattern))?);
+    }
+    Ok(builder.build()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(rule: Option<&str>, path: Option<&str>, message: Option<&str>) -> OutputFilter {
+        OutputFilter {
+            rule: rule.map(str::to_string),
+            severity: None,
+            path: path.map(str::to_string),
+            message: message.map(str::to_string),
+        }
+    }
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|item| item.to_string()).collect()
+    }
+
+    #[test]
+    fn every_given_criterion_must_match() {
+        let filters = ReportFilters::new(&[filter(
+            Some("base/max-line-length"),
+            Some("generated/**"),
+            Some("exceeds \\d+ characters"),
+        )])
+        .unwrap();
+
+        let rule = "base/max-line-length";
+        let message = "line exceeds 120 characters";
+        assert!(filters.mutes(rule, "warn", "generated/api/client.rs", message));
+        assert!(!filters.mutes(rule, "warn", "src/main.rs", message));
+        assert!(!filters.mutes("base/todo", "warn", "generated/api/client.rs", message));
+        assert!(!filters.mutes(rule, "warn", "generated/api/client.rs", "too long"));
+    }
+
+    #[test]
+    fn any_filter_mutes() {
+        let info = OutputFilter {
+            severity: Some("info".to_string()),
+            ..OutputFilter::default()
+        };
+        let filters = ReportFilters::new(&[filter(Some("style/*"), None, None), info]).unwrap();
+
+        assert!(filters.mutes("style/indent", "error", "a.rs", ""));
+        assert!(filters.mutes("base/todo", "info", "a.rs", ""));
+        assert!(!filters.mutes("base/todo", "warn", "a.rs", ""));
+    }
+
+    #[test]
+    fn rejects_empty_and_invalid_filters() {
+        assert!(ReportFilters::new(&[OutputFilter::default()]).is_err());
+        assert!(ReportFilters::new(&[filter(None, None, Some("("))]).is_err());
+        assert!(ReportFilters::new(&[filter(None, Some("a/{b"), None)]).is_err());
+        assert!(ReportFilters::new(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn file_scope_includes_and_excludes() {
+        let files = RulesetFiles {
+            include: strings(&["infra/**"]),
+            exclude: strings(&["**/fixtures/**"]),
+        };
+        let scope = FileScope::new(&files, None).unwrap();
+
+        assert!(scope.covers("infra/main.tf"));
+        assert!(!scope.covers("infra/fixtures/bad.tf"));
+        assert!(!scope.covers("src/main.rs"));
+    }
+
+    #[test]
+    fn file_patterns_match_in_every_directory() {
+        let patterns = strings(&["*.tf", "*.tfvars"]);
+        let scope = FileScope::new(&RulesetFiles::default(), Some(&patterns)).unwrap();
+
+        assert!(!scope.is_empty());
+        assert!(scope.covers("main.tf"));
+        assert!(scope.covers("modules/vpc/vars.tfvars"));
+        assert!(!scope.covers("README.md"));
+        assert!(
+            FileScope::new(&RulesetFiles::default(), None)
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,117 @@
+/// Version tag included in reports next to each fingerprint, bumped whenever
+/// the algorithm changes so consumers know old fingerprints won't match
+pub const FINGERPRINT_VERSION: &str = "forseti/v1";
+
+/// Stable identity of a finding that survives unrelated edits shifting it to
+/// another line
+///
+/// The hash covers the ruleset, rule, workspace-relative path and the
+/// whitespace-normalized source line the diagnostic starts on. `occurrence`
+/// disambiguates identical findings on identical lines within one file.
+pub fn compute(
+    ruleset_id: &str,
+    rule_id: &str,
+    path: &str,
+    context: &str,
+    occurrence: usize,
+) -> String {
+    let mut hash = Fnv1a::new();
+    for part in [ruleset_id, rule_id, path, &normalize_context(context)] {
+        hash.write(part.as_bytes());
+        // Separator so ("ab", "c") and ("a", "bc") differ
+        hash.write(&[0]);
+    }
+    hash.write(&(occurrence as u64).to_le_bytes());
+    format!("{:016x}", hash.finish())
+}
+
+/// Stable hex digest of arbitrary content, e.g. a config file
+pub fn hash_bytes(bytes: &[u8]) -> String {
+    let mut hash = Fnv1a::new();
+    hash.write(bytes);
+    format!("{:016x}", hash.finish())
+}
+
+/// Stable position of a path in a `--sample` draw with `seed`
+pub fn sample_key(seed: u64, path: &str) -> u64 {
+    let mut hash = Fnv1a::new();
+    hash.write(&seed.to_le_bytes());
+    hash.write(path.as_bytes());
+    // FNV leaves similar paths close together; mixing spreads them out
+    let mut key = hash.finish();
+    key ^= key >> 33;
+    key = key.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    key ^= key >> 33;
+    key
+}
+
+/// Collapse whitespace so re-indentation doesn't change fingerprints
+pub fn normalize_context(line: &str) -> String {
+    line.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// 64-bit FNV-1a, chosen because its output is fixed forever unlike std's hasher
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    fn new() -> Self {
+        Self(Self::OFFSET_BASIS)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fnv1a_matches_reference_vectors() {
+        assert_eq!(hash_bytes(b""), "cbf29ce484222325");
+        assert_eq!(hash_bytes(b"a"), "af63dc4c8601ec8c");
+        assert_eq!(hash_bytes(b"foobar"), "85944171f73967e8");
+    }
+
+    #[test]
+    fn reindenting_keeps_the_fingerprint() {
+        assert_eq!(
+            compute("base", "todo", "src/a.rs", "    // TODO  fix", 0),
+            compute("base", "todo", "src/a.rs", "\t// TODO fix", 0)
+        );
+    }
+
+    #[test]
+    fn every_part_changes_the_fingerprint() {
+        let base = compute("base", "todo", "src/a.rs", "TODO", 0);
+
+        assert_ne!(base, compute("other", "todo", "src/a.rs", "TODO", 0));
+        assert_ne!(base, compute("base", "fixme", "src/a.rs", "TODO", 0));
+        assert_ne!(base, compute("base", "todo", "src/b.rs", "TODO", 0));
+        assert_ne!(base, compute("base", "todo", "src/a.rs", "TODO later", 0));
+        assert_ne!(base, compute("base", "todo", "src/a.rs", "TODO", 1));
+        // Parts are separated, so shifting text between them is a change too
+        assert_ne!(
+            compute("ab", "c", "p", "x", 0),
+            compute("a", "bc", "p", "x", 0)
+        );
+    }
+
+    #[test]
+    fn sample_keys_depend_on_seed_and_path() {
+        assert_eq!(sample_key(7, "src/a.rs"), sample_key(7, "src/a.rs"));
+        assert_ne!(sample_key(7, "src/a.rs"), sample_key(8, "src/a.rs"));
+        assert_ne!(sample_key(7, "src/a.rs"), sample_key(7, "src/b.rs"));
+    }
+}
@@ -0,0 +1,496 @@
+use crate::paths;
+use anyhow::{Context, Result, anyhow};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Bases tried in order when `--changed` names none: the upstream main
+/// branch, so CI sees everything a branch changed, else the last commit
+const DEFAULT_BASES: &[&str] = &["origin/main", "HEAD"];
+
+/// Run git in `dir` and return what it printed
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = git_bytes(dir, args)?;
+    Ok(String::from_utf8_lossy(&output).into_owned())
+}
+
+/// Run git in `dir` and return what it printed, byte for byte
+fn git_bytes(dir: &Path, args: &[&str]) -> Result<Vec<u8>> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .context("Failed to run git. Make sure git is installed.")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(output.stdout)
+}
+
+/// Root of the git work tree containing `dir`, or `dir` itself in a bare
+/// repository, like the one a server runs its pre-receive hook in
+pub fn toplevel(dir: &Path) -> Result<PathBuf> {
+    let bare = git(dir, &["rev-parse", "--is-bare-repository"])
+        .with_context(|| format!("{} is not inside a git repository", dir.display()))?;
+    if bare.trim() == "true" {
+        return Ok(paths::canonicalize(dir));
+    }
+    let root = git(dir, &["rev-parse", "--show-toplevel"])
+        .with_context(|| format!("{} is not inside a git repository", dir.display()))?;
+    Ok(paths::canonicalize(Path::new(root.trim())))
+}
+
+/// The ref changes are measured against, and the commit where `head` forked
+/// from it, so a branch's changes leave out what landed on the base since
+pub fn merge_base(toplevel: &Path, base: Option<&str>, head: &str) -> Result<(String, String)> {
+    let base = match base {
+        Some(base) => base.to_string(),
+        None => DEFAULT_BASES
+            .iter()
+            .find(|base| {
+                let commit = format!("{}^{{commit}}", base);
+                git(toplevel, &["rev-parse", "--verify", "--quiet", &commit]).is_ok()
+            })
+            .ok_or_else(|| anyhow!("No commit to compare against; pass --changed=<base>"))?
+            .to_string(),
+    };
+    let commit = git(toplevel, &["merge-base", head, &base])
+        .with_context(|| format!("Failed to find where {} forked from {}", head, base))?;
+    Ok((base, commit.trim().to_string()))
+}
+
+/// Files added, copied, modified or renamed since `commit`, committed or
+/// not, plus untracked files that aren't ignored, as absolute paths
+pub fn changed_files(toplevel: &Path, commit: &str) -> Result<Vec<PathBuf>> {
+    let changed = git(
+        toplevel,
+        &["diff", "--name-only", "-z", "--diff-filter=ACMR", commit],
+    )?;
+    let untracked = git(
+        toplevel,
+        &["ls-files", "-z", "--others", "--exclude-standard"],
+    )?;
+    let files: BTreeSet<PathBuf> = changed
+        .split('\0')
+        .chain(untracked.split('\0'))
+        .filter(|path| !path.is_empty())
+        .map(|path| toplevel.join(path))
+        .collect();
+    Ok(files.into_iter().collect())
+}
+
+/// A file whose content git holds as a blob, in the index or in a tree
+pub struct BlobFile {
+    /// Absolute path in the work tree, where the file may not exist
+    pub path: PathBuf,
+    /// Id of the blob
+    pub blob: String,
+}
+
+/// Files added, copied, modified or renamed in the index, leaving out
+/// symlinks and submodules, whose blobs aren't file content
+pub fn staged_files(toplevel: &Path) -> Result<Vec<BlobFile>> {
+    let changed = git(
+        toplevel,
+        &[
+            "diff",
+            "--cached",
+            "--name-only",
+            "-z",
+            "--diff-filter=ACMR",
+        ],
+    )?;
+    let changed: BTreeSet<&str> = changed
+        .split('\0')
+        .filter(|path| !path.is_empty())
+        .collect();
+    let index = git(toplevel, &["ls-files", "--stage", "-z"])?;
+    let mut files = Vec::new();
+    // Entries look like `100644 <blob> 0\t<path>`
+    for entry in index.split('\0') {
+        let Some((info, path)) = entry.split_once('\t') else {
+            continue;
+        };
+        let mut info = info.split(' ');
+        let (Some(mode), Some(blob), Some("0")) = (info.next(), info.next(), info.next()) else {
+            continue;
+        };
+        if mode.starts_with("100") && changed.contains(path) {
+            files.push(BlobFile {
+                path: toplevel.join(path),
+                blob: blob.to_string(),
+            });
+        }
+    }
+    Ok(files)
+}
+
+/// Every file of the tree `rev` names (a commit, a tag or a tree), leaving
+/// out symlinks and submodules like [`staged_files`]
+pub fn tree_files(toplevel: &Path, rev: &str) -> Result<Vec<BlobFile>> {
+    let tree = format!("{}^{{tree}}", rev);
+    git(toplevel, &["rev-parse", "--verify", "--quiet", &tree])
+        .map_err(|_| anyhow!("{} does not name a git tree", rev))?;
+    let listing = git(toplevel, &["ls-tree", "-r", "-z", "--full-tree", &tree])?;
+    let mut files = Vec::new();
+    // Entries look like `100644 blob <blob>\t<path>`
+    for entry in listing.split('\0') {
+        let Some((info, path)) = entry.split_once('\t') else {
+            continue;
+        };
+        let mut info = info.split(' ');
+        let (Some(mode), Some("blob"), Some(blob)) = (info.next(), info.next(), info.next()) else {
+            continue;
+        };
+        if mode.starts_with("100") {
+            files.push(BlobFile {
+                path: toplevel.join(path),
+                blob: blob.to_string(),
+            });
+        }
+    }
+    Ok(files)
+}
+
+/// Size of a blob in bytes, without reading it
+pub fn blob_size(toplevel: &Path, blob: &str) -> Result<u64> {
+    let size = git(toplevel, &["cat-file", "-s", blob])?;
+    size.trim()
+        .parse()
+        .with_context(|| format!("git cat-file -s printed {:?}", size.trim()))
+}
+
+/// Content of a blob
+pub fn read_blob(toplevel: &Path, blob: &str) -> Result<Vec<u8>> {
+    git_bytes(toplevel, &["cat-file", "blob", blob])
+}
+
+/// Lines of a file added or modified since a commit
+pub enum FileChanges {
+    /// A file git doesn't track yet, new in its entirety
+    Whole,
+    /// One-based, inclusive line ranges
+    Lines(Vec<(u32, u32)>),
+}
+
+impl FileChanges {
+    /// Whether any of the one-based lines `first..=last` changed
+    pub fn touches(&self, first: u32, last: u32) -> bool {
+        match self {
+            FileChanges::Whole => true,
+            FileChanges::Lines(ranges) => ranges
+                .iter()
+                .any(|(start, end)| *start <= last && first <= *end),
+        }
+    }
+}
+
+/// What a commit is compared with to find changed lines
+#[derive(Clone, Copy)]
+pub enum DiffTarget<'a> {
+    /// The work tree, untracked files included
+    WorkTree,
+    /// The index
+    Staged,
+    /// A tree, as named by a commit, tag or tree id
+    Tree(&'a str),
+}
+
+/// Changed lines of every file changed since a commit, committed or not,
+/// only staged, or in another tree
+pub struct ChangedLines {
+    /// Keyed by canonical path
+    files: HashMap<PathBuf, FileChanges>,
+}
+
+impl ChangedLines {
+    /// Lines changed between `commit` and `target`
+    pub fn since(toplevel: &Path, commit: &str, target: DiffTarget) -> Result<Self> {
+        // Fixed prefixes, whatever diff.noprefix or diff.mnemonicPrefix say
+        let mut args = vec![
+            "diff",
+            "-U0",
+            "--no-color",
+            "--no-ext-diff",
+            "--src-prefix=a/",
+            "--dst-prefix=b/",
+            "--diff-filter=ACMR",
+        ];
+        if let DiffTarget::Staged = target {
+            args.push("--cached");
+        }
+        args.push(commit);
+        if let DiffTarget::Tree(rev) = target {
+            args.push(rev);
+        }
+        let diff = git(toplevel, &args)?;
+        let mut files = HashMap::new();
+        let mut current: Option<PathBuf> = None;
+        // Added lines start with `+` too, so `+++` only names a file before
+        // its first hunk
+        let mut in_header = false;
+        for line in diff.lines() {
+            if line.starts_with("diff --git ") {
+                in_header = true;
+                current = None;
+            } else if in_header && let Some(path) = line.strip_prefix("+++ ") {
+                // Names with spaces end in a tab, like GNU diff writes them
+                let path = path.strip_suffix('\t').unwrap_or(path);
+                current = unquote(path)
+                    .strip_prefix("b/")
+                    .map(|path| paths::canonicalize_missing(&toplevel.join(path)));
+            } else if let Some(header) = line.strip_prefix("@@ ") {
+                in_header = false;
+                let (Some(path), Some(range)) = (&current, added_range(header)) else {
+                    continue;
+                };
+                let changes = files
+                    .entry(path.clone())
+                    .or_insert_with(|| FileChanges::Lines(Vec::new()));
+                if let FileChanges::Lines(ranges) = changes {
+                    ranges.push(range);
+                }
+            }
+        }
+        // Untracked files are only in the work tree
+        let untracked = match target {
+            DiffTarget::WorkTree => git(
+                toplevel,
+                &["ls-files", "-z", "--others", "--exclude-standard"],
+            )?,
+            DiffTarget::Staged | DiffTarget::Tree(_) => String::new(),
+        };
+        for path in untracked.split('\0').filter(|path| !path.is_empty()) {
+            files.insert(
+                paths::canonicalize(&toplevel.join(path)),
+                FileChanges::Whole,
+            );
+        }
+        Ok(Self { files })
+    }
+
+    /// Changes to the file at `path`; `None` when it didn't change
+    pub fn file(&self, path: &Path) -> Option<&FileChanges> {
+        self.files.get(&paths::canonicalize_missing(path))
+    }
+}
+
+/// A path from a diff header, which git C-quotes when it has characters
+/// `core.quotePath` escapes, e.g. `"b/caf\303\251.txt"` for `b/café.txt`
+fn unquote(path: &str) -> String {
+    let Some(quoted) = path
+        .strip_prefix('"')
+        .and_then(|path| path.strip_suffix('"'))
+    else {
+        return path.to_string();
+    };
+    let mut bytes = Vec::with_capacity(quoted.len());
+    let mut rest = quoted.bytes().peekable();
+    while let Some(byte) = rest.next() {
+        if byte != b'\\' {
+            bytes.push(byte);
+            continue;
+        }
+        match rest.next() {
+            Some(b'a') => bytes.push(0x07),
+            Some(b'b') => bytes.push(0x08),
+            Some(b't') => bytes.push(b'\t'),
+            Some(b'n') => bytes.push(b'\n'),
+            Some(b'v') => bytes.push(0x0b),
+            Some(b'f') => bytes.push(0x0c),
+            Some(b'r') => bytes.push(b'\r'),
+            // `\NNN`: one byte in octal, e.g. of a multi-byte character
+            Some(digit @ b'0'..=b'7') => {
+                let mut value = u32::from(digit - b'0');
+                for _ in 0..2 {
+                    if let Some(digit) = rest.next_if(|next| (b'0'..=b'7').contains(next)) {
+                        value = value * 8 + u32::from(digit - b'0');
+                    }
+                }
+                bytes.push(value as u8);
+            }
+            // `\"` and `\\`
+            Some(other) => bytes.push(other),
+            None => {}
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Added lines of a hunk header like `-12,3 +14,5 @@`; `None` for a hunk
+/// that only removes lines
+fn added_range(header: &str) -> Option<(u32, u32)> {
+    let added = header.split(' ').find_map(|part| part.strip_prefix('+'))?;
+    let (start, count) = match added.split_once(',') {
+        Some((start, count)) => (start.parse::<u32>().ok()?, count.parse::<u32>().ok()?),
+        None => (added.parse::<u32>().ok()?, 1),
+    };
+    (count > 0).then(|| (start, start + count - 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, fs};
+
+    /// A repository with `a.txt` and `c.txt` committed and `*.log` ignored
+    fn repo(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("forseti-git-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        git(&dir, &["init", "-q"]).unwrap();
+        git(&dir, &["config", "user.name", "Forseti"]).unwrap();
+        git(&dir, &["config", "user.email", "forseti@example.com"]).unwrap();
+        git(&dir, &["config", "commit.gpgsign", "false"]).unwrap();
+        fs::write(dir.join("a.txt"), "one\ntwo\nthree\n").unwrap();
+        fs::write(dir.join("c.txt"), "unchanged\n").unwrap();
+        fs::write(dir.join(".gitignore"), "*.log\n").unwrap();
+        git(&dir, &["add", "."]).unwrap();
+        git(&dir, &["commit", "-q", "-m", "initial"]).unwrap();
+        paths::canonicalize(&dir)
+    }
+
+    #[test]
+    fn toplevel_from_a_subdirectory() {
+        let dir = repo("toplevel");
+
+        assert_eq!(toplevel(&dir.join("sub")).unwrap(), dir);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn changed_files_include_untracked_but_not_ignored_or_deleted() {
+        let dir = repo("changed");
+        fs::write(dir.join("a.txt"), "one\n2\nthree\n").unwrap();
+        fs::write(dir.join("b.txt"), "new\n").unwrap();
+        fs::write(dir.join("debug.log"), "noise\n").unwrap();
+        fs::remove_file(dir.join("c.txt")).unwrap();
+        // Without an origin/main to compare with, changes are since HEAD
+        let (base, commit) = merge_base(&dir, None, "HEAD").unwrap();
+
+        assert_eq!(base, "HEAD");
+        assert_eq!(
+            changed_files(&dir, &commit).unwrap(),
+            [dir.join("a.txt"), dir.join("b.txt")]
+        );
+        assert!(merge_base(&dir, Some("no-such-branch"), "HEAD").is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn hunk_headers_give_added_lines() {
+        assert_eq!(added_range("-12,3 +14,5 @@ fn main() {"), Some((14, 18)));
+        assert_eq!(added_range("-3 +3 @@"), Some((3, 3)));
+        // Only removes lines
+        assert_eq!(added_range("-5,2 +4,0 @@"), None);
+    }
+
+    #[test]
+    fn changed_lines_since_a_commit() {
+        let dir = repo("lines");
+        fs::write(dir.join("a.txt"), "zero\none\n2\nthree\n").unwrap();
+        fs::write(dir.join("b.txt"), "new\n").unwrap();
+        let changes = ChangedLines::since(&dir, "HEAD", DiffTarget::WorkTree).unwrap();
+
+        let a = changes.file(&dir.join("a.txt")).unwrap();
+        assert!(a.touches(1, 1));
+        assert!(!a.touches(2, 2));
+        assert!(a.touches(2, 3));
+        assert!(!a.touches(4, 4));
+        assert!(matches!(
+            changes.file(&dir.join("b.txt")),
+            Some(FileChanges::Whole)
+        ));
+        assert!(changes.file(&dir.join("c.txt")).is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn staged_files_are_the_index_content_of_staged_changes() {
+        let dir = repo("staged");
+        fs::write(dir.join("a.txt"), "one\nstaged\nthree\n").unwrap();
+        git(&dir, &["add", "a.txt"]).unwrap();
+        fs::write(dir.join("a.txt"), "one\nnot staged\nthree\n").unwrap();
+        fs::write(dir.join("c.txt"), "only in the work tree\n").unwrap();
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink("a.txt", dir.join("link")).unwrap();
+            git(&dir, &["add", "link"]).unwrap();
+        }
+
+        let staged = staged_files(&dir).unwrap();
+        assert_eq!(staged.len(), 1);
+        assert_eq!(staged[0].path, dir.join("a.txt"));
+        assert_eq!(
+            read_blob(&dir, &staged[0].blob).unwrap(),
+            b"one\nstaged\nthree\n"
+        );
+        let changes = ChangedLines::since(&dir, "HEAD", DiffTarget::Staged).unwrap();
+        assert!(changes.file(&dir.join("a.txt")).unwrap().touches(2, 2));
+        assert!(changes.file(&dir.join("c.txt")).is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn tree_files_list_a_commit_without_its_work_tree() {
+        let dir = repo("tree");
+        fs::write(dir.join("a.txt"), "edited\n").unwrap();
+        fs::write(dir.join("b.txt"), "untracked\n").unwrap();
+
+        let files = tree_files(&dir, "HEAD").unwrap();
+        let paths: Vec<&PathBuf> = files.iter().map(|file| &file.path).collect();
+        assert_eq!(
+            paths,
+            [
+                &dir.join(".gitignore"),
+                &dir.join("a.txt"),
+                &dir.join("c.txt")
+            ]
+        );
+        assert_eq!(
+            read_blob(&dir, &files[1].blob).unwrap(),
+            b"one\ntwo\nthree\n"
+        );
+        assert!(tree_files(&dir, "no-such-ref").is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn unquotes_c_style_paths() {
+        assert_eq!(unquote("b/plain.txt"), "b/plain.txt");
+        assert_eq!(unquote("\"b/caf\\303\\251.txt\""), "b/café.txt");
+        assert_eq!(
+            unquote("\"b/say \\\"hi\\\"\\tback\\\\.txt\""),
+            "b/say \"hi\"\tback\\.txt"
+        );
+    }
+
+    #[test]
+    fn changed_lines_of_files_with_quoted_names() {
+        let dir = repo("quoted");
+        let names: &[&str] = if cfg!(unix) {
+            &["café.txt", "two words.txt", "say \"hi\".txt"]
+        } else {
+            &["café.txt", "two words.txt"]
+        };
+        for name in names {
+            fs::write(dir.join(name), "one\n").unwrap();
+        }
+        git(&dir, &["add", "."]).unwrap();
+        git(&dir, &["commit", "-q", "-m", "quoted"]).unwrap();
+        for name in names {
+            fs::write(dir.join(name), "one\ntwo\n").unwrap();
+        }
+        let changes = ChangedLines::since(&dir, "HEAD", DiffTarget::WorkTree).unwrap();
+
+        for name in names {
+            let file = changes.file(&dir.join(name));
+            assert!(file.is_some_and(|file| file.touches(2, 2)), "{}", name);
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,383 @@
+//! Translations of what people read: the text summary, the notes on stderr
+//! about diagnostics left out of a report and the missing-config error.
+//! Other warnings and errors are in English, as is machine-readable output
+//! (JSON, SARIF, the final status line, ...) so scripts can parse it.
+
+use anyhow::{Result, anyhow};
+use std::env;
+use std::fmt::Display;
+
+/// Environment variable picking the language when `--lang` isn't given
+pub const LANG_ENV: &str = "FORSETI_LANG";
+
+/// A language of the message catalog
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Lang {
+    #[default]
+    En,
+    De,
+    Es,
+    Fr,
+}
+
+/// Languages by tag, as accepted by `--lang`
+const LANGS: &[(&str, Lang)] = &[
+    ("en", Lang::En),
+    ("de", Lang::De),
+    ("es", Lang::Es),
+    ("fr", Lang::Fr),
+];
+
+/// A message of the catalog; `{}` placeholders are filled in order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Msg {
+    Summary,
+    FilesDiscovered,
+    FilesAnalyzed,
+    FilesSkipped,
+    FilesWithIssues,
+    TotalIssues,
+    Errors,
+    Warnings,
+    Info,
+    LintScore,
+    NoIssues,
+    Truncated,
+    MoreFrom,
+    Directory,
+    Files,
+    Issues,
+    Ruleset,
+    Time,
+    NoConfig,
+    MoreDiagnostics,
+    OutsideDiff,
+    BelowSeverity,
+    Progress,
+}
+
+impl Msg {
+    fn english(self) -> &'static str {
+        match self {
+            Msg::Summary => "Summary:",
+            Msg::FilesDiscovered => "Files discovered",
+            Msg::FilesAnalyzed => "Files analyzed",
+            Msg::FilesSkipped => "Files skipped",
+            Msg::FilesWithIssues => "Files with issues",
+            Msg::TotalIssues => "Total issues",
+            Msg::Errors => "Errors",
+            Msg::Warnings => "Warnings",
+            Msg::Info => "Info",
+            Msg::LintScore => "Lint score",
+            Msg::NoIssues => "No issues found in {} file(s)",
+            Msg::Truncated => "Report truncated: {} more diagnostic(s) omitted",
+            Msg::MoreFrom => "... and {} more diagnostic(s) from {}",
+            Msg::Directory => "Directory",
+            Msg::Files => "Files",
+            Msg::Issues => "Issues",
+            Msg::Ruleset => "Ruleset",
+            Msg::Time => "Time",
+            Msg::NoConfig => "No .forseti.toml found at {}. Run 'forseti init' first.",
+            Msg::MoreDiagnostics => "... and {} more diagnostic(s)",
+            Msg::OutsideDiff => "Ignored {} diagnostic(s) outside the changed lines",
+            Msg::BelowSeverity => "Hidden {} diagnostic(s) below --severity {}",
+            Msg::Progress => "Linted {} of {} file(s)",
+        }
+    }
+}
+
+/// Catalogs may leave messages out; those are shown in English
+const DE: &[(Msg, &str)] = &[
+    (Msg::Summary, "Zusammenfassung:"),
+    (Msg::FilesDiscovered, "Gefundene Dateien"),
+    (Msg::FilesAnalyzed, "Analysierte Dateien"),
+    (Msg::FilesSkipped, "Übersprungene Dateien"),
+    (Msg::FilesWithIssues, "Dateien mit Problemen"),
+    (Msg::TotalIssues, "Probleme insgesamt"),
+    (Msg::Errors, "Fehler"),
+    (Msg::Warnings, "Warnungen"),
+    (Msg::Info, "Hinweise"),
+    (Msg::LintScore, "Lint-Bewertung"),
+    (Msg::NoIssues, "Keine Probleme in {} Datei(en) gefunden"),
+    (
+        Msg::Truncated,
+        "Bericht gekürzt: {} weitere Diagnose(n) ausgelassen",
+    ),
+    (Msg::MoreFrom, "... und {} weitere Diagnose(n) aus {}"),
+    (Msg::Directory, "Verzeichnis"),
+    (Msg::Files, "Dateien"),
+    (Msg::Issues, "Probleme"),
+    (Msg::Ruleset, "Regelsatz"),
+    (Msg::Time, "Zeit"),
+    (
+        Msg::NoConfig,
+        "Keine .forseti.toml unter {} gefunden. Führen Sie zuerst 'forseti init' aus.",
+    ),
+    (Msg::MoreDiagnostics, "... und {} weitere Diagnose(n)"),
+    (
+        Msg::OutsideDiff,
+        "{} Diagnose(n) außerhalb der geänderten Zeilen ignoriert",
+    ),
+    (
+        Msg::BelowSeverity,
+        "{} Diagnose(n) unter --severity {} ausgeblendet",
+    ),
+    (Msg::Progress, "{} von {} Datei(en) geprüft"),
+];
+
+const ES: &[(Msg, &str)] = &[
+    (Msg::Summary, "Resumen:"),
+    (Msg::FilesDiscovered, "Archivos encontrados"),
+    (Msg::FilesAnalyzed, "Archivos analizados"),
+    (Msg::FilesSkipped, "Archivos omitidos"),
+    (Msg::FilesWithIssues, "Archivos con problemas"),
+    (Msg::TotalIssues, "Problemas en total"),
+    (Msg::Errors, "Errores"),
+    (Msg::Warnings, "Advertencias"),
+    (Msg::Info, "Info"),
+    (Msg::LintScore, "Puntuación de lint"),
+    (
+        Msg::NoIssues,
+        "No se encontraron problemas en {} archivo(s)",
+    ),
+    (
+        Msg::Truncated,
+        "Informe truncado: se omitieron {} diagnóstico(s) más",
+    ),
+    (Msg::MoreFrom, "... y {} diagnóstico(s) más de {}"),
+    (Msg::Directory, "Directorio"),
+    (Msg::Files, "Archivos"),
+    (Msg::Issues, "Problemas"),
+    (Msg::Ruleset, "Conjunto de reglas"),
+    (Msg::Time, "Tiempo"),
+    (
+        Msg::NoConfig,
+        "No se encontró .forseti.toml en {}. Ejecute primero 'forseti init'.",
+    ),
+    (Msg::MoreDiagnostics, "... y {} diagnóstico(s) más"),
+    (
+        Msg::OutsideDiff,
+        "Se ignoraron {} diagnóstico(s) fuera de las líneas modificadas",
+    ),
+    (
+        Msg::BelowSeverity,
+        "Se ocultaron {} diagnóstico(s) por debajo de --severity {}",
+    ),
+    (Msg::Progress, "Analizados {} de {} archivo(s)"),
+];
+
+const FR: &[(Msg, &str)] = &[
+    (Msg::Summary, "Résumé :"),
+    (Msg::FilesDiscovered, "Fichiers trouvés"),
+    (Msg::FilesAnalyzed, "Fichiers analysés"),
+    (Msg::FilesSkipped, "Fichiers ignorés"),
+    (Msg::FilesWithIssues, "Fichiers avec problèmes"),
+    (Msg::TotalIssues, "Problèmes au total"),
+    (Msg::Errors, "Erreurs"),
+    (Msg::Warnings, "Avertissements"),
+    (Msg::Info, "Infos"),
+    (Msg::LintScore, "Score de lint"),
+    (Msg::NoIssues, "Aucun problème trouvé dans {} fichier(s)"),
+    (
+        Msg::Truncated,
+        "Rapport tronqué : {} diagnostic(s) supplémentaire(s) omis",
+    ),
+    (
+        Msg::MoreFrom,
+        "... et {} diagnostic(s) de plus provenant de {}",
+    ),
+    (Msg::Directory, "Répertoire"),
+    (Msg::Files, "Fichiers"),
+    (Msg::Issues, "Problèmes"),
+    (Msg::Ruleset, "Jeu de règles"),
+    (Msg::Time, "Durée"),
+    (
+        Msg::NoConfig,
+        "Aucun .forseti.toml trouvé dans {}. Exécutez d'abord 'forseti init'.",
+    ),
+    (Msg::MoreDiagnostics, "... et {} diagnostic(s) de plus"),
+    (
+        Msg::OutsideDiff,
+        "{} diagnostic(s) hors des lignes modifiées ignoré(s)",
+    ),
+    (
+        Msg::BelowSeverity,
+        "{} diagnostic(s) sous --severity {} masqué(s)",
+    ),
+    (Msg::Progress, "{} sur {} fichier(s) analysé(s)"),
+];
+
+impl Lang {
+    /// `--lang` when given, else `FORSETI_LANG`, else the locale
+    /// (`LC_ALL`, `LC_MESSAGES`, `LANG`), falling back to English
+    ///
+    /// Only an explicit `--lang` naming an unknown language is an error;
+    /// locales without a catalog just mean English.
+    pub fn resolve(flag: Option<&str>) -> Result<Self> {
+        if let Some(tag) = flag {
+            return Self::parse(tag).ok_or_else(|| {
+                let known: Vec<&str> = LANGS.iter().map(|(tag, _)| *tag).collect();
+                anyhow!(
+                    "Unsupported language '{}'; expected one of: {}",
+                    tag,
+                    known.join(", ")
+                )
+            });
+        }
+        let lang = [LANG_ENV, "LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| Self::parse(&value));
+        Ok(lang.unwrap_or_default())
+    }
+
+    /// Language of a tag like `de`, `de-AT` or `de_DE.UTF-8`
+    fn parse(tag: &str) -> Option<Self> {
+        let language = tag
+            .split(['_', '-', '.', '@'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        LANGS
+            .iter()
+            .find(|(known, _)| *known == language)
+            .map(|(_, lang)| *lang)
+    }
+
+    /// The message in this language, or in English when it has no translation
+    pub fn text(self, msg: Msg) -> &'static str {
+        let catalog = match self {
+            Lang::En => &[],
+            Lang::De => DE,
+            Lang::Es => ES,
+            Lang::Fr => FR,
+        };
+        catalog
+            .iter()
+            .find(|(key, _)| *key == msg)
+            .map_or_else(|| msg.english(), |(_, text)| text)
+    }
+
+    /// The message with its `{}` placeholders replaced by `args`, in order
+    pub fn format(self, msg: Msg, args: &[&dyn Display]) -> String {
+        let mut parts = self.text(msg).split("{}");
+        let mut out = parts.next().unwrap_or_default().to_string();
+        for (index, part) in parts.enumerate() {
+            if let Some(arg) = args.get(index) {
+                out.push_str(&arg.to_string());
+            }
+            out.push_str(part);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every message; the match below stops compiling when one is added
+    /// without being listed here too
+    const MESSAGES: &[Msg] = &[
+        Msg::Summary,
+        Msg::FilesDiscovered,
+        Msg::FilesAnalyzed,
+        Msg::FilesSkipped,
+        Msg::FilesWithIssues,
+        Msg::TotalIssues,
+        Msg::Errors,
+        Msg::Warnings,
+        Msg::Info,
+        Msg::LintScore,
+        Msg::NoIssues,
+        Msg::Truncated,
+        Msg::MoreFrom,
+        Msg::Directory,
+        Msg::Files,
+        Msg::Issues,
+        Msg::Ruleset,
+        Msg::Time,
+        Msg::NoConfig,
+        Msg::MoreDiagnostics,
+        Msg::OutsideDiff,
+        Msg::BelowSeverity,
+        Msg::Progress,
+    ];
+
+    #[test]
+    fn every_language_has_every_message() {
+        for &msg in MESSAGES {
+            match msg {
+                Msg::Summary
+                | Msg::FilesDiscovered
+                | Msg::FilesAnalyzed
+                | Msg::FilesSkipped
+                | Msg::FilesWithIssues
+                | Msg::TotalIssues
+                | Msg::Errors
+                | Msg::Warnings
+                | Msg::Info
+                | Msg::LintScore
+                | Msg::NoIssues
+                | Msg::Truncated
+                | Msg::MoreFrom
+                | Msg::Directory
+                | Msg::Files
+                | Msg::Issues
+                | Msg::Ruleset
+                | Msg::Time
+                | Msg::NoConfig
+                | Msg::MoreDiagnostics
+                | Msg::OutsideDiff
+                | Msg::BelowSeverity
+                | Msg::Progress => {}
+            }
+        }
+        for (tag, catalog) in [("de", DE), ("es", ES), ("fr", FR)] {
+            for &msg in MESSAGES {
+                let translated = catalog.iter().filter(|(key, _)| *key == msg).count();
+                assert_eq!(
+                    translated, 1,
+                    "{} translates {:?} {} times",
+                    tag, msg, translated
+                );
+            }
+        }
+        for &(tag, lang) in LANGS {
+            for &msg in MESSAGES {
+                let text = lang.text(msg);
+                assert!(!text.trim().is_empty(), "{} {:?}", tag, msg);
+                assert_eq!(
+                    text.matches("{}").count(),
+                    msg.english().matches("{}").count(),
+                    "{} {:?} has different placeholders",
+                    tag,
+                    msg
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn resolves_tags_and_locales() {
+        assert_eq!(Lang::parse("de"), Some(Lang::De));
+        assert_eq!(Lang::parse("fr-CA"), Some(Lang::Fr));
+        assert_eq!(Lang::parse("es_ES.UTF-8"), Some(Lang::Es));
+        assert_eq!(Lang::parse("C"), None);
+        assert_eq!(Lang::resolve(Some("DE")).unwrap(), Lang::De);
+        assert!(Lang::resolve(Some("tlh")).is_err());
+    }
+
+    #[test]
+    fn fills_placeholders_in_order() {
+        assert_eq!(
+            Lang::De.format(Msg::MoreFrom, &[&3, &"base"]),
+            "... und 3 weitere Diagnose(n) aus base"
+        );
+        assert_eq!(
+            Lang::En.format(Msg::NoIssues, &[]),
+            "No issues found in  file(s)"
+        );
+    }
+}
@@ -0,0 +1,158 @@
+use anyhow::{Context, Result, anyhow};
+use forseti_sdk::config::{Config, RulesetCfg};
+use std::env;
+use std::path::Path;
+
+/// Values substituted into config strings:
+///
+/// - `${env:NAME}`: the environment variable `NAME`, an error when unset
+/// - `${workspaceRoot}`: absolute path of the directory holding the config
+///
+/// `$${` produces a literal `${`.
+pub struct Variables {
+    workspace_root: String,
+}
+
+impl Variables {
+    pub fn new(workspace_root: &Path) -> Self {
+        Self {
+            workspace_root: workspace_root.to_string_lossy().into_owned(),
+        }
+    }
+
+    /// Expand every `${...}` in `text`
+    pub fn expand(&self, text: &str) -> Result<String> {
+        let mut expanded = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find("${") {
+            if rest[..start].ends_with('$') {
+                expanded.push_str(&rest[..start - 1]);
+                expanded.push_str("${");
+                rest = &rest[start + 2..];
+                continue;
+            }
+            expanded.push_str(&rest[..start]);
+            let end = rest[start..]
+                .find('}')
+                .ok_or_else(|| anyhow!("Unterminated '${{' in \"{}\"", text))?;
+            let name = &rest[start + 2..start + end];
+            expanded.push_str(&self.lookup(name)?);
+            rest = &rest[start + end + 1..];
+        }
+        expanded.push_str(rest);
+        Ok(expanded)
+    }
+
+    fn lookup(&self, name: &str) -> Result<String> {
+        if let Some(var) = name.strip_prefix("env:") {
+            return env::var(var)
+                .map_err(|_| anyhow!("Environment variable {} used in config is not set", var));
+        }
+        match name {
+            "workspaceRoot" => Ok(self.workspace_root.clone()),
+            _ => Err(anyhow!(
+                "Unknown variable '${{{}}}' (expected env:NAME or workspaceRoot)",
+                name
+            )),
+        }
+    }
+
+    /// Expand every string in a TOML value, recursively
+    pub fn expand_value(&self, value: &mut toml::Value) -> Result<()> {
+        match value {
+            toml::Value::String(text) => *text = self.expand(text)?,
+            toml::Value::Array(items) => {
+                for item in items {
+                    self.expand_value(item)?;
+                }
+            }
+            toml::Value::Table(table) => self.expand_table(table)?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    pub fn expand_table(&self, table: &mut toml::Table) -> Result<()> {
+        for (_, value) in table.iter_mut() {
+            self.expand_value(value)?;
+        }
+        Ok(())
+    }
+
+    /// Expand ruleset sources and the config passed to each ruleset
+    pub fn expand_config(&self, config: &mut Config) -> Result<()> {
+        for (id, ruleset) in config.ruleset.iter_mut() {
+            self.expand_ruleset(ruleset)
+                .with_context(|| format!("In [ruleset.{}]", id))?;
+        }
+        Ok(())
+    }
+
+    fn expand_ruleset(&self, ruleset: &mut RulesetCfg) -> Result<()> {
+        for source in [&mut ruleset.path, &mut ruleset.git].into_iter().flatten() {
+            *source = self.expand(source)?;
+        }
+        self.expand_table(&mut ruleset.config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variables() -> Variables {
+        Variables::new(Path::new("/work/project"))
+    }
+
+    #[test]
+    fn expands_workspace_root() {
+        assert_eq!(
+            variables()
+                .expand("${workspaceRoot}/tools/ruleset")
+                .unwrap(),
+            "/work/project/tools/ruleset"
+        );
+    }
+
+    #[test]
+    fn expands_environment_variables() {
+        let path = env::var("PATH").unwrap();
+        assert_eq!(
+            variables().expand("[${env:PATH}]").unwrap(),
+            format!("[{}]", path)
+        );
+        assert!(
+            variables()
+                .expand("${env:FORSETI_TEST_UNSET_VARIABLE}")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn double_dollar_escapes() {
+        assert_eq!(
+            variables()
+                .expand("$${workspaceRoot} is ${workspaceRoot}")
+                .unwrap(),
+            "${workspaceRoot} is /work/project"
+        );
+        assert_eq!(variables().expand("cost: $5").unwrap(), "cost: $5");
+    }
+
+    #[test]
+    fn rejects_unknown_and_unterminated_variables() {
+        assert!(variables().expand("${home}").is_err());
+        assert!(variables().expand("${workspaceRoot").is_err());
+    }
+
+    #[test]
+    fn expands_nested_values() {
+        let mut table: toml::Table =
+            toml::from_str("paths = [\"${workspaceRoot}/a\"]\n[inner]\nb = \"${workspaceRoot}\"\n")
+                .unwrap();
+        variables().expand_table(&mut table).unwrap();
+
+        assert_eq!(table["paths"][0].as_str(), Some("/work/project/a"));
+        assert_eq!(table["inner"]["b"].as_str(), Some("/work/project"));
+    }
+}
@@ -0,0 +1,318 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Bytes read from the start of a file to find its shebang line
+const SHEBANG_MAX: u64 = 256;
+
+/// A language forseti knows how to recognize, and the ruleset suggested for it
+#[derive(Debug)]
+pub struct Language {
+    /// Stable identifier, also used as the suggested ruleset id
+    pub id: &'static str,
+    /// Human readable name
+    pub name: &'static str,
+    /// File extensions without the leading dot
+    pub extensions: &'static [&'static str],
+    /// Well-known file names matched exactly
+    pub filenames: &'static [&'static str],
+    /// Interpreters named by the shebang line of extensionless scripts,
+    /// without version suffixes like `3` or `3.12`
+    pub interpreters: &'static [&'static str],
+    /// Comment opener and closer used when forseti writes comments into files
+    pub comment: Option<(&'static str, &'static str)>,
+}
+
+/// Built-in language table
+pub const LANGUAGES: &[Language] = &[
+    Language {
+        id: "rust",
+        name: "Rust",
+        extensions: &["rs"],
+        filenames: &[],
+        interpreters: &[],
+        comment: Some(("//", "")),
+    },
+    Language {
+        id: "python",
+        name: "Python",
+        extensions: &["py", "pyi"],
+        filenames: &[],
+        interpreters: &["python", "pypy"],
+        comment: Some(("#", "")),
+    },
+    Language {
+        id: "javascript",
+        name: "JavaScript",
+        extensions: &["js", "mjs", "cjs", "jsx"],
+        filenames: &[],
+        interpreters: &["node", "nodejs", "deno", "bun"],
+        comment: Some(("//", "")),
+    },
+    Language {
+        id: "typescript",
+        name: "TypeScript",
+        extensions: &["ts", "mts", "cts", "tsx"],
+        filenames: &[],
+        interpreters: &["ts-node", "tsx"],
+        comment: Some(("//", "")),
+    },
+    Language {
+        id: "go",
+        name: "Go",
+        extensions: &["go"],
+        filenames: &[],
+        interpreters: &[],
+        comment: Some(("//", "")),
+    },
+    Language {
+        id: "java",
+        name: "Java",
+        extensions: &["java"],
+        filenames: &[],
+        interpreters: &[],
+        comment: Some(("//", "")),
+    },
+    Language {
+        id: "kotlin",
+        name: "Kotlin",
+        extensions: &["kt", "kts"],
+        filenames: &[],
+        interpreters: &["kotlin"],
+        comment: Some(("//", "")),
+    },
+    Language {
+        id: "c",
+        name: "C",
+        extensions: &["c", "h"],
+        filenames: &[],
+        interpreters: &[],
+        comment: Some(("//", "")),
+    },
+    Language {
+        id: "cpp",
+        name: "C++",
+        extensions: &["cc", "cpp", "cxx", "hpp", "hh", "hxx"],
+        filenames: &[],
+        interpreters: &[],
+        comment: Some(("//", "")),
+    },
+    Language {
+        id: "csharp",
+        name: "C#",
+        extensions: &["cs"],
+        filenames: &[],
+        interpreters: &[],
+        comment: Some(("//", "")),
+    },
+    Language {
+        id: "ruby",
+        name: "Ruby",
+        extensions: &["rb"],
+        filenames: &["Gemfile", "Rakefile", "Vagrantfile", "Brewfile", "Podfile"],
+        interpreters: &["ruby", "jruby"],
+        comment: Some(("#", "")),
+    },
+    Language {
+        id: "php",
+        name: "PHP",
+        extensions: &["php"],
+        filenames: &[],
+        interpreters: &["php"],
+        comment: Some(("//", "")),
+    },
+    Language {
+        id: "swift",
+        name: "Swift",
+        extensions: &["swift"],
+        filenames: &[],
+        interpreters: &["swift"],
+        comment: Some(("//", "")),
+    },
+    Language {
+        id: "scala",
+        name: "Scala",
+        extensions: &["scala", "sc"],
+        filenames: &[],
+        interpreters: &["scala"],
+        comment: Some(("//", "")),
+    },
+    Language {
+        id: "lua",
+        name: "Lua",
+        extensions: &["lua"],
+        filenames: &[],
+        interpreters: &["lua", "luajit"],
+        comment: Some(("--", "")),
+    },
+    Language {
+        id: "shell",
+        name: "Shell",
+        extensions: &["sh", "bash", "zsh"],
+        filenames: &[".bashrc", ".bash_profile", ".zshrc", ".profile"],
+        interpreters: &["sh", "bash", "zsh", "dash", "ksh"],
+        comment: Some(("#", "")),
+    },
+    Language {
+        id: "terraform",
+        name: "Terraform",
+        extensions: &["tf", "tfvars"],
+        filenames: &[],
+        interpreters: &[],
+        comment: Some(("#", "")),
+    },
+    Language {
+        id: "dockerfile",
+        name: "Dockerfile",
+        extensions: &["dockerfile"],
+        filenames: &["Dockerfile", "Containerfile"],
+        interpreters: &[],
+        comment: Some(("#", "")),
+    },
+    Language {
+        id: "makefile",
+        name: "Makefile",
+        extensions: &["mk"],
+        filenames: &["Makefile", "GNUmakefile", "makefile"],
+        interpreters: &["make"],
+        comment: Some(("#", "")),
+    },
+    Language {
+        id: "yaml",
+        name: "YAML",
+        extensions: &["yml", "yaml"],
+        filenames: &[],
+        interpreters: &[],
+        comment: Some(("#", "")),
+    },
+    Language {
+        id: "json",
+        name: "JSON",
+        extensions: &["json"],
+        filenames: &[],
+        interpreters: &[],
+        comment: None,
+    },
+    Language {
+        id: "toml",
+        name: "TOML",
+        extensions: &["toml"],
+        filenames: &[],
+        interpreters: &[],
+        comment: Some(("#", "")),
+    },
+    Language {
+        id: "markdown",
+        name: "Markdown",
+        extensions: &["md", "markdown"],
+        filenames: &[],
+        interpreters: &[],
+        comment: Some(("<!--", " -->")),
+    },
+    Language {
+        id: "html",
+        name: "HTML",
+        extensions: &["html", "htm"],
+        filenames: &[],
+        interpreters: &[],
+        comment: Some(("<!--", " -->")),
+    },
+    Language {
+        id: "css",
+        name: "CSS",
+        extensions: &["css", "scss", "sass", "less"],
+        filenames: &[],
+        interpreters: &[],
+        comment: Some(("/*", " */")),
+    },
+    Language {
+        id: "sql",
+        name: "SQL",
+        extensions: &["sql"],
+        filenames: &[],
+        interpreters: &[],
+        comment: Some(("--", "")),
+    },
+];
+
+/// Curated rulesets `forseti enable-language` adds for languages whose
+/// recommendation goes beyond the general `base` ruleset and the one named
+/// after the language
+const BUNDLES: &[(&str, &[&str])] = &[
+    ("typescript", &["base", "javascript", "typescript"]),
+    ("kotlin", &["base", "java", "kotlin"]),
+    ("cpp", &["base", "c", "cpp"]),
+    ("terraform", &["base", "terraform", "security"]),
+    ("dockerfile", &["base", "dockerfile", "security"]),
+];
+
+/// Rulesets recommended for a language, in the order they are added
+pub fn recommended_rulesets(language: &Language) -> Vec<&'static str> {
+    match BUNDLES.iter().find(|(id, _)| *id == language.id) {
+        Some((_, rulesets)) => rulesets.to_vec(),
+        None => vec!["base", language.id],
+    }
+}
+
+/// Look up a language by its id
+pub fn by_id(id: &str) -> Option<&'static Language> {
+    LANGUAGES.iter().find(|l| l.id == id)
+}
+
+/// Detect a file's language from its name or extension
+pub fn detect(path: &Path) -> Option<&'static Language> {
+    let file_name = path.file_name()?.to_str()?;
+    if let Some(language) = LANGUAGES.iter().find(|l| l.filenames.contains(&file_name)) {
+        return Some(language);
+    }
+
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    LANGUAGES
+        .iter()
+        .find(|l| l.extensions.contains(&extension.as_str()))
+}
+
+/// Detect a file's language from its name, else for files without a known
+/// extension from the interpreter on its shebang line
+pub fn detect_content(path: &Path, content: &str) -> Option<&'static Language> {
+    detect(path).or_else(|| {
+        let interpreter = shebang_interpreter(content)?;
+        LANGUAGES
+            .iter()
+            .find(|l| l.interpreters.contains(&interpreter))
+    })
+}
+
+/// Detect the language of a file on disk, reading the start of it only when
+/// its name doesn't tell
+pub fn detect_file(path: &Path) -> Option<&'static Language> {
+    detect(path).or_else(|| {
+        let mut head = Vec::new();
+        File::open(path)
+            .ok()?
+            .take(SHEBANG_MAX)
+            .read_to_end(&mut head)
+            .ok()?;
+        detect_content(path, &String::from_utf8_lossy(&head))
+    })
+}
+
+/// Interpreter a `#!` line runs, looking through `env` and its options and
+/// dropping version suffixes: `#!/usr/bin/env -S python3 -u` gives `python`
+fn shebang_interpreter(content: &str) -> Option<&str> {
+    let line = content.lines().next()?.strip_prefix("#!")?;
+    let mut words = line.split_whitespace();
+    let mut program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        program = words.find(|word| !word.starts_with('-') && !word.contains('='))?;
+    }
+    Some(program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.'))
+}
+
+/// Label used when reporting groups of files, e.g. `.tf` or `Dockerfile`
+pub fn file_kind(path: &Path) -> Option<String> {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) => Some(format!(".{}", ext.to_ascii_lowercase())),
+        None => path.file_name().map(|n| n.to_string_lossy().to_string()),
+    }
+}
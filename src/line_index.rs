@@ -0,0 +1,132 @@
+/// Line lookup over a text, mapping zero-based (line, character) positions
+/// from rulesets to byte offsets
+///
+/// Lines end at `\n`; a `\r` before it belongs to the terminator, so `\n`,
+/// `\r\n` and files mixing both map positions the same way, and writers can
+/// put each line's own terminator back.
+pub struct LineIndex<'a> {
+    text: &'a str,
+    /// Byte offset where each line starts
+    starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(text: &'a str) -> Self {
+        let starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self { text, starts }
+    }
+
+    /// Number of lines, counting the (possibly empty) one after a final newline
+    pub fn line_count(&self) -> usize {
+        self.starts.len()
+    }
+
+    /// A line without its terminator
+    pub fn line(&self, line: usize) -> Option<&'a str> {
+        let (start, end) = self.content_bounds(line)?;
+        Some(&self.text[start..end])
+    }
+
+    /// The terminator of a line: `"\r\n"`, `"\n"`, or `""` for the last line
+    pub fn ending(&self, line: usize) -> &'a str {
+        match (self.content_bounds(line), self.starts.get(line + 1)) {
+            (Some((_, end)), Some(&next)) => &self.text[end..next],
+            _ => "",
+        }
+    }
+
+    /// The terminator most lines use, for new lines inserted into the text
+    pub fn dominant_ending(&self) -> &'static str {
+        let crlf = (0..self.line_count())
+            .filter(|&line| self.ending(line) == "\r\n")
+            .count();
+        if crlf * 2 > self.line_count() - 1 {
+            "\r\n"
+        } else {
+            "\n"
+        }
+    }
+
+    /// Byte offset of a position; characters past the end of a line clamp to
+    /// just before its terminator
+    pub fn offset(&self, line: usize, character: usize) -> Option<usize> {
+        let (start, end) = self.content_bounds(line)?;
+        let column = self.text[start..end]
+            .char_indices()
+            .nth(character)
+            .map(|(i, _)| i)
+            .unwrap_or(end - start);
+        Some(start + column)
+    }
+
+    fn content_bounds(&self, line: usize) -> Option<(usize, usize)> {
+        let start = *self.starts.get(line)?;
+        let end = match self.starts.get(line + 1) {
+            Some(&next) => {
+                let end = next - 1;
+                if self.text[..end].ends_with('\r') {
+                    end - 1
+                } else {
+                    end
+                }
+            }
+            None => self.text.len(),
+        };
+        Some((start, end))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_exclude_their_terminators() {
+        let index = LineIndex::new("one\r\ntwo\nthree");
+
+        assert_eq!(index.line_count(), 3);
+        assert_eq!(index.line(0), Some("one"));
+        assert_eq!(index.line(1), Some("two"));
+        assert_eq!(index.line(2), Some("three"));
+        assert_eq!(index.line(3), None);
+        assert_eq!(index.ending(0), "\r\n");
+        assert_eq!(index.ending(1), "\n");
+        assert_eq!(index.ending(2), "");
+    }
+
+    #[test]
+    fn final_newline_starts_an_empty_line() {
+        let index = LineIndex::new("a\r\n");
+
+        assert_eq!(index.line_count(), 2);
+        assert_eq!(index.line(1), Some(""));
+    }
+
+    #[test]
+    fn crlf_and_lf_map_positions_alike() {
+        let lf = LineIndex::new("ab\ncd\n");
+        let crlf = LineIndex::new("ab\r\ncd\r\n");
+
+        assert_eq!(lf.offset(1, 1), Some(4));
+        assert_eq!(crlf.offset(1, 1), Some(5));
+        // Past the end of a line clamps before the terminator, `\r` included
+        assert_eq!(lf.offset(0, 10), Some(2));
+        assert_eq!(crlf.offset(0, 10), Some(2));
+    }
+
+    #[test]
+    fn characters_count_chars_not_bytes() {
+        let index = LineIndex::new("é=1\n");
+
+        assert_eq!(index.offset(0, 1), Some(2));
+    }
+
+    #[test]
+    fn dominant_ending_follows_most_lines() {
+        assert_eq!(LineIndex::new("a\r\nb\r\nc\nd").dominant_ending(), "\r\n");
+        assert_eq!(LineIndex::new("a\r\nb\nc\nd").dominant_ending(), "\n");
+        assert_eq!(LineIndex::new("single line").dominant_ending(), "\n");
+    }
+}
@@ -1,10 +1,31 @@
-use crate::commands::Commands;
+use crate::commands::{Commands, PluginCommands};
 use anyhow::Result;
-use clap::{Parser, command};
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser};
 use std::path::PathBuf;
 
+mod artifacts;
+mod cache;
 mod commands;
+mod container;
 mod context;
+mod filters;
+mod fingerprint;
+mod git;
+mod i18n;
+mod interpolate;
+mod languages;
+mod line_index;
+mod manifest;
+mod metadata;
+mod paths;
+mod policy;
+mod rules;
+mod settings;
+mod state;
+#[cfg(feature = "store")]
+mod store;
+mod suppressions;
 
 use context::GlobalContext;
 
@@ -13,7 +34,8 @@ use context::GlobalContext;
     name = "forseti",
     version,
     about = "Forseti — a pluggable, multi-language linter",
-    propagate_version = true
+    propagate_version = true,
+    arg_required_else_help = true
 )]
 struct Cli {
     /// Enable verbose output
@@ -28,30 +50,99 @@ struct Cli {
     #[arg(short, long, global = true)]
     config: Option<PathBuf>,
 
+    /// Directory of package-manager installed ruleset binaries, searched after the user cache
+    #[arg(long, global = true)]
+    system_plugin_dir: Vec<PathBuf>,
+
+    /// Non-interactive mode for CI containers: no colors, compact output, and no writes outside the workspace or an explicit cache dir
+    #[arg(long, global = true)]
+    ci: bool,
+
+    /// Language of summaries and messages (en, de, es, fr); defaults to FORSETI_LANG, then the locale
+    #[arg(long, global = true, value_name = "LANG")]
+    lang: Option<String>,
+
+    /// Accessible output for screen readers and dumb terminals: ASCII only, severities spelled out rather than told apart by color, and plain progress lines (implied by TERM=dumb)
+    #[arg(long, global = true)]
+    ascii: bool,
+
+    /// Directory rulesets are installed into and found in, under `cache/`, instead of ~/.forseti; overrides FORSETI_HOME
+    #[arg(long, global = true, value_name = "DIR")]
+    install_root: Option<PathBuf>,
+
+    /// Run as a Bazel persistent worker: read JSON work requests from stdin and run each as a forseti command line, answering on stdout (Bazel passes this itself)
+    #[arg(long, global = true, alias = "persistent_worker")]
+    persistent_worker: bool,
+
     #[command(subcommand)]
-    command: Commands,
+    command: Option<Commands>,
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
-    
+
     // Create global context from CLI args
-    let ctx = GlobalContext::new(cli.verbose, cli.no_color, cli.config);
+    let ctx = GlobalContext::new(
+        cli.verbose,
+        cli.no_color,
+        cli.config,
+        cli.system_plugin_dir,
+        cli.ci,
+        i18n::Lang::resolve(cli.lang.as_deref())?,
+        cli.ascii,
+    )
+    .with_install_root(cli.install_root);
+
+    if cli.persistent_worker {
+        return commands::worker::run(&ctx);
+    }
+    let Some(command) = cli.command else {
+        Cli::command()
+            .error(ErrorKind::MissingSubcommand, "a subcommand is required")
+            .exit()
+    };
 
-    match cli.command {
+    match command {
         Commands::Init { path, force } => commands::init::run(&ctx, &path, force),
         Commands::Install {
             cache_path,
             enable_cache,
             path,
             force,
-        } => commands::install::run(&ctx, &cache_path, enable_cache, &path, force),
-        Commands::Lint {
+            check,
+        } => commands::install::run(
+            &ctx,
+            cache_path.as_deref(),
+            enable_cache,
+            &path,
+            force,
+            check,
+        ),
+        Commands::GenInstaller { path } => commands::gen_installer::run(&ctx, &path),
+        Commands::EnableLanguage {
+            language,
+            path,
+            no_install,
+        } => commands::enable_language::run(&ctx, &language, &path, no_install),
+        Commands::Lint(args) => commands::lint::run(&ctx, *args),
+        Commands::Langs { path } => commands::langs::run(&ctx, &path),
+        Commands::Coverage { path } => commands::coverage::run(&ctx, &path),
+        Commands::Daemon { path, stop } => commands::daemon::run(&ctx, &path, stop),
+        Commands::Annotate {
+            rule,
+            justification,
             path,
-            fix,
-            recursive,
-            output,
-            output_file,
-        } => commands::lint::run(&ctx, &path, fix, recursive, output, output_file),
+        } => commands::annotate::run(&ctx, &rule, &path, &justification),
+        Commands::Rerun { report, args } => commands::rerun::run(&ctx, &report, &args),
+        Commands::Verify { path } => commands::verify::run(&ctx, &path),
+        Commands::Plugin { command } => match command {
+            PluginCommands::Verify { binary } => commands::plugin::verify(&ctx, &binary),
+            PluginCommands::Fuzz {
+                binary,
+                seconds,
+                seed,
+                out,
+            } => commands::plugin::fuzz(&ctx, &binary, seconds, seed, &out),
+        },
     }
 }
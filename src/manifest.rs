@@ -0,0 +1,101 @@
+use crate::languages;
+use anyhow::{Context, Result, anyhow};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Manifest a ruleset repository ships at its root
+pub const MANIFEST_FILE: &str = "forseti-ruleset.toml";
+
+/// Version of the stdio protocol this CLI speaks
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Declares what a ruleset repository provides
+///
+/// ```toml
+/// id = "security"
+/// version = "1.4.0"
+/// bins = ["forseti-security"]   # the first entry is the ruleset entrypoint
+/// languages = ["rust", "go"]
+/// min_protocol = 1
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct Manifest {
+    pub id: String,
+    pub version: String,
+    pub bins: Vec<String>,
+    #[serde(default)]
+    pub languages: Vec<String>,
+    #[serde(default = "default_min_protocol")]
+    pub min_protocol: u32,
+}
+
+fn default_min_protocol() -> u32 {
+    1
+}
+
+impl Manifest {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read ruleset manifest: {}", path.display()))?;
+        toml::from_str(&content)
+            .with_context(|| format!("Failed to parse ruleset manifest: {}", path.display()))
+    }
+
+    /// Look for a manifest in `start` and its ancestors, stopping at the
+    /// project root (the first directory with a `Cargo.toml` or `.git`)
+    pub fn find(start: &Path) -> Result<Option<(Self, PathBuf)>> {
+        for dir in start.ancestors() {
+            let path = dir.join(MANIFEST_FILE);
+            if path.is_file() {
+                return Ok(Some((Self::load(&path)?, path)));
+            }
+            if dir.join("Cargo.toml").exists() || dir.join(".git").exists() {
+                break;
+            }
+        }
+        Ok(None)
+    }
+
+    /// Check the manifest describes the ruleset configured as `id` and that
+    /// this CLI can talk to it
+    pub fn validate(&self, id: &str) -> Result<()> {
+        if self.id != id {
+            return Err(anyhow!(
+                "Manifest declares ruleset '{}' but it is configured as '{}'",
+                self.id,
+                id
+            ));
+        }
+        if self.bins.is_empty() {
+            return Err(anyhow!("Manifest for '{}' lists no bins", self.id));
+        }
+        if self.min_protocol > PROTOCOL_VERSION {
+            return Err(anyhow!(
+                "Ruleset '{}' requires protocol v{}, this forseti speaks v{}. Upgrade forseti.",
+                self.id,
+                self.min_protocol,
+                PROTOCOL_VERSION
+            ));
+        }
+        for language in &self.languages {
+            if languages::by_id(language).is_none() {
+                eprintln!(
+                    "Warning: ruleset '{}' declares unknown language '{}'",
+                    self.id, language
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// File name of the entrypoint binary on this platform
+    pub fn entrypoint(&self) -> String {
+        let bin = &self.bins[0];
+        if cfg!(windows) && !bin.ends_with(".exe") {
+            format!("{}.exe", bin)
+        } else {
+            bin.clone()
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use crate::fingerprint;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Name under which run metadata is embedded in reports
+pub const METADATA_KEY: &str = "forseti.run";
+
+/// Everything needed to tell lint runs apart and to reproduce one later
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunMetadata {
+    pub run_id: String,
+    /// RFC 3339 start time
+    pub timestamp: String,
+    pub cli_version: String,
+    /// Hash of the config file contents
+    pub config_hash: String,
+    /// Version reported by each ruleset during initialize, when known
+    pub plugin_versions: BTreeMap<String, String>,
+    /// `HEAD` of the workspace repository, when it is one
+    pub git_commit: Option<String>,
+    /// Directory the run was started from, which `args` are relative to
+    pub working_dir: PathBuf,
+    /// Directory containing the config file
+    pub workspace_root: PathBuf,
+    /// Command line arguments (without the program name)
+    pub args: Vec<String>,
+}
+
+impl RunMetadata {
+    /// Capture metadata at the start of a run
+    pub fn capture(config_path: &Path, workspace_root: &Path) -> Result<Self> {
+        let config = fs::read(config_path)
+            .with_context(|| format!("Failed to read config file: {}", config_path.display()))?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+
+        Ok(Self {
+            run_id: format!("{:x}-{:x}", now.as_nanos(), std::process::id()),
+            timestamp: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            cli_version: env!("CARGO_PKG_VERSION").to_string(),
+            config_hash: fingerprint::hash_bytes(&config),
+            plugin_versions: BTreeMap::new(),
+            git_commit: git_commit(workspace_root),
+            working_dir: std::env::current_dir().context("Failed to read current directory")?,
+            workspace_root: workspace_root.to_path_buf(),
+            args: std::env::args().skip(1).collect(),
+        })
+    }
+
+    /// Derive the run id and start time from the run's inputs rather than
+    /// the clock, so identical runs report identical metadata; `epoch` is
+    /// the Unix time to report as the start
+    pub fn pin(&mut self, epoch: i64) {
+        let inputs = [
+            self.config_hash.as_str(),
+            self.git_commit.as_deref().unwrap_or_default(),
+            &self.args.join("\0"),
+        ]
+        .join("\0");
+        self.run_id = fingerprint::hash_bytes(inputs.as_bytes());
+        self.timestamp = chrono::DateTime::from_timestamp(epoch, 0)
+            .unwrap_or_default()
+            .to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    }
+}
+
+/// Current commit of the repository containing `dir`
+pub fn git_commit(dir: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .current_dir(dir)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
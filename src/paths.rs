@@ -0,0 +1,148 @@
+use std::path::{Component, Path, PathBuf};
+
+/// Longest path Windows APIs accept without the `\\?\` prefix
+#[cfg(windows)]
+const MAX_PATH: usize = 260;
+
+/// `canonicalize` that falls back to the path as given and, on Windows,
+/// drops the `\\?\` prefix again when the path doesn't need it
+pub fn canonicalize(path: &Path) -> PathBuf {
+    path.canonicalize()
+        .map(|absolute| simplify(&absolute))
+        .unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// [`canonicalize`] for paths that may not exist, like files only in git:
+/// the deepest existing ancestor is resolved and the rest appended to it
+pub fn canonicalize_missing(path: &Path) -> PathBuf {
+    let mut missing = Vec::new();
+    let mut existing = path;
+    loop {
+        let dir = if existing.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            existing
+        };
+        if let Ok(absolute) = dir.canonicalize() {
+            let mut resolved = simplify(&absolute);
+            resolved.extend(missing.iter().rev());
+            return resolved;
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name);
+                existing = parent;
+            }
+            _ => return path.to_path_buf(),
+        }
+    }
+}
+
+/// Turn `\\?\C:\dir` into `C:\dir` and `\\?\UNC\server\share` into
+/// `\\server\share` when short enough to work without the prefix, so paths
+/// compare equal to user-supplied ones and render readably
+pub fn simplify(path: &Path) -> PathBuf {
+    #[cfg(windows)]
+    {
+        let text = path.to_string_lossy();
+        let simplified = if let Some(unc) = text.strip_prefix(r"\\?\UNC\") {
+            Some(format!(r"\\{}", unc))
+        } else if let Some(rest) = text.strip_prefix(r"\\?\") {
+            // Only drive paths; other verbatim forms have no short equivalent
+            (rest.as_bytes().get(1) == Some(&b':')).then(|| rest.to_string())
+        } else {
+            None
+        };
+        if let Some(simplified) = simplified
+            && simplified.len() < MAX_PATH
+        {
+            return PathBuf::from(simplified);
+        }
+    }
+    path.to_path_buf()
+}
+
+/// Add the `\\?\` prefix to long absolute paths on Windows so file
+/// operations on deep cache directories don't fail at 260 characters
+pub fn extended(path: PathBuf) -> PathBuf {
+    #[cfg(windows)]
+    {
+        let text = path.to_string_lossy();
+        if text.len() >= MAX_PATH && !text.starts_with(r"\\?\") {
+            if let Some(unc) = text.strip_prefix(r"\\") {
+                return PathBuf::from(format!(r"\\?\UNC\{}", unc));
+            }
+            if path.is_absolute() {
+                return PathBuf::from(format!(r"\\?\{}", text));
+            }
+        }
+    }
+    path
+}
+
+/// Path with `/` separators for reports, without any `\\?\` prefix
+pub fn display(path: &Path) -> String {
+    let text = simplify(path).to_string_lossy().into_owned();
+    if cfg!(windows) {
+        text.replace('\\', "/")
+    } else {
+        text
+    }
+}
+
+/// `path` relative to `base`, stepping out with `..` where needed; both
+/// should be absolute
+pub fn relative_to(path: &Path, base: &Path) -> PathBuf {
+    let path: Vec<Component> = path.components().collect();
+    let base: Vec<Component> = base.components().collect();
+    let common = path.iter().zip(&base).take_while(|(a, b)| a == b).count();
+    // Different drives on Windows have no relative form
+    if common == 0 {
+        return path.iter().collect();
+    }
+    let mut relative: PathBuf = base[common..]
+        .iter()
+        .map(|_| Component::ParentDir)
+        .collect();
+    relative.extend(&path[common..]);
+    if relative.as_os_str().is_empty() {
+        relative.push(".");
+    }
+    relative
+}
+
+/// `file://` URI for a path sent to rulesets
+///
+/// Drive paths become `file:///C:/dir/file` and UNC paths put the server in
+/// the authority (`file://server/share/file`).
+pub fn file_uri(path: &Path) -> String {
+    let text = display(path);
+    if cfg!(windows) {
+        if let Some(unc) = text.strip_prefix("//") {
+            return format!("file://{}", unc);
+        }
+        if text.as_bytes().get(1) == Some(&b':') {
+            return format!("file:///{}", text);
+        }
+    }
+    format!("file://{}", text)
+}
+
+/// Hidden file next to `path` to write into before renaming it over `path`
+pub fn temp_sibling(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{}.{}.tmp", name, std::process::id()))
+}
+
+/// Replace `path` with `contents` in one step, so a crash midway never
+/// leaves a truncated file behind
+pub fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let temp = temp_sibling(path);
+    std::fs::write(&temp, contents)?;
+    std::fs::rename(&temp, path).inspect_err(|_| {
+        let _ = std::fs::remove_file(&temp);
+    })
+}
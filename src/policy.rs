@@ -0,0 +1,242 @@
+use anyhow::{Context, Result, anyhow};
+use serde::Deserialize;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+/// Environment variable pointing at the organization policy file
+pub const POLICY_ENV: &str = "FORSETI_POLICY";
+
+/// Organization-level restrictions on which rulesets may be installed
+///
+/// ```toml
+/// allow = ["base", "security"]   # empty means any ruleset id
+/// deny = ["experimental"]
+///
+/// [sources]
+/// crates_io = false
+/// local = false
+/// git = true
+/// git_allow = ["https://git.example.com/lint/"]
+/// image = true
+/// image_allow = ["registry.example.com/"]
+/// npm = false
+/// pip = false
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Policy {
+    /// Ruleset ids that may be installed (empty allows every id)
+    pub allow: Vec<String>,
+    /// Ruleset ids that may never be installed
+    pub deny: Vec<String>,
+    /// Which kinds of sources are permitted
+    pub sources: SourcePolicy,
+    /// Where the policy was loaded from, used in error messages
+    #[serde(skip)]
+    pub origin: PathBuf,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct SourcePolicy {
+    pub crates_io: bool,
+    pub local: bool,
+    pub git: bool,
+    /// URL prefixes git sources must start with (empty allows any URL)
+    pub git_allow: Vec<String>,
+    pub image: bool,
+    /// Prefixes container images must start with (empty allows any image)
+    pub image_allow: Vec<String>,
+    pub npm: bool,
+    pub pip: bool,
+}
+
+impl Default for SourcePolicy {
+    fn default() -> Self {
+        Self {
+            crates_io: true,
+            local: true,
+            git: true,
+            git_allow: Vec::new(),
+            image: true,
+            image_allow: Vec::new(),
+            npm: true,
+            pip: true,
+        }
+    }
+}
+
+/// Where a ruleset is being installed from
+#[derive(Debug, Clone, Copy)]
+pub enum RulesetSource<'a> {
+    CratesIo,
+    Local(&'a str),
+    Git(&'a str),
+    Image(&'a str),
+    Npm(&'a str),
+    Pip(&'a str),
+}
+
+impl Policy {
+    /// Load the policy named by `FORSETI_POLICY`, if any
+    pub fn from_env() -> Result<Option<Self>> {
+        match env::var_os(POLICY_ENV) {
+            Some(path) if !path.is_empty() => Self::load(PathBuf::from(path)).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    pub fn load(path: PathBuf) -> Result<Self> {
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read policy file: {}", path.display()))?;
+        let mut policy: Policy = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse policy file: {}", path.display()))?;
+        policy.origin = path;
+        Ok(policy)
+    }
+
+    /// Check whether installing `id` from `source` is permitted
+    pub fn check(&self, id: &str, source: RulesetSource) -> Result<()> {
+        if self.deny.iter().any(|d| d == id) {
+            return Err(self.violation(id, "ruleset is on the deny list"));
+        }
+        if !self.allow.is_empty() && !self.allow.iter().any(|a| a == id) {
+            return Err(self.violation(id, "ruleset is not on the allow list"));
+        }
+
+        match source {
+            RulesetSource::CratesIo if !self.sources.crates_io => {
+                Err(self.violation(id, "crates.io sources are not allowed"))
+            }
+            RulesetSource::Local(path) if !self.sources.local => Err(self.violation(
+                id,
+                &format!("local path sources are not allowed ({})", path),
+            )),
+            RulesetSource::Git(url) if !self.sources.git => {
+                Err(self.violation(id, &format!("git sources are not allowed ({})", url)))
+            }
+            RulesetSource::Git(url)
+                if !self.sources.git_allow.is_empty()
+                    && !self
+                        .sources
+                        .git_allow
+                        .iter()
+                        .any(|p| url.starts_with(p.as_str())) =>
+            {
+                Err(self.violation(
+                    id,
+                    &format!(
+                        "git URL {} does not match any allowed prefix ({})",
+                        url,
+                        self.sources.git_allow.join(", ")
+                    ),
+                ))
+            }
+            RulesetSource::Image(image) if !self.sources.image => Err(self.violation(
+                id,
+                &format!("container image sources are not allowed ({})", image),
+            )),
+            RulesetSource::Image(image)
+                if !self.sources.image_allow.is_empty()
+                    && !self
+                        .sources
+                        .image_allow
+                        .iter()
+                        .any(|p| image.starts_with(p.as_str())) =>
+            {
+                Err(self.violation(
+                    id,
+                    &format!(
+                        "image {} does not match any allowed prefix ({})",
+                        image,
+                        self.sources.image_allow.join(", ")
+                    ),
+                ))
+            }
+            RulesetSource::Npm(package) if !self.sources.npm => {
+                Err(self.violation(id, &format!("npm sources are not allowed ({})", package)))
+            }
+            RulesetSource::Pip(package) if !self.sources.pip => {
+                Err(self.violation(id, &format!("pip sources are not allowed ({})", package)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn violation(&self, id: &str, reason: &str) -> anyhow::Error {
+        anyhow!(
+            "Ruleset '{}' is blocked by organization policy {}: {}",
+            id,
+            self.origin.display(),
+            reason
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(content: &str) -> Policy {
+        toml::from_str(content).unwrap()
+    }
+
+    #[test]
+    fn deny_wins_over_allow() {
+        let policy = policy("allow = [\"base\", \"experimental\"]\ndeny = [\"experimental\"]\n");
+
+        assert!(policy.check("base", RulesetSource::CratesIo).is_ok());
+        let error = policy
+            .check("experimental", RulesetSource::CratesIo)
+            .unwrap_err();
+        assert!(error.to_string().contains("deny list"), "{}", error);
+        assert!(policy.check("other", RulesetSource::CratesIo).is_err());
+    }
+
+    #[test]
+    fn empty_policy_allows_everything() {
+        let policy = policy("");
+
+        for source in [
+            RulesetSource::CratesIo,
+            RulesetSource::Local("./tools/ruleset"),
+            RulesetSource::Git("https://example.com/ruleset"),
+            RulesetSource::Image("ghcr.io/acme/ruleset:1"),
+            RulesetSource::Npm("forseti-ruleset-js"),
+            RulesetSource::Pip("forseti-ruleset-py"),
+        ] {
+            assert!(policy.check("any", source).is_ok(), "{:?}", source);
+        }
+    }
+
+    #[test]
+    fn disabled_source_kinds_are_rejected() {
+        let policy = policy("[sources]\ncrates_io = false\nlocal = false\nnpm = false\n");
+
+        assert!(policy.check("base", RulesetSource::CratesIo).is_err());
+        assert!(policy.check("base", RulesetSource::Local("./x")).is_err());
+        assert!(policy.check("base", RulesetSource::Npm("x")).is_err());
+        assert!(policy.check("base", RulesetSource::Pip("x")).is_ok());
+        assert!(
+            policy
+                .check("base", RulesetSource::Git("https://x"))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn git_and_image_sources_must_match_a_prefix() {
+        let policy = policy(
+            "[sources]\ngit_allow = [\"https://git.example.com/lint/\"]\n\
+             image_allow = [\"registry.example.com/\"]\n",
+        );
+
+        let git = |url| policy.check("base", RulesetSource::Git(url));
+        assert!(git("https://git.example.com/lint/base").is_ok());
+        assert!(git("https://git.example.com/other/base").is_err());
+        let image = |image| policy.check("base", RulesetSource::Image(image));
+        assert!(image("registry.example.com/base:1").is_ok());
+        assert!(image("docker.io/base:1").is_err());
+    }
+}
@@ -0,0 +1,222 @@
+use anyhow::{Result, anyhow};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Canonical `ruleset/rule` id of a diagnostic, used in every report and
+/// accepted everywhere config names a rule
+///
+/// Rulesets that already namespace their rule ids aren't prefixed twice.
+pub fn qualify(ruleset_id: &str, rule_id: &str) -> String {
+    match rule_id.strip_prefix(ruleset_id) {
+        Some(rest) if rest.starts_with('/') => rule_id.to_string(),
+        _ => format!("{}/{}", ruleset_id, rule_id),
+    }
+}
+
+/// Rule part of a canonical id
+pub fn short(id: &str) -> &str {
+    id.split_once('/').map_or(id, |(_, rule)| rule)
+}
+
+/// Short rule ids reported by more than one ruleset, with those rulesets
+///
+/// A bare id in config matches the rule in every one of them.
+pub fn collisions<'a>(
+    rules: impl IntoIterator<Item = (&'a str, &'a str)>,
+) -> BTreeMap<String, BTreeSet<String>> {
+    let mut owners: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for (ruleset_id, rule_id) in rules {
+        let id = qualify(ruleset_id, rule_id);
+        owners
+            .entry(short(&id).to_string())
+            .or_default()
+            .insert(ruleset_id.to_string());
+    }
+    owners.retain(|_, rulesets| rulesets.len() > 1);
+    owners
+}
+
+/// Severities a rule can be set to in a ruleset's config
+const SEVERITIES: &[&str] = &["off", "error", "warn", "info", "hint"];
+
+/// Rules a ruleset's config sets a severity for, as `"rule" = "warn"` or
+/// `"rule" = ["warn", { ... }]`, with that severity; other keys are options
+pub fn configured(config: &toml::Table) -> BTreeMap<String, String> {
+    config
+        .iter()
+        .filter_map(|(rule, value)| {
+            let severity = match value {
+                toml::Value::Array(items) => items.first()?.as_str()?,
+                value => value.as_str()?,
+            };
+            SEVERITIES
+                .contains(&severity)
+                .then(|| (rule.clone(), severity.to_string()))
+        })
+        .collect()
+}
+
+/// `[aliases]` from `.forseti.toml`, mapping the id a rule used to have to
+/// the id it has now
+///
+/// ```toml
+/// [aliases]
+/// "legacy/max-line-length" = "style/line-length"
+/// ```
+///
+/// Suppressions and budgets written against the old id keep applying, and
+/// fingerprints keep using it so existing baselines still match.
+#[derive(Debug, Default)]
+pub struct RuleAliases {
+    /// Current id → every id it replaced, directly or through a chain
+    previous: HashMap<String, Vec<String>>,
+    /// Current id → the id it replaced, when it replaced exactly one
+    predecessor: HashMap<String, String>,
+}
+
+impl RuleAliases {
+    pub fn new(aliases: &HashMap<String, String>) -> Self {
+        let mut previous: HashMap<String, Vec<String>> = HashMap::new();
+        let mut replaced: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (old, new) in aliases {
+            replaced.entry(new).or_default().push(old);
+            // `validate` rules out cycles, so every chain ends
+            let mut current = new;
+            while let Some(next) = aliases.get(current) {
+                current = next;
+            }
+            previous
+                .entry(current.clone())
+                .or_default()
+                .push(old.clone());
+        }
+        for olds in previous.values_mut() {
+            olds.sort();
+        }
+        let predecessor = replaced
+            .into_iter()
+            .filter(|(_, olds)| olds.len() == 1)
+            .map(|(new, olds)| (new.to_string(), olds[0].to_string()))
+            .collect();
+        Self {
+            previous,
+            predecessor,
+        }
+    }
+
+    /// Check every alias maps a `ruleset/rule` id to another and that
+    /// following them never loops
+    pub fn validate(aliases: &HashMap<String, String>) -> Result<()> {
+        for (old, new) in aliases {
+            for id in [old, new] {
+                if !id.contains('/') {
+                    return Err(anyhow!("Alias '{}' must be a ruleset/rule id", id));
+                }
+            }
+            let mut current = new;
+            for _ in 0..aliases.len() {
+                match aliases.get(current) {
+                    Some(next) if next == old => {
+                        return Err(anyhow!("Aliases for '{}' form a cycle", old));
+                    }
+                    Some(next) => current = next,
+                    None => break,
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The canonical id followed by every id aliased to it
+    pub fn names(&self, id: &str) -> Vec<String> {
+        let mut names = vec![id.to_string()];
+        if let Some(previous) = self.previous.get(id) {
+            names.extend(previous.iter().cloned());
+        }
+        names
+    }
+
+    /// The oldest id a rule went by, following the chain while each step
+    /// replaced a single rule
+    pub fn original<'a>(&'a self, id: &'a str) -> &'a str {
+        let mut current = id;
+        while let Some(previous) = self.predecessor.get(current) {
+            current = previous;
+        }
+        current
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aliases(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(old, new)| (old.to_string(), new.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn qualifies_once() {
+        assert_eq!(qualify("base", "todo"), "base/todo");
+        assert_eq!(qualify("base", "base/todo"), "base/todo");
+        assert_eq!(qualify("base", "basement/todo"), "base/basement/todo");
+        assert_eq!(short("base/todo"), "todo");
+        assert_eq!(short("todo"), "todo");
+    }
+
+    #[test]
+    fn finds_colliding_short_ids() {
+        let collisions = collisions([("base", "todo"), ("style", "todo"), ("base", "fixme")]);
+
+        assert_eq!(collisions.len(), 1);
+        assert_eq!(
+            collisions["todo"].iter().collect::<Vec<_>>(),
+            ["base", "style"]
+        );
+    }
+
+    #[test]
+    fn alias_chains_resolve_to_the_current_id() {
+        let pairs = aliases(&[("old/a", "mid/a"), ("mid/a", "new/a")]);
+        RuleAliases::validate(&pairs).unwrap();
+        let aliases = RuleAliases::new(&pairs);
+
+        assert_eq!(aliases.names("new/a"), ["new/a", "mid/a", "old/a"]);
+        assert_eq!(aliases.names("other/b"), ["other/b"]);
+        assert_eq!(aliases.original("new/a"), "old/a");
+    }
+
+    #[test]
+    fn merged_rules_keep_their_own_ids() {
+        let aliases = RuleAliases::new(&aliases(&[("a/x", "c/z"), ("b/y", "c/z")]));
+
+        assert_eq!(aliases.names("c/z"), ["c/z", "a/x", "b/y"]);
+        assert_eq!(aliases.original("c/z"), "c/z");
+    }
+
+    #[test]
+    fn rejects_cycles_and_bare_ids() {
+        assert!(RuleAliases::validate(&aliases(&[("a/x", "a/x")])).is_err());
+        assert!(RuleAliases::validate(&aliases(&[("a/x", "b/y"), ("b/y", "a/x")])).is_err());
+        assert!(
+            RuleAliases::validate(&aliases(&[("a/x", "b/y"), ("b/y", "c/z"), ("c/z", "a/x")]))
+                .is_err()
+        );
+        assert!(RuleAliases::validate(&aliases(&[("todo", "base/todo")])).is_err());
+    }
+
+    #[test]
+    fn configured_severities() {
+        let config: toml::Table = toml::from_str(
+            "todo = \"warn\"\nmax-line-length = [\"error\", { limit = 120 }]\nlimit = 3\nname = \"x\"\n",
+        )
+        .unwrap();
+        let configured = configured(&config);
+
+        assert_eq!(configured.len(), 2);
+        assert_eq!(configured["todo"], "warn");
+        assert_eq!(configured["max-line-length"], "error");
+    }
+}
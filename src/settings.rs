@@ -0,0 +1,801 @@
+use crate::commands::OutputSpec;
+use crate::filters::{FileScope, ReportFilters};
+use crate::interpolate::Variables;
+use crate::paths;
+use crate::rules::RuleAliases;
+use crate::state;
+use anyhow::{Context, Result, anyhow};
+use forseti_sdk::config::Config;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use std::{env, fs};
+
+/// CLI-specific keys read from `.forseti.toml` alongside the SDK configuration
+///
+/// The SDK owns the core schema; everything the CLI adds on top lives here so
+/// unknown keys never have to round-trip through the SDK types.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub ruleset: HashMap<String, RulesetSettings>,
+    pub output: OutputSettings,
+    /// Extra suppression files (globs relative to the workspace root)
+    pub suppressions: Vec<String>,
+    /// Per-rule issue budgets keyed by `ruleset/rule` or bare rule id; a
+    /// budgeted rule only fails the run once its count exceeds the budget
+    pub limits: HashMap<String, usize>,
+    /// Old `ruleset/rule` id → the id the rule has now, so config written
+    /// against the old id keeps working after a rule moves
+    pub aliases: HashMap<String, String>,
+    /// CLI additions to the SDK's `[linter]` table
+    pub linter: LinterSettings,
+    pub score: ScoreSettings,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+pub struct LinterSettings {
+    /// Run time (`"60s"`, `"2m"`, `"1m30s"`) past which the summary points at
+    /// the slowest rulesets and files; the run still succeeds
+    pub soft_time_budget: Option<String>,
+    /// Diagnostics printed to stdout before the rest are summarized as a
+    /// count; `--max-diagnostics` takes precedence
+    pub max_diagnostics: Option<usize>,
+    /// Diagnostics any one rule may put in reports; the rest are counted in
+    /// a note and still fail the run
+    pub max_diagnostics_per_rule: Option<usize>,
+    /// Diagnostics any one file may put in reports, likewise
+    pub max_diagnostics_per_file: Option<usize>,
+    /// Days after its `since` date at which a suppression file entry without
+    /// an `until` date stops hiding findings, which are then reported at
+    /// `resurface_severity`
+    pub resurface_suppressed_after_days: Option<u32>,
+    /// Highest severity resurfaced findings are reported at, `info` unless
+    /// given; findings already below it keep theirs
+    pub resurface_severity: Option<String>,
+    /// Size (`"512KB"`, `"10MB"`) above which files are skipped rather than
+    /// read and sent to rulesets; `--max-file-size` takes precedence
+    pub max_file_size: Option<String>,
+}
+
+/// Top-level keys only the CLI reads, left out of what the SDK parses
+const CLI_KEYS: &[&str] = &["output", "suppressions", "limits", "aliases", "score"];
+
+/// `[linter]` keys of [`LinterSettings`], likewise
+const CLI_LINTER_KEYS: &[&str] = &[
+    "soft_time_budget",
+    "max_diagnostics",
+    "max_diagnostics_per_rule",
+    "max_diagnostics_per_file",
+    "resurface_suppressed_after_days",
+    "resurface_severity",
+    "max_file_size",
+];
+
+/// `[ruleset.<id>]` keys of [`RulesetSettings`], likewise
+const CLI_RULESET_KEYS: &[&str] = &[
+    "image",
+    "npm_package",
+    "pip_package",
+    "bin",
+    "preload",
+    "when",
+    "files",
+    "file_patterns",
+    "download",
+];
+
+/// Severities from most to least severe
+const SEVERITIES: &[&str] = &["error", "warn", "info", "hint"];
+
+impl LinterSettings {
+    pub fn soft_time_budget(&self) -> Result<Option<Duration>> {
+        self.soft_time_budget
+            .as_deref()
+            .map(parse_duration)
+            .transpose()
+    }
+
+    pub fn max_file_size(&self) -> Result<Option<u64>> {
+        self.max_file_size.as_deref().map(parse_size).transpose()
+    }
+
+    pub fn resurface_severity(&self) -> &str {
+        self.resurface_severity.as_deref().unwrap_or("info")
+    }
+
+    fn validate(&self) -> Result<()> {
+        self.soft_time_budget()?;
+        self.max_file_size()?;
+        if !SEVERITIES.contains(&self.resurface_severity()) {
+            return Err(anyhow!(
+                "resurface_severity must be one of {}, got '{}'",
+                SEVERITIES.join(", "),
+                self.resurface_severity()
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// How the lint score is computed: 100 minus weighted diagnostics per
+/// thousand analyzed lines, floored at 0
+///
+/// ```toml
+/// [score]
+/// weights = { error = 10, warn = 3, info = 1 }
+/// min = 85
+/// ```
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScoreSettings {
+    /// Points a diagnostic costs per thousand lines, by severity, overriding
+    /// the defaults (`error` 10, `warn` 3, `info` 1, `hint` 0)
+    pub weights: HashMap<String, f64>,
+    /// Fail the run when the score is below this; `--min-score` takes
+    /// precedence
+    pub min: Option<f64>,
+}
+
+impl ScoreSettings {
+    /// Cost of one diagnostic; unknown severities cost as much as `warn`
+    pub fn weight(&self, severity: &str) -> f64 {
+        if let Some(weight) = self.weights.get(severity) {
+            return *weight;
+        }
+        match severity {
+            "error" => 10.0,
+            "info" => 1.0,
+            "hint" => 0.0,
+            _ => self.weights.get("warn").copied().unwrap_or(3.0),
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        if let Some((severity, weight)) = self.weights.iter().find(|(_, weight)| **weight < 0.0) {
+            return Err(anyhow!(
+                "Weight for '{}' must not be negative, got {}",
+                severity,
+                weight
+            ));
+        }
+        if let Some(min) = self.min
+            && !(0.0..=100.0).contains(&min)
+        {
+            return Err(anyhow!("min must be between 0 and 100, got {}", min));
+        }
+        Ok(())
+    }
+}
+
+/// Parse durations like `"90s"`, `"2m"`, `"1h"`, `"500ms"` or `"1m30s"`
+pub fn parse_duration(text: &str) -> Result<Duration> {
+    let invalid = || {
+        anyhow!(
+            "Invalid duration '{}' (expected e.g. \"60s\", \"2m\", \"1m30s\")",
+            text
+        )
+    };
+    let mut total = Duration::ZERO;
+    let mut rest = text.trim();
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .ok_or_else(invalid)?;
+        let value: u64 = rest[..digits].parse().map_err(|_| invalid())?;
+        rest = &rest[digits..];
+        let unit = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        total += match &rest[..unit] {
+            "ms" => Duration::from_millis(value),
+            "s" => Duration::from_secs(value),
+            "m" => Duration::from_secs(value * 60),
+            "h" => Duration::from_secs(value * 3600),
+            _ => return Err(invalid()),
+        };
+        rest = &rest[unit..];
+    }
+    Ok(total)
+}
+
+/// Parse sizes like `"2048"` (bytes), `"512KB"`, `"10MB"` or `"1GB"`, in
+/// multiples of 1024
+pub fn parse_size(text: &str) -> Result<u64> {
+    let invalid = || {
+        anyhow!(
+            "Invalid size '{}' (expected e.g. \"512KB\", \"10MB\", \"1GB\")",
+            text
+        )
+    };
+    let text = text.trim();
+    let digits = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let value: u64 = text[..digits].parse().map_err(|_| invalid())?;
+    let scale: u64 = match text[digits..].trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "KB" | "K" => 1 << 10,
+        "MB" | "M" => 1 << 20,
+        "GB" | "G" => 1 << 30,
+        _ => return Err(invalid()),
+    };
+    value.checked_mul(scale).ok_or_else(invalid)
+}
+
+/// Remove the keys [`Settings`] reads from a parsed config file, leaving the
+/// SDK's schema
+fn remove_cli_keys(table: &mut toml::Table) {
+    let strip = |value: Option<&mut toml::Value>, keys: &[&str]| {
+        if let Some(toml::Value::Table(table)) = value {
+            table.retain(|key, _| !keys.contains(&key));
+        }
+    };
+    table.retain(|key, _| !CLI_KEYS.contains(&key));
+    strip(table.get_mut("linter"), CLI_LINTER_KEYS);
+    if let Some(toml::Value::Table(rulesets)) = table.get_mut("ruleset") {
+        for (_, ruleset) in rulesets.iter_mut() {
+            strip(Some(ruleset), CLI_RULESET_KEYS);
+        }
+    }
+}
+
+/// Variables for a config file, resolving `${workspaceRoot}` to its directory
+fn variables(config_path: &Path) -> Variables {
+    Variables::new(&paths::canonicalize(&state::workspace_root(config_path)))
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+pub struct OutputSettings {
+    /// Never include flagged source text in reports (for rulesets whose
+    /// findings may contain credentials)
+    pub redact_snippets: bool,
+    /// Columns a tab advances to in human-readable reports, matching the
+    /// editor; unset counts every character as one column
+    pub tab_width: Option<usize>,
+    pub sarif: SarifSettings,
+    pub junit: JunitSettings,
+    /// Include `hint` diagnostics in every report, not only the JSON formats
+    /// editor integrations read
+    pub hints: bool,
+    /// Findings muted in every report
+    pub filters: Vec<OutputFilter>,
+    /// Named reports that take some rulesets' findings away from the main output
+    pub routes: BTreeMap<String, OutputRoute>,
+}
+
+impl OutputSettings {
+    /// Columns a tab advances to, for reported columns and code frames alike
+    pub fn tab_width(&self) -> usize {
+        self.tab_width.unwrap_or(1)
+    }
+}
+
+/// Sends the listed rulesets' findings to their own report
+///
+/// ```toml
+/// [output.routes.security]
+/// rulesets = ["security"]
+/// format = "sarif"
+/// file = "reports/security.sarif"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OutputRoute {
+    pub rulesets: Vec<String>,
+    /// Any `--output` value, including `exec:<command>`
+    pub format: String,
+    /// Report file relative to the workspace root; stdout when unset
+    pub file: Option<PathBuf>,
+}
+
+/// Mutes matching findings in reports without touching ruleset config;
+/// every field given must match
+///
+/// ```toml
+/// [[output.filters]]
+/// rule = "base/max-line-length"
+/// path = "generated/**"
+/// message = "exceeds \\d+ characters"
+/// ```
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputFilter {
+    /// `ruleset/rule`, `ruleset/*`, or a bare rule id
+    pub rule: Option<String>,
+    pub severity: Option<String>,
+    /// Glob over workspace-relative paths
+    pub path: Option<String>,
+    /// Regex searched for in the message
+    pub message: Option<String>,
+}
+
+/// How forseti severities appear in SARIF reports
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+pub struct SarifSettings {
+    /// SARIF `level` per severity, overriding the defaults (`error` → `error`,
+    /// `warn` → `warning`, `info` and `hint` → `note`)
+    pub levels: HashMap<String, String>,
+    /// `security-severity` score (0.0 to 10.0) per severity, used by code
+    /// scanning dashboards to rank alerts; omitted when unset
+    pub security_severity: HashMap<String, f64>,
+}
+
+impl SarifSettings {
+    const LEVELS: [&'static str; 4] = ["none", "note", "warning", "error"];
+
+    fn validate(&self) -> Result<()> {
+        for (severity, level) in &self.levels {
+            if !Self::LEVELS.contains(&level.as_str()) {
+                return Err(anyhow!(
+                    "Invalid SARIF level '{}' for severity '{}' (expected one of: {})",
+                    level,
+                    severity,
+                    Self::LEVELS.join(", ")
+                ));
+            }
+        }
+        for (severity, score) in &self.security_severity {
+            if !(0.0..=10.0).contains(score) {
+                return Err(anyhow!(
+                    "security_severity for '{}' must be between 0.0 and 10.0, got {}",
+                    severity,
+                    score
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// SARIF level for a diagnostic severity
+    pub fn level(&self, severity: &str) -> &str {
+        if let Some(level) = self.levels.get(severity) {
+            return level;
+        }
+        match severity {
+            "error" => "error",
+            "info" | "hint" => "note",
+            _ => "warning",
+        }
+    }
+}
+
+/// How forseti severities appear in JUnit reports
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+pub struct JunitSettings {
+    /// Element per severity, overriding the default of `failure` for all:
+    /// `failure`, `error`, `skipped`, or `none` to leave the finding out
+    pub elements: HashMap<String, String>,
+}
+
+impl JunitSettings {
+    const ELEMENTS: [&'static str; 4] = ["failure", "error", "skipped", "none"];
+
+    fn validate(&self) -> Result<()> {
+        for (severity, element) in &self.elements {
+            if !Self::ELEMENTS.contains(&element.as_str()) {
+                return Err(anyhow!(
+                    "Invalid JUnit element '{}' for severity '{}' (expected one of: {})",
+                    element,
+                    severity,
+                    Self::ELEMENTS.join(", ")
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// JUnit element for a diagnostic severity, `None` when it is left out
+    pub fn element(&self, severity: &str) -> Option<&str> {
+        match self
+            .elements
+            .get(severity)
+            .map_or("failure", String::as_str)
+        {
+            "none" => None,
+            element => Some(element),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+pub struct RulesetSettings {
+    /// Container image to run the ruleset from instead of a local binary
+    pub image: Option<String>,
+    /// npm package providing the ruleset, installed into an isolated `node_modules`
+    pub npm_package: Option<String>,
+    /// pip package providing the ruleset, installed into an isolated venv
+    pub pip_package: Option<String>,
+    /// Executable name inside the npm/pip package (defaults to the package name)
+    pub bin: Option<String>,
+    /// Start and initialize the ruleset while files are being collected,
+    /// hiding slow startups (JVM, language servers) behind other work
+    pub preload: bool,
+    /// Only enable the ruleset where these conditions hold
+    pub when: Option<Condition>,
+    /// Which workspace files the ruleset sees
+    pub files: RulesetFiles,
+    /// Globs over workspace-relative paths naming the kinds of files the
+    /// ruleset understands, e.g. `["*.tf", "*.tfvars"]`; replaces the
+    /// `filePatterns` the ruleset reports during initialize
+    pub file_patterns: Option<Vec<String>>,
+    /// Prebuilt binaries by platform, fetched by the scripts `forseti
+    /// gen-installer` writes
+    pub download: BTreeMap<String, Download>,
+}
+
+/// Platforms a pinned download can be given for
+pub const DOWNLOAD_PLATFORMS: &[&str] = &[
+    "linux-x86_64",
+    "linux-aarch64",
+    "macos-x86_64",
+    "macos-aarch64",
+    "windows-x86_64",
+    "windows-aarch64",
+];
+
+/// A prebuilt ruleset binary pinned by its digest
+///
+/// ```toml
+/// [ruleset.base.download.linux-x86_64]
+/// url = "https://github.com/acme/base/releases/download/v1.4.0/forseti_ruleset_base-linux-x86_64"
+/// sha256 = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Download {
+    pub url: String,
+    /// Hex SHA-256 of the binary
+    pub sha256: String,
+}
+
+impl Download {
+    fn validate(&self) -> Result<()> {
+        if !self.url.starts_with("https://") && !self.url.starts_with("http://") {
+            return Err(anyhow!("url must be an http(s) URL, got '{}'", self.url));
+        }
+        if self.sha256.len() != 64 || !self.sha256.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(anyhow!(
+                "sha256 must be 64 hex digits, got '{}'",
+                self.sha256
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Scopes a ruleset to some files without excluding them from the others
+///
+/// ```toml
+/// [ruleset.base.files]
+/// exclude = ["**/fixtures/**"]
+/// ```
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RulesetFiles {
+    /// Globs over workspace-relative paths; when given, only matching files
+    pub include: Vec<String>,
+    /// Globs over workspace-relative paths never sent to the ruleset
+    pub exclude: Vec<String>,
+}
+
+/// Where a ruleset runs; every condition given must hold
+///
+/// ```toml
+/// [ruleset.slow-security.when]
+/// ci = true
+/// os = "linux"
+/// ```
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Condition {
+    /// Running with `--ci` or with the `CI` environment variable set
+    pub ci: Option<bool>,
+    /// Operating system: `linux`, `macos`, `windows`, ...
+    pub os: Option<String>,
+    /// CPU architecture: `x86_64`, `aarch64`, ...
+    pub arch: Option<String>,
+    /// Environment variables that must be set and non-empty
+    pub env: Vec<String>,
+}
+
+impl Condition {
+    pub fn holds(&self, ci: bool) -> bool {
+        let set = |name: &str| env::var_os(name).is_some_and(|value| !value.is_empty());
+        let in_ci = ci || env::var("CI").is_ok_and(|value| !value.is_empty() && value != "false");
+        self.ci.is_none_or(|wanted| wanted == in_ci)
+            && self.os.as_ref().is_none_or(|os| os == env::consts::OS)
+            && self
+                .arch
+                .as_ref()
+                .is_none_or(|arch| arch == env::consts::ARCH)
+            && self.env.iter().all(|name| set(name))
+    }
+}
+
+impl Settings {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+        let mut table: toml::Table = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse config file: {}", path.display()))?;
+        variables(path)
+            .expand_table(&mut table)
+            .with_context(|| format!("Failed to interpolate config file: {}", path.display()))?;
+        let settings: Self = table
+            .try_into()
+            .with_context(|| format!("Failed to parse config file: {}", path.display()))?;
+        settings
+            .output
+            .sarif
+            .validate()
+            .with_context(|| format!("Invalid [output.sarif] in {}", path.display()))?;
+        settings
+            .output
+            .junit
+            .validate()
+            .with_context(|| format!("Invalid [output.junit] in {}", path.display()))?;
+        for (name, route) in &settings.output.routes {
+            route
+                .format
+                .parse::<OutputSpec>()
+                .map_err(|e| anyhow!(e))
+                .with_context(|| {
+                    format!("Invalid [output.routes.{}] in {}", name, path.display())
+                })?;
+        }
+        ReportFilters::new(&settings.output.filters)
+            .with_context(|| format!("Invalid [[output.filters]] in {}", path.display()))?;
+        RuleAliases::validate(&settings.aliases)
+            .with_context(|| format!("Invalid [aliases] in {}", path.display()))?;
+        settings
+            .linter
+            .validate()
+            .with_context(|| format!("Invalid [linter] in {}", path.display()))?;
+        settings
+            .score
+            .validate()
+            .with_context(|| format!("Invalid [score] in {}", path.display()))?;
+        for (id, ruleset) in &settings.ruleset {
+            FileScope::new(&ruleset.files, ruleset.file_patterns.as_deref()).with_context(
+                || {
+                    format!(
+                        "Invalid [ruleset.{}] files or file_patterns in {}",
+                        id,
+                        path.display()
+                    )
+                },
+            )?;
+            for (platform, download) in &ruleset.download {
+                if !DOWNLOAD_PLATFORMS.contains(&platform.as_str()) {
+                    return Err(anyhow!(
+                        "Unknown platform '{}' in [ruleset.{}.download] in {} (expected one of: {})",
+                        platform,
+                        id,
+                        path.display(),
+                        DOWNLOAD_PLATFORMS.join(", ")
+                    ));
+                }
+                download.validate().with_context(|| {
+                    format!(
+                        "Invalid [ruleset.{}.download.{}] in {}",
+                        id,
+                        platform,
+                        path.display()
+                    )
+                })?;
+            }
+        }
+        Ok(settings)
+    }
+
+    /// Load the SDK configuration with `${...}` variables expanded and
+    /// rulesets whose `when` conditions don't hold disabled
+    ///
+    /// The SDK rejects keys it doesn't know, so the CLI's own are removed
+    /// before it parses the file.
+    pub fn load_config(&self, path: &Path, ci: bool) -> Result<Config> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+        let mut table: toml::Table = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse config file: {}", path.display()))?;
+        remove_cli_keys(&mut table);
+        let mut config = Config::load_from_str(&toml::to_string(&table)?)
+            .context("Failed to load configuration")?;
+        variables(path)
+            .expand_config(&mut config)
+            .with_context(|| format!("Failed to interpolate config file: {}", path.display()))?;
+        for (id, ruleset) in config.ruleset.iter_mut() {
+            if let Some(when) = &self.ruleset(id).when
+                && !when.holds(ci)
+            {
+                ruleset.enabled = false;
+            }
+        }
+        Ok(config)
+    }
+
+    /// CLI settings for a ruleset (defaults when the ruleset has none)
+    pub fn ruleset(&self, id: &str) -> RulesetSettings {
+        self.ruleset.get(id).cloned().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every key the README documents for `.forseti.toml`
+    const EVERY_KEY: &str = r#"
+suppressions = [".forseti/suppressions/*.toml"]
+
+[linter]
+log_level = "info"
+output_format = "json"
+parallelism = 4
+fail_on_error = true
+soft_time_budget = "1m30s"
+max_file_size = "10MB"
+max_diagnostics = 200
+max_diagnostics_per_rule = 500
+max_diagnostics_per_file = 100
+resurface_suppressed_after_days = 180
+resurface_severity = "info"
+
+[limits]
+"base/max-line-length" = 50
+
+[score]
+weights = { error = 20, warn = 2 }
+min = 85
+
+[aliases]
+"legacy/max-line-length" = "style/line-length"
+
+[output]
+redact_snippets = true
+tab_width = 4
+hints = true
+
+[[output.filters]]
+rule = "base/max-line-length"
+severity = "info"
+path = "generated/**"
+message = "^consider "
+
+[output.sarif]
+levels = { warn = "note" }
+security_severity = { error = 8.0, warn = 5.0 }
+
+[output.junit]
+elements = { error = "error", info = "skipped", hint = "none" }
+
+[output.routes.security]
+rulesets = ["security"]
+format = "sarif"
+file = "reports/security.sarif"
+
+[ruleset.base]
+enabled = true
+path = "${workspaceRoot}/tools/forseti-ruleset-base"
+file_patterns = ["*.tf", "*.tfvars"]
+preload = true
+
+[ruleset.base.config]
+"max-line-length" = ["warn", { "limit" = 120 }]
+
+[ruleset.base.when]
+ci = true
+os = "linux"
+env = ["HOME"]
+
+[ruleset.base.files]
+include = ["infra/**"]
+exclude = ["**/fixtures/**"]
+
+[ruleset.base.download.linux-x86_64]
+url = "https://github.com/acme/base/releases/download/v1.4.0/forseti_ruleset_base-linux-x86_64"
+sha256 = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08"
+
+[ruleset.python]
+image = "ghcr.io/org/forseti-ruleset-py:1.2"
+
+[ruleset.eslint-bridge]
+npm_package = "@org/forseti-ruleset-ts@1.2"
+
+[ruleset.pylint]
+pip_package = "forseti-ruleset-py==0.4"
+bin = "forseti-py"
+
+[ruleset.remote]
+git = "https://github.com/acme/forseti-ruleset-remote"
+"#;
+
+    fn write_config(name: &str, content: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("forseti-settings-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(".forseti.toml");
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("60s").unwrap(), Duration::from_secs(60));
+        assert_eq!(parse_duration("2m").unwrap(), Duration::from_secs(120));
+        assert_eq!(parse_duration("1h").unwrap(), Duration::from_secs(3600));
+        assert_eq!(parse_duration("500ms").unwrap(), Duration::from_millis(500));
+        assert_eq!(parse_duration(" 1m30s ").unwrap(), Duration::from_secs(90));
+        for invalid in ["", "60", "s", "1d", "1.5s", "-1s", "1m 30s"] {
+            assert!(parse_duration(invalid).is_err(), "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn parses_sizes() {
+        assert_eq!(parse_size("2048").unwrap(), 2048);
+        assert_eq!(parse_size("2048B").unwrap(), 2048);
+        assert_eq!(parse_size("512KB").unwrap(), 512 << 10);
+        assert_eq!(parse_size("10 MB").unwrap(), 10 << 20);
+        assert_eq!(parse_size("1g").unwrap(), 1 << 30);
+        for invalid in ["", "MB", "1TB", "1.5MB", "-1", "99999999999999999999GB"] {
+            assert!(parse_size(invalid).is_err(), "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn loads_every_documented_key() {
+        let path = write_config("every-key", EVERY_KEY);
+        let settings = Settings::load(&path).unwrap();
+        let config = settings.load_config(&path, true).unwrap();
+
+        assert_eq!(config.linter.parallelism, 4);
+        assert_eq!(config.ruleset.len(), 5);
+        let base = &config.ruleset["base"];
+        assert!(base.enabled);
+        assert!(
+            base.path
+                .as_deref()
+                .unwrap()
+                .ends_with("tools/forseti-ruleset-base")
+        );
+        assert!(base.config.contains_key("max-line-length"));
+        assert_eq!(settings.linter.max_file_size().unwrap(), Some(10 << 20));
+        assert_eq!(
+            settings.ruleset("pylint").bin.as_deref(),
+            Some("forseti-py")
+        );
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn unmet_conditions_disable_rulesets() {
+        let path = write_config(
+            "when",
+            "[ruleset.base]\n[ruleset.base.when]\nenv = [\"FORSETI_TEST_UNSET_VARIABLE\"]\n",
+        );
+        let settings = Settings::load(&path).unwrap();
+        let config = settings.load_config(&path, false).unwrap();
+
+        assert!(!config.ruleset["base"].enabled);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn sdk_still_rejects_unknown_keys() {
+        let path = write_config("unknown", "[ruleset.base]\ncolour = \"blue\"\n");
+        let settings = Settings::load(&path).unwrap();
+
+        assert!(settings.load_config(&path, false).is_err());
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,134 @@
+use anyhow::{Context, Result};
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+/// Per-project state directory, created next to `.forseti.toml`
+pub const STATE_DIR: &str = ".forseti";
+
+/// Written into a new state directory so it stays out of version control
+pub const GITIGNORE: &str = "# Created by forseti\n*\n!.gitignore\n";
+
+/// Subdirectory holding cached lint results
+const CACHE_DIR: &str = "cache";
+
+/// Subdirectory the `forseti gen-installer` scripts download rulesets into
+pub const VENDOR_DIR: &str = "vendor";
+
+/// Subdirectory the artifacts of the last lint run are written to
+const ARTIFACTS_DIR: &str = "artifacts";
+
+/// Everything forseti keeps about a project between runs, under
+/// `<workspace>/.forseti/`:
+///
+/// ```text
+/// .forseti/
+/// ├── .gitignore      ignores everything but itself
+/// ├── lock            held while a process changes anything in here
+/// ├── artifacts/      files rulesets produced in the last run, by ruleset
+/// ├── cache/
+/// │   ├── results/          lint results keyed by content, config and ruleset
+/// │   └── incremental.json  files and results of the last incremental run
+/// ├── vendor/         pinned rulesets fetched by the gen-installer scripts
+/// └── daemon.sock     socket of a running daemon
+/// ```
+///
+/// Entries are created on first use.
+#[derive(Debug, Clone)]
+pub struct ProjectState {
+    dir: PathBuf,
+}
+
+/// Exclusive hold on a project's state directory, released on drop
+pub struct StateLock {
+    _file: File,
+}
+
+impl ProjectState {
+    pub fn new(workspace_root: &Path) -> Self {
+        Self {
+            dir: workspace_root.join(STATE_DIR),
+        }
+    }
+
+    /// Create the directory and its `.gitignore` if needed and take the
+    /// lock, waiting for any other forseti process holding it
+    pub fn lock(&self) -> Result<StateLock> {
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+
+        let lock_path = self.dir.join("lock");
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .with_context(|| format!("Failed to open {}", lock_path.display()))?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                eprintln!(
+                    "Waiting for another forseti process to release {}",
+                    lock_path.display()
+                );
+                file.lock()
+                    .with_context(|| format!("Failed to lock {}", lock_path.display()))?;
+            }
+            Err(TryLockError::Error(e)) => {
+                return Err(e).with_context(|| format!("Failed to lock {}", lock_path.display()));
+            }
+        }
+
+        let gitignore = self.dir.join(".gitignore");
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&gitignore)
+        {
+            Ok(mut file) => file
+                .write_all(GITIGNORE.as_bytes())
+                .with_context(|| format!("Failed to write {}", gitignore.display()))?,
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {}
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to create {}", gitignore.display()));
+            }
+        }
+
+        Ok(StateLock { _file: file })
+    }
+
+    /// Directory of the lint result cache
+    pub fn results_cache(&self) -> PathBuf {
+        self.dir.join(CACHE_DIR).join("results")
+    }
+
+    /// Files and results of the last `forseti lint --incremental` run
+    pub fn incremental_state(&self) -> PathBuf {
+        self.dir.join(CACHE_DIR).join("incremental.json")
+    }
+
+    /// Directory of rulesets fetched by the `forseti gen-installer` scripts,
+    /// found before the user cache
+    pub fn vendor_dir(&self) -> PathBuf {
+        self.dir.join(VENDOR_DIR)
+    }
+
+    /// Where `forseti lint` writes ruleset artifacts unless given
+    /// `--artifacts-dir`; emptied at the start of every run that writes here
+    pub fn artifacts_dir(&self) -> PathBuf {
+        self.dir.join(ARTIFACTS_DIR)
+    }
+
+    /// Socket a running `forseti daemon` listens on
+    pub fn daemon_socket(&self) -> PathBuf {
+        self.dir.join("daemon.sock")
+    }
+}
+
+/// Workspace a config file belongs to: the directory containing it
+pub fn workspace_root(config_path: &Path) -> PathBuf {
+    match config_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    }
+}
@@ -0,0 +1,138 @@
+use crate::metadata::RunMetadata;
+use anyhow::{Context, Result, anyhow};
+use rusqlite::{Connection, params};
+use std::path::Path;
+
+/// Version of the schema below, kept in SQLite's `user_version`
+const SCHEMA_VERSION: i64 = 1;
+
+/// Tables and indices of a results store; every statement is idempotent
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    run_id TEXT NOT NULL UNIQUE,
+    started_at TEXT NOT NULL,
+    git_commit TEXT,
+    cli_version TEXT NOT NULL,
+    config_hash TEXT NOT NULL,
+    files INTEGER NOT NULL,
+    diagnostics INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS runs_by_commit ON runs (git_commit);
+CREATE TABLE IF NOT EXISTS diagnostics (
+    run INTEGER NOT NULL REFERENCES runs (id) ON DELETE CASCADE,
+    path TEXT NOT NULL,
+    ruleset TEXT NOT NULL,
+    rule TEXT NOT NULL,
+    severity TEXT NOT NULL,
+    start_line INTEGER NOT NULL,
+    start_column INTEGER NOT NULL,
+    end_line INTEGER NOT NULL,
+    end_column INTEGER NOT NULL,
+    message TEXT NOT NULL,
+    fingerprint TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS diagnostics_by_run ON diagnostics (run);
+CREATE INDEX IF NOT EXISTS diagnostics_by_path ON diagnostics (path, run);
+CREATE INDEX IF NOT EXISTS diagnostics_by_rule ON diagnostics (rule, run);
+CREATE INDEX IF NOT EXISTS diagnostics_by_fingerprint ON diagnostics (fingerprint, run);
+";
+
+/// One diagnostic as stored, with one-based lines and columns
+pub struct StoredDiagnostic<'a> {
+    /// Relative to the workspace root, with `/` separators
+    pub path: &'a str,
+    pub ruleset: &'a str,
+    /// Qualified as `ruleset/rule`
+    pub rule: &'a str,
+    pub severity: &'a str,
+    pub start: (u32, u32),
+    pub end: (u32, u32),
+    pub message: &'a str,
+    pub fingerprint: &'a str,
+}
+
+/// SQLite database accumulating the diagnostics of every run given
+/// `--store`, indexed by file, rule, fingerprint and commit so questions
+/// about a large repository's history are queries rather than scans of
+/// JSON reports
+pub struct ResultStore {
+    conn: Connection,
+}
+
+impl ResultStore {
+    /// Open the store at `path`, creating it and its tables if needed
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open results store: {}", path.display()))?;
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version > SCHEMA_VERSION {
+            return Err(anyhow!(
+                "Results store {} was written by a newer forseti (schema {}, this one knows {})",
+                path.display(),
+                version,
+                SCHEMA_VERSION
+            ));
+        }
+        conn.execute_batch(SCHEMA)
+            .and_then(|_| conn.pragma_update(None, "user_version", SCHEMA_VERSION))
+            .and_then(|_| conn.pragma_update(None, "foreign_keys", true))
+            .with_context(|| format!("Failed to set up results store: {}", path.display()))?;
+        Ok(Self { conn })
+    }
+
+    /// Add a run and its diagnostics in one transaction, so readers never
+    /// see half a run
+    pub fn record(
+        &mut self,
+        metadata: &RunMetadata,
+        files: usize,
+        diagnostics: &[StoredDiagnostic],
+    ) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        // A --deterministic rerun has the same id and replaces the earlier record
+        tx.execute(
+            "DELETE FROM runs WHERE run_id = ?1",
+            params![metadata.run_id],
+        )?;
+        tx.execute(
+            "INSERT INTO runs (run_id, started_at, git_commit, cli_version, config_hash, files, \
+             diagnostics) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                metadata.run_id,
+                metadata.timestamp,
+                metadata.git_commit,
+                metadata.cli_version,
+                metadata.config_hash,
+                files as i64,
+                diagnostics.len() as i64,
+            ],
+        )
+        .with_context(|| format!("Failed to record run {}", metadata.run_id))?;
+        let run = tx.last_insert_rowid();
+        {
+            let mut insert = tx.prepare(
+                "INSERT INTO diagnostics (run, path, ruleset, rule, severity, start_line, \
+                 start_column, end_line, end_column, message, fingerprint) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            )?;
+            for diagnostic in diagnostics {
+                insert.execute(params![
+                    run,
+                    diagnostic.path,
+                    diagnostic.ruleset,
+                    diagnostic.rule,
+                    diagnostic.severity,
+                    diagnostic.start.0,
+                    diagnostic.start.1,
+                    diagnostic.end.0,
+                    diagnostic.end.1,
+                    diagnostic.message,
+                    diagnostic.fingerprint,
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
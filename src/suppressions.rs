@@ -0,0 +1,549 @@
+use anyhow::{Context, Result, anyhow};
+use chrono::NaiveDate;
+use globset::{Glob, GlobMatcher};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Suppression file looked up in the workspace root
+pub const DEFAULT_FILE: &str = ".forseti-suppressions.toml";
+
+/// Inline directive suppressing matching diagnostics on the following line
+pub const NEXT_LINE_DIRECTIVE: &str = "forseti-ignore-next-line";
+/// Inline directive suppressing matching diagnostics on its own line
+pub const SAME_LINE_DIRECTIVE: &str = "forseti-ignore-line";
+
+#[derive(Debug, Default, Deserialize)]
+struct SuppressionFile {
+    #[serde(default)]
+    suppress: Vec<RawEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawEntry {
+    rule: String,
+    path: Option<String>,
+    until: Option<String>,
+    since: Option<String>,
+    #[allow(dead_code)]
+    reason: Option<String>,
+}
+
+/// A suppression from the suppression file
+#[derive(Debug)]
+struct FileEntry {
+    rule: String,
+    path: Option<GlobMatcher>,
+    until: Option<NaiveDate>,
+    /// When the suppressed findings were accepted
+    since: Option<NaiveDate>,
+    /// `file#index` of the entry, for reporting
+    origin: String,
+}
+
+/// A suppression comment found in a linted file
+#[derive(Debug, Clone)]
+pub struct InlineSuppression {
+    /// Zero-based line the directive applies to
+    pub target_line: usize,
+    /// Zero-based line the directive is written on
+    pub directive_line: usize,
+    pub rules: Vec<String>,
+    pub until: Option<NaiveDate>,
+}
+
+/// An expired suppression that no longer applies
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Expired {
+    pub origin: String,
+    pub rule: String,
+    pub until: NaiveDate,
+}
+
+/// A suppression without an `until` date whose `since` date is further back
+/// than the resurfacing age, so its findings are reported again
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Aged {
+    pub origin: String,
+    pub rule: String,
+    pub since: NaiveDate,
+}
+
+/// Outcome of checking a diagnostic against suppressions
+#[derive(Debug)]
+pub enum Verdict {
+    NotSuppressed,
+    Suppressed,
+    /// A suppression matched but its `until` date has passed
+    Expired(Expired),
+    /// Only suppressions older than the resurfacing age matched
+    Aged(Aged),
+}
+
+/// Suppressions from the suppression file, checked together with inline ones
+#[derive(Debug)]
+pub struct Suppressions {
+    entries: Vec<FileEntry>,
+    today: NaiveDate,
+    /// Entries accepted this many days ago or earlier stop hiding findings
+    resurface_after: Option<u32>,
+}
+
+impl Suppressions {
+    /// Load `.forseti-suppressions.toml` from the workspace root when present,
+    /// merged with every file matching the configured `suppressions` globs
+    pub fn load(workspace_root: &Path, patterns: &[String]) -> Result<Self> {
+        let mut suppressions = Suppressions {
+            entries: Vec::new(),
+            today: chrono::Local::now().date_naive(),
+            resurface_after: None,
+        };
+
+        let mut files = Vec::new();
+        let default_file = workspace_root.join(DEFAULT_FILE);
+        if default_file.exists() {
+            files.push(default_file);
+        }
+        for pattern in patterns {
+            for file in expand_glob(workspace_root, pattern)? {
+                if !files.contains(&file) {
+                    files.push(file);
+                }
+            }
+        }
+
+        for file in &files {
+            suppressions.add_file(file)?;
+        }
+        Ok(suppressions)
+    }
+
+    fn add_file(&mut self, path: &PathBuf) -> Result<()> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read suppression file: {}", path.display()))?;
+        let file: SuppressionFile = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse suppression file: {}", path.display()))?;
+
+        for (index, raw) in file.suppress.into_iter().enumerate() {
+            let origin = format!("{}#{}", path.display(), index + 1);
+            let matcher = raw
+                .path
+                .as_deref()
+                .map(|glob| {
+                    Glob::new(glob)
+                        .map(|g| g.compile_matcher())
+                        .with_context(|| format!("Invalid path glob in {}", origin))
+                })
+                .transpose()?;
+            let until = raw
+                .until
+                .as_deref()
+                .map(|date| parse_date(date).with_context(|| format!("Invalid date in {}", origin)))
+                .transpose()?;
+            let since = raw
+                .since
+                .as_deref()
+                .map(|date| parse_date(date).with_context(|| format!("Invalid date in {}", origin)))
+                .transpose()?;
+            self.entries.push(FileEntry {
+                rule: raw.rule,
+                path: matcher,
+                until,
+                since,
+                origin,
+            });
+        }
+        Ok(())
+    }
+
+    /// Report the findings of open-ended file entries again once their
+    /// `since` date is `days` days or more in the past
+    pub fn resurface_after(mut self, days: Option<u32>) -> Self {
+        self.resurface_after = days;
+        self
+    }
+
+    /// Entries in the suppression file whose date has passed
+    pub fn expired_entries(&self) -> Vec<Expired> {
+        self.entries
+            .iter()
+            .filter_map(|entry| {
+                let until = entry.until?;
+                self.is_expired(until).then(|| Expired {
+                    origin: entry.origin.clone(),
+                    rule: entry.rule.clone(),
+                    until,
+                })
+            })
+            .collect()
+    }
+
+    /// Entries naming a bare rule id, which match that rule in every ruleset
+    pub fn bare_entries(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.rule != "*" && !entry.rule.contains('/'))
+            .map(|entry| (entry.origin.as_str(), entry.rule.as_str()))
+    }
+
+    /// Check a diagnostic against the file entries and a file's inline suppressions
+    ///
+    /// `names` are the diagnostic's `ruleset/rule` id followed by any ids
+    /// aliased to it.
+    pub fn check(
+        &self,
+        names: &[String],
+        relative_path: &str,
+        line: usize,
+        inline: &[InlineSuppression],
+    ) -> Verdict {
+        let mut expired = None;
+        let mut aged = None;
+
+        for suppression in inline
+            .iter()
+            .filter(|s| s.target_line == line)
+            .filter(|s| s.rules.iter().any(|r| matches_any(r, names)))
+        {
+            match suppression.until {
+                Some(until) if self.is_expired(until) => {
+                    expired = Some(Expired {
+                        origin: format!("{}:{}", relative_path, suppression.directive_line + 1),
+                        rule: names[0].clone(),
+                        until,
+                    });
+                }
+                _ => return Verdict::Suppressed,
+            }
+        }
+
+        for entry in self.entries.iter().filter(|e| {
+            matches_any(&e.rule, names)
+                && e.path
+                    .as_ref()
+                    .is_none_or(|glob| glob.is_match(relative_path))
+        }) {
+            match entry.until {
+                Some(until) if self.is_expired(until) => {
+                    expired = Some(Expired {
+                        origin: entry.origin.clone(),
+                        rule: entry.rule.clone(),
+                        until,
+                    });
+                }
+                Some(_) => return Verdict::Suppressed,
+                None => match entry.since.filter(|since| self.is_aged(*since)) {
+                    Some(since) => {
+                        aged = Some(Aged {
+                            origin: entry.origin.clone(),
+                            rule: entry.rule.clone(),
+                            since,
+                        });
+                    }
+                    None => return Verdict::Suppressed,
+                },
+            }
+        }
+
+        match (expired, aged) {
+            (Some(expired), _) => Verdict::Expired(expired),
+            (None, Some(aged)) => Verdict::Aged(aged),
+            (None, None) => Verdict::NotSuppressed,
+        }
+    }
+
+    fn is_expired(&self, until: NaiveDate) -> bool {
+        // A suppression is valid through the whole of its `until` day
+        self.today > until
+    }
+
+    fn is_aged(&self, since: NaiveDate) -> bool {
+        self.resurface_after.is_some_and(|days| {
+            since
+                .checked_add_days(chrono::Days::new(days.into()))
+                .is_some_and(|due| self.today >= due)
+        })
+    }
+}
+
+/// Files under `root` matching a glob relative to it, in sorted order
+fn expand_glob(root: &Path, pattern: &str) -> Result<Vec<PathBuf>> {
+    const GLOB_CHARS: &[char] = &['*', '?', '[', '{'];
+
+    if !pattern.contains(GLOB_CHARS) {
+        let path = root.join(pattern);
+        return if path.is_file() {
+            Ok(vec![path])
+        } else {
+            Err(anyhow!("Suppression file not found: {}", path.display()))
+        };
+    }
+
+    let matcher = Glob::new(pattern)
+        .with_context(|| format!("Invalid suppressions glob: {}", pattern))?
+        .compile_matcher();
+
+    // Only walk below the literal part of the pattern
+    let base: PathBuf = pattern
+        .split('/')
+        .take_while(|part| !part.contains(GLOB_CHARS))
+        .collect();
+    let walk_root = root.join(&base);
+    if !walk_root.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut files: Vec<PathBuf> = walkdir::WalkDir::new(&walk_root)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter(|e| {
+            e.path().strip_prefix(root).is_ok_and(|relative| {
+                let relative = relative
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                matcher.is_match(relative)
+            })
+        })
+        .map(|e| e.into_path())
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Parse the inline suppression directives in a file
+///
+/// Directives live in any comment syntax, e.g.
+/// `// forseti-ignore-next-line max-line-length until=2025-12-31 -- generated table`
+pub fn parse_inline(content: &str) -> Vec<InlineSuppression> {
+    let mut suppressions = Vec::new();
+
+    for (index, line) in content.lines().enumerate() {
+        let (directive, target_line) = if let Some(pos) = line.find(NEXT_LINE_DIRECTIVE) {
+            (&line[pos + NEXT_LINE_DIRECTIVE.len()..], index + 1)
+        } else if let Some(pos) = line.find(SAME_LINE_DIRECTIVE) {
+            (&line[pos + SAME_LINE_DIRECTIVE.len()..], index)
+        } else {
+            continue;
+        };
+
+        // Everything after `--` is a free-form justification
+        let directive = directive.split("--").next().unwrap_or_default();
+        let mut rules = Vec::new();
+        let mut until = None;
+        for token in directive
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|t| !t.is_empty())
+        {
+            if let Some(date) = token.strip_prefix("until=") {
+                until = parse_date(date).ok();
+            } else if token == "*"
+                || (token.chars().any(char::is_alphanumeric)
+                    && token
+                        .chars()
+                        .all(|c| c.is_alphanumeric() || "-_/.@*".contains(c)))
+            {
+                rules.push(token.to_string());
+            }
+        }
+        if rules.is_empty() {
+            rules.push("*".to_string());
+        }
+
+        suppressions.push(InlineSuppression {
+            target_line,
+            directive_line: index,
+            rules,
+            until,
+        });
+    }
+
+    suppressions
+}
+
+fn matches_any(selector: &str, names: &[String]) -> bool {
+    names.iter().any(|name| rule_matches(selector, name))
+}
+
+/// Whether a suppression's rule selector covers a `ruleset/rule` id
+///
+/// Selectors are `*`, a bare rule id, `ruleset/*`, or `ruleset/rule`.
+pub fn rule_matches(selector: &str, id: &str) -> bool {
+    if selector == "*" || selector == id {
+        return true;
+    }
+    let (ruleset_id, rule_id) = id.split_once('/').unwrap_or(("", id));
+    match selector.split_once('/') {
+        Some((ruleset, "*")) => ruleset == ruleset_id,
+        Some(_) => false,
+        None => selector == rule_id,
+    }
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| anyhow!("expected a YYYY-MM-DD date, got '{}'", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(text: &str) -> NaiveDate {
+        parse_date(text).unwrap()
+    }
+
+    fn suppressions(today: &str, entries: Vec<FileEntry>) -> Suppressions {
+        Suppressions {
+            entries,
+            today: date(today),
+            resurface_after: None,
+        }
+    }
+
+    fn entry(rule: &str, path: Option<&str>, until: Option<&str>) -> FileEntry {
+        FileEntry {
+            rule: rule.to_string(),
+            path: path.map(|glob| Glob::new(glob).unwrap().compile_matcher()),
+            until: until.map(date),
+            since: None,
+            origin: "suppressions.toml#1".to_string(),
+        }
+    }
+
+    fn names(id: &str) -> Vec<String> {
+        vec![id.to_string()]
+    }
+
+    #[test]
+    fn parses_next_line_and_same_line_directives() {
+        let content = "\
+// forseti-ignore-next-line base/max-line-length, todo
+let x = 1;
+let y = 2; // forseti-ignore-line
+";
+        let inline = parse_inline(content);
+
+        assert_eq!(inline.len(), 2);
+        assert_eq!(inline[0].directive_line, 0);
+        assert_eq!(inline[0].target_line, 1);
+        assert_eq!(inline[0].rules, ["base/max-line-length", "todo"]);
+        assert_eq!(inline[1].directive_line, 2);
+        assert_eq!(inline[1].target_line, 2);
+        assert_eq!(inline[1].rules, ["*"]);
+    }
+
+    #[test]
+    fn justification_and_until_are_not_rules() {
+        let inline = parse_inline(
+            "# forseti-ignore-next-line max-line-length until=2025-12-31 -- generated table\n",
+        );
+
+        assert_eq!(inline[0].rules, ["max-line-length"]);
+        assert_eq!(inline[0].until, Some(date("2025-12-31")));
+    }
+
+    #[test]
+    fn inline_suppression_matches_its_target_line_only() {
+        let inline = parse_inline("// forseti-ignore-next-line todo\nTODO\nTODO\n");
+        let suppressions = suppressions("2025-01-01", Vec::new());
+
+        assert!(matches!(
+            suppressions.check(&names("base/todo"), "a.rs", 1, &inline),
+            Verdict::Suppressed
+        ));
+        assert!(matches!(
+            suppressions.check(&names("base/todo"), "a.rs", 2, &inline),
+            Verdict::NotSuppressed
+        ));
+        assert!(matches!(
+            suppressions.check(&names("base/fixme"), "a.rs", 1, &inline),
+            Verdict::NotSuppressed
+        ));
+    }
+
+    #[test]
+    fn inline_until_holds_through_its_day() {
+        let inline = parse_inline("// forseti-ignore-next-line todo until=2025-06-30\nTODO\n");
+
+        let on_the_day = suppressions("2025-06-30", Vec::new());
+        assert!(matches!(
+            on_the_day.check(&names("base/todo"), "a.rs", 1, &inline),
+            Verdict::Suppressed
+        ));
+
+        let day_after = suppressions("2025-07-01", Vec::new());
+        match day_after.check(&names("base/todo"), "a.rs", 1, &inline) {
+            Verdict::Expired(expired) => {
+                assert_eq!(expired.origin, "a.rs:1");
+                assert_eq!(expired.until, date("2025-06-30"));
+            }
+            verdict => panic!("expected an expired suppression, got {:?}", verdict),
+        }
+    }
+
+    #[test]
+    fn file_entries_are_scoped_by_path_glob() {
+        let suppressions = suppressions(
+            "2025-01-01",
+            vec![entry("base/max-line-length", Some("src/legacy/**"), None)],
+        );
+        let id = names("base/max-line-length");
+
+        assert!(matches!(
+            suppressions.check(&id, "src/legacy/old.rs", 0, &[]),
+            Verdict::Suppressed
+        ));
+        assert!(matches!(
+            suppressions.check(&id, "src/new.rs", 0, &[]),
+            Verdict::NotSuppressed
+        ));
+    }
+
+    #[test]
+    fn expired_file_entries_are_reported() {
+        let suppressions = suppressions(
+            "2026-01-01",
+            vec![
+                entry("base/todo", None, Some("2025-12-31")),
+                entry("base/fixme", None, Some("2026-01-01")),
+            ],
+        );
+
+        assert!(matches!(
+            suppressions.check(&names("base/todo"), "a.rs", 0, &[]),
+            Verdict::Expired(_)
+        ));
+        assert!(matches!(
+            suppressions.check(&names("base/fixme"), "a.rs", 0, &[]),
+            Verdict::Suppressed
+        ));
+        let expired = suppressions.expired_entries();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].rule, "base/todo");
+    }
+
+    #[test]
+    fn open_ended_entries_resurface_once_aged() {
+        let mut aged = entry("base/todo", None, None);
+        aged.since = Some(date("2025-01-01"));
+        let suppressions = suppressions("2025-07-01", vec![aged]).resurface_after(Some(180));
+
+        assert!(matches!(
+            suppressions.check(&names("base/todo"), "a.rs", 0, &[]),
+            Verdict::Aged(_)
+        ));
+    }
+
+    #[test]
+    fn rule_selectors() {
+        assert!(rule_matches("*", "base/todo"));
+        assert!(rule_matches("base/todo", "base/todo"));
+        assert!(rule_matches("todo", "base/todo"));
+        assert!(rule_matches("base/*", "base/todo"));
+        assert!(!rule_matches("other/*", "base/todo"));
+        assert!(!rule_matches("other/todo", "base/todo"));
+    }
+}